@@ -0,0 +1,803 @@
+//! Compute-unit benchmarks. Runs every instruction against the SBF build of
+//! the program, with collections and multisigs of several sizes, prints what
+//! each consumed and fails when one goes over its budget or has none.
+//!
+//! Build the program first (`anchor build`), then run
+//! `cargo bench --bench compute_units`. Set `UPDATE_BUDGETS=1` to record the
+//! current run as the new budgets, and `BASELINE_SBF_OUT_DIR` to the build of
+//! another commit to print its cost next to this one's.
+
+#[path = "../tests/common/mod.rs"]
+mod common;
+
+use anchor_lang::{
+    prelude::Pubkey,
+    solana_program::{hash::hashv, instruction::Instruction},
+};
+use common::*;
+use rotary_savings::{client, dot::program::*};
+use solana_program_test::ProgramTest;
+use solana_sdk::signature::{Keypair, Signer};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    process,
+};
+
+/// Each size is used for both the member count and the signer count, with
+/// every signer needed to pass.
+const SIZES: [usize; 3] = [3, 10, 25];
+
+/// How far, in percent, an instruction may go past its budget before the run
+/// fails.
+const TOLERANCE_PCT: u64 = 2;
+
+/// The default per-instruction compute limit, enforced on top of the budgets.
+const COMPUTE_LIMIT: u64 = 200_000;
+
+const BUDGETS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/benches/compute_units.budgets");
+
+type Budgets = BTreeMap<(String, usize), u64>;
+
+struct Sample {
+    instruction: &'static str,
+    size: usize,
+    units: u64,
+}
+
+/// The build under test: `SBF_OUT_DIR` (or `BPF_OUT_DIR`) if set, otherwise
+/// the workspace's target/deploy, where `anchor build` leaves the program.
+fn out_dir() -> PathBuf {
+    std::env::var_os("SBF_OUT_DIR")
+        .or_else(|| std::env::var_os("BPF_OUT_DIR"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| concat!(env!("CARGO_MANIFEST_DIR"), "/../../target/deploy").into())
+}
+
+fn sbf_program_test(out_dir: &Path) -> ProgramTest {
+    if !out_dir.join("rotary_savings.so").exists() {
+        eprintln!(
+            "rotary_savings.so not found in {}; run `anchor build` or point SBF_OUT_DIR at the build",
+            out_dir.display()
+        );
+
+        process::exit(1);
+    }
+
+    // ProgramTest only looks for programs in the directory named by the
+    // environment, with BPF_OUT_DIR taking precedence
+    std::env::remove_var("BPF_OUT_DIR");
+    std::env::set_var("SBF_OUT_DIR", out_dir);
+
+    let mut program_test = ProgramTest::new("rotary_savings", rotary_savings::ID, None);
+
+    program_test.prefer_bpf(true);
+
+    program_test
+}
+
+/// One size's run through every instruction.
+struct Run<'a> {
+    env: TestEnv,
+    size: usize,
+    samples: &'a mut Vec<Sample>,
+}
+
+impl Run<'_> {
+    /// Records what the last transaction sent through `env` consumed.
+    fn record(&mut self, instruction: &'static str) {
+        self.samples.push(Sample {
+            instruction,
+            size: self.size,
+            units: self.env.compute_units,
+        });
+    }
+
+    async fn measure(&mut self, instruction: &'static str, ix: Instruction, signers: &[&Keypair]) {
+        self.env.send(&[ix], signers).await.unwrap();
+        self.record(instruction);
+    }
+}
+
+/// Walks a signer-governed collection with collateral from setup through a
+/// withdrawal, each kind of proposal, a migration and closing. Growing
+/// instructions are recorded at their most expensive point: the last member
+/// added or paying and the last signer's vote, which scans the whole signer
+/// list.
+async fn savings(run: &mut Run<'_>) {
+    let admin = run.env.admin.insecure_clone();
+    let size = run.size;
+    let params = CollectionParams {
+        total_members: size as u8 + 1,
+        collateral_amount: AMOUNT / 2,
+        ..Default::default()
+    };
+    let collection = run.env.create_collection("Savings", params).await;
+
+    run.record("create_collection");
+
+    let members = run.env.add_members(&collection, size).await;
+
+    run.record("add_user");
+
+    let co_admins = (0..MAX_CO_ADMINS).map(|_| Pubkey::new_unique()).collect();
+    let pause_key = run.env.funded_keypair().await;
+    let guardian = run.env.funded_keypair().await;
+
+    run.measure(
+        "set_co_admins",
+        client::set_co_admins(&admin.pubkey(), &collection, co_admins),
+        &[&admin],
+    )
+    .await;
+    run.measure(
+        "set_pause_key",
+        client::set_pause_key(&admin.pubkey(), &collection, &pause_key.pubkey()),
+        &[&admin],
+    )
+    .await;
+    run.measure(
+        "set_guardian",
+        client::set_guardian(&admin.pubkey(), &collection, &guardian.pubkey()),
+        &[&admin],
+    )
+    .await;
+
+    let signers = run.env.create_multisig(&collection, size, size as u8).await;
+
+    run.record("create_multisig");
+
+    // An allow list of one is its own root
+    let joiner = run.env.funded_keypair().await;
+    let root = hashv(&[joiner.pubkey().as_ref()]).to_bytes();
+
+    run.measure(
+        "set_enrollment",
+        client::set_enrollment(&admin.pubkey(), &collection, root, &Pubkey::default()),
+        &[&admin],
+    )
+    .await;
+    run.measure(
+        "join_collection",
+        client::join_collection(&joiner.pubkey(), &collection, vec![]),
+        &[&joiner],
+    )
+    .await;
+
+    // Joining already took the joiner's collateral
+    for member in &members {
+        run.env
+            .send(
+                &[client::deposit_collateral(&member.pubkey(), &collection)],
+                &[member],
+            )
+            .await
+            .unwrap();
+    }
+
+    run.record("deposit_collateral");
+
+    // The second member never pays, so they fall into default
+    for member in members
+        .iter()
+        .chain([&joiner])
+        .filter(|member| member.pubkey() != members[1].pubkey())
+    {
+        run.env.try_pay(&collection, member).await.unwrap();
+    }
+
+    run.record("pay");
+
+    let (last, others) = signers.split_last().unwrap();
+
+    run.measure(
+        "delegate_vote",
+        client::delegate_vote(&others[0].pubkey(), &collection, &last.pubkey()),
+        &[&others[0]],
+    )
+    .await;
+    run.measure(
+        "revoke_delegation",
+        client::revoke_delegation(&others[0].pubkey(), &collection),
+        &[&others[0]],
+    )
+    .await;
+
+    run.env.warp(PERIOD).await;
+    run.env.pay_periods(&collection, &members[..1], 2).await;
+
+    run.measure(
+        "slash_collateral",
+        client::slash_collateral(&admin.pubkey(), &collection, &members[1].pubkey()),
+        &[&admin],
+    )
+    .await;
+
+    run.env
+        .try_propose_withdraw(&collection, &members[0], 1)
+        .await
+        .unwrap();
+
+    run.record("propose_withdraw");
+
+    run.env.approve(&collection, 1, others).await;
+
+    run.measure(
+        "vote_on_proposal",
+        client::vote_on_proposal(&last.pubkey(), &collection, 1, true),
+        &[last],
+    )
+    .await;
+
+    let user_account = user_pda(&collection, &members[0].pubkey());
+
+    run.measure(
+        "execute_proposal",
+        client::execute_proposal(&collection, 1, &user_account),
+        &[],
+    )
+    .await;
+
+    let early = ProposalArgs {
+        proposal_type: PROPOSAL_EARLY_WITHDRAW,
+        withdraw_user: joiner.pubkey(),
+        withdraw_amount: AMOUNT,
+        ..Default::default()
+    };
+
+    run.env
+        .try_create_proposal(
+            &collection,
+            &joiner,
+            2,
+            &user_pda(&collection, &joiner.pubkey()),
+            early,
+        )
+        .await
+        .unwrap();
+
+    run.record("create_proposal");
+
+    run.measure(
+        "cancel_proposal",
+        client::cancel_proposal(&joiner.pubkey(), &collection, &joiner.pubkey(), 2),
+        &[&joiner],
+    )
+    .await;
+
+    let change = ProposalArgs {
+        settings_changes: SETTING_AMOUNT_PER_PERIOD,
+        new_amount_per_period: 2 * AMOUNT,
+        ..Default::default()
+    };
+
+    run.env
+        .try_adjust_settings(&collection, &admin, 3, change)
+        .await
+        .unwrap();
+
+    run.record("adjust_settings");
+
+    run.env
+        .try_pause_collection(&collection, &admin, 4)
+        .await
+        .unwrap();
+
+    run.record("pause_collection");
+
+    run.env.approve(&collection, 4, &signers).await;
+    run.env
+        .try_execute(&collection, 4, &user_account)
+        .await
+        .unwrap();
+
+    run.env
+        .try_resume_collection(&collection, &admin, 5)
+        .await
+        .unwrap();
+
+    run.record("resume_collection");
+
+    run.env.approve(&collection, 5, &signers).await;
+    run.env
+        .try_execute(&collection, 5, &user_account)
+        .await
+        .unwrap();
+
+    run.measure(
+        "guardian_freeze",
+        client::guardian_freeze(&guardian.pubkey(), &collection, DAY),
+        &[&guardian],
+    )
+    .await;
+
+    run.env
+        .try_unfreeze_vault(&collection, &admin, 6)
+        .await
+        .unwrap();
+
+    run.record("unfreeze_vault");
+
+    run.env.approve(&collection, 6, &signers).await;
+    run.env
+        .try_execute(&collection, 6, &user_account)
+        .await
+        .unwrap();
+
+    let new_admin = run.env.funded_keypair().await;
+
+    run.env
+        .try_transfer_admin(&collection, &admin, 7, new_admin.pubkey())
+        .await
+        .unwrap();
+
+    run.record("transfer_admin");
+
+    run.env.approve(&collection, 7, &signers).await;
+    run.env
+        .try_execute(&collection, 7, &user_account)
+        .await
+        .unwrap();
+
+    run.measure(
+        "accept_admin",
+        client::accept_admin(&new_admin.pubkey(), &collection),
+        &[&new_admin],
+    )
+    .await;
+
+    let now = run.env.now().await;
+    let legacy = client::collection_pda(&admin.pubkey(), "Legacy");
+
+    run.env
+        .plant(
+            &legacy,
+            legacy_data(
+                <Collection as anchor_lang::Discriminator>::DISCRIMINATOR,
+                &LegacyCollection {
+                    admin: admin.pubkey(),
+                    name: "Legacy".to_string(),
+                    duration: (size as i64) * PERIOD,
+                    period: PERIOD,
+                    amount_per_period: AMOUNT,
+                    total_members: size as u8,
+                    active_members: 1,
+                    total_balance: AMOUNT,
+                    is_active: true,
+                    early_withdrawal_penalty_rate: 5,
+                },
+            ),
+        )
+        .await;
+    run.env
+        .plant(
+            &client::multisig_pda(&legacy),
+            legacy_data(
+                <Multisig as anchor_lang::Discriminator>::DISCRIMINATOR,
+                &LegacyMultisig {
+                    collection: legacy,
+                    signers: vec![],
+                    threshold: 1,
+                    nonce: 1,
+                },
+            ),
+        )
+        .await;
+    run.env
+        .plant(
+            &client::user_pda(&legacy, &admin.pubkey()),
+            legacy_data(
+                <User as anchor_lang::Discriminator>::DISCRIMINATOR,
+                &LegacyUser {
+                    collection: legacy,
+                    user: admin.pubkey(),
+                    paid_periods: 1,
+                    last_paid: now,
+                    total_paid: AMOUNT,
+                    can_withdraw: false,
+                    early_withdrawal_requested: false,
+                },
+            ),
+        )
+        .await;
+    run.env
+        .plant(
+            &client::legacy_proposal_pda(&legacy, 0),
+            legacy_data(
+                <Proposal as anchor_lang::Discriminator>::DISCRIMINATOR,
+                &legacy_proposal(&legacy, &admin.pubkey(), now),
+            ),
+        )
+        .await;
+
+    run.measure(
+        "migrate_collection",
+        client::migrate_collection(&admin.pubkey(), &legacy),
+        &[&admin],
+    )
+    .await;
+
+    run.measure(
+        "migrate_user",
+        client::migrate_user(&admin.pubkey(), &legacy, &admin.pubkey()),
+        &[&admin],
+    )
+    .await;
+
+    run.measure(
+        "migrate_proposal",
+        client::migrate_proposal(&admin.pubkey(), &legacy, &admin.pubkey(), 0),
+        &[&admin],
+    )
+    .await;
+
+    run.env.warp(PROPOSAL_LIFETIME).await;
+
+    run.measure(
+        "prune_proposal",
+        client::prune_proposal(&collection, 3),
+        &[],
+    )
+    .await;
+
+    run.env
+        .try_close_collection(&collection, &new_admin, 9)
+        .await
+        .unwrap();
+
+    run.record("close_collection");
+
+    run.env.approve(&collection, 9, &signers).await;
+    run.env
+        .try_execute(&collection, 9, &user_account)
+        .await
+        .unwrap();
+
+    run.measure(
+        "reclaim_collateral",
+        client::reclaim_collateral(&members[0].pubkey(), &collection),
+        &[&members[0]],
+    )
+    .await;
+    run.measure(
+        "emergency_pause",
+        client::emergency_pause(&pause_key.pubkey(), &collection),
+        &[&pause_key],
+    )
+    .await;
+}
+
+/// A member-governed collection: delegating and every member voting.
+async fn member_votes(run: &mut Run<'_>) {
+    let admin = run.env.admin.insecure_clone();
+    let params = CollectionParams {
+        total_members: run.size as u8,
+        governance_mode: GOVERNANCE_MEMBERS,
+        quorum_pct: 50,
+        approval_pct: 60,
+        ..Default::default()
+    };
+    let collection = run.env.create_collection("Members", params).await;
+    let members = run.env.add_members(&collection, run.size).await;
+    let stand_in = run.env.funded_keypair().await;
+    let change = ProposalArgs {
+        settings_changes: SETTING_AMOUNT_PER_PERIOD,
+        new_amount_per_period: 2 * AMOUNT,
+        ..Default::default()
+    };
+
+    run.env
+        .try_create_multisig(&collection, vec![], 0, vec![], 0, 0, PASS_THRESHOLD)
+        .await
+        .unwrap();
+    run.env
+        .try_adjust_settings(&collection, &admin, 1, change)
+        .await
+        .unwrap();
+
+    run.measure(
+        "delegate_member_vote",
+        client::delegate_member_vote(&members[0].pubkey(), &collection, &stand_in.pubkey()),
+        &[&members[0]],
+    )
+    .await;
+    run.measure(
+        "revoke_member_delegation",
+        client::revoke_member_delegation(&members[0].pubkey(), &collection),
+        &[&members[0]],
+    )
+    .await;
+
+    for member in &members {
+        run.env
+            .send(
+                &[client::cast_vote(
+                    &member.pubkey(),
+                    &collection,
+                    &member.pubkey(),
+                    1,
+                    true,
+                )],
+                &[member],
+            )
+            .await
+            .unwrap();
+    }
+
+    run.record("cast_vote");
+}
+
+/// Rotating collections: setting and shuffling the order, trading slots and
+/// paying out a round.
+async fn rotation(run: &mut Run<'_>) {
+    let admin = run.env.admin.insecure_clone();
+    let size = run.size;
+    let rotating = |payout_order_mode| CollectionParams {
+        duration: size as i64 * PERIOD,
+        total_members: size as u8,
+        payout_order_mode,
+        ..Default::default()
+    };
+
+    let fixed = run
+        .env
+        .create_collection("Fixed", rotating(PAYOUT_FIXED))
+        .await;
+    let members = run.env.add_members(&fixed, size).await;
+    let order = members.iter().rev().map(|member| member.pubkey()).collect();
+
+    run.measure(
+        "set_payout_order",
+        client::set_payout_order(&admin.pubkey(), &fixed, order),
+        &[&admin],
+    )
+    .await;
+
+    let random = run
+        .env
+        .create_collection("Random", rotating(PAYOUT_RANDOM))
+        .await;
+
+    run.env.add_members(&random, size).await;
+
+    run.measure(
+        "shuffle_payout_order",
+        client::shuffle_payout_order(&admin.pubkey(), &random),
+        &[&admin],
+    )
+    .await;
+
+    // The first member trades with the last, the farthest slot away
+    let collection = run
+        .env
+        .create_collection("Joined", rotating(PAYOUT_JOIN_ORDER))
+        .await;
+    let members = run.env.add_members(&collection, size).await;
+    let (first, last) = (&members[0], &members[size - 1]);
+
+    run.measure(
+        "propose_swap",
+        client::propose_swap(&first.pubkey(), &collection, &last.pubkey(), 0, AMOUNT / 10),
+        &[first],
+    )
+    .await;
+    run.measure(
+        "accept_swap",
+        client::accept_swap(&last.pubkey(), &collection, &first.pubkey(), 0),
+        &[last],
+    )
+    .await;
+
+    run.env
+        .send(
+            &[client::propose_swap(
+                &members[1].pubkey(),
+                &collection,
+                &first.pubkey(),
+                0,
+                AMOUNT / 10,
+            )],
+            &[&members[1]],
+        )
+        .await
+        .unwrap();
+
+    run.measure(
+        "cancel_swap",
+        client::cancel_swap(&members[1].pubkey(), &collection, 0),
+        &[&members[1]],
+    )
+    .await;
+
+    run.env.create_multisig(&collection, 1, 1).await;
+    run.env.pay_periods(&collection, &members, 1).await;
+
+    let recipient = run.env.fetch::<Collection>(&collection).await.payout_order[0];
+
+    run.measure(
+        "disburse_round",
+        client::disburse_round(&collection, &recipient),
+        &[],
+    )
+    .await;
+}
+
+/// An auction round: every member bids, then the round settles and a member
+/// claims their dividend.
+async fn auction(run: &mut Run<'_>) {
+    let size = run.size;
+    let params = CollectionParams {
+        duration: size as i64 * PERIOD,
+        total_members: size as u8,
+        payout_order_mode: PAYOUT_AUCTION,
+        ..Default::default()
+    };
+    let collection = run.env.create_collection("Auction", params).await;
+    let members = run.env.add_members(&collection, size).await;
+    let pot = size as u64 * AMOUNT;
+
+    run.env.create_multisig(&collection, 1, 1).await;
+
+    for member in &members {
+        run.env.try_pay(&collection, member).await.unwrap();
+    }
+
+    // Each bid undercuts the last, so the final bidder wins
+    for (index, member) in members.iter().enumerate() {
+        let amount = pot - (index as u64 + 1) * AMOUNT / 10;
+
+        run.env
+            .send(
+                &[client::place_bid(&member.pubkey(), &collection, amount)],
+                &[member],
+            )
+            .await
+            .unwrap();
+    }
+
+    run.record("place_bid");
+
+    run.measure(
+        "settle_round",
+        client::settle_round(&collection, &members[size - 1].pubkey()),
+        &[],
+    )
+    .await;
+    run.measure(
+        "claim_dividends",
+        client::claim_dividends(&members[0].pubkey(), &collection),
+        &[&members[0]],
+    )
+    .await;
+}
+
+async fn run(out_dir: &Path, size: usize, samples: &mut Vec<Sample>) {
+    let mut run = Run {
+        env: TestEnv::start(sbf_program_test(out_dir)).await,
+        size,
+        samples,
+    };
+
+    savings(&mut run).await;
+    member_votes(&mut run).await;
+    rotation(&mut run).await;
+    auction(&mut run).await;
+}
+
+fn read_budgets() -> Budgets {
+    let Ok(contents) = fs::read_to_string(BUDGETS) else {
+        return Budgets::new();
+    };
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            match fields[..] {
+                [instruction, size, units] => (
+                    (instruction.to_string(), size.parse().unwrap()),
+                    units.parse().unwrap(),
+                ),
+                _ => panic!("malformed budget line: {}", line),
+            }
+        })
+        .collect()
+}
+
+fn write_budgets(samples: &[Sample]) {
+    let mut contents = String::from(
+        "# Compute units per instruction and collection/multisig size, recorded by\n\
+         # `UPDATE_BUDGETS=1 cargo bench --bench compute_units`\n",
+    );
+
+    for sample in samples {
+        contents.push_str(&format!(
+            "{} {} {}\n",
+            sample.instruction, sample.size, sample.units
+        ));
+    }
+
+    fs::write(BUDGETS, contents).unwrap();
+}
+
+fn measure(runtime: &tokio::runtime::Runtime, out_dir: &Path) -> Vec<Sample> {
+    let mut samples = vec![];
+
+    for size in SIZES {
+        runtime.block_on(run(out_dir, size, &mut samples));
+    }
+
+    samples
+}
+
+fn main() {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let baseline_dir = std::env::var_os("BASELINE_SBF_OUT_DIR").map(PathBuf::from);
+    let samples = measure(&runtime, &out_dir());
+
+    // Both builds walk the same steps, so their samples line up one to one
+    let baseline = baseline_dir.map(|dir| measure(&runtime, &dir));
+    let budgets = read_budgets();
+    let mut failures = vec![];
+
+    println!(
+        "{:<24} {:>5} {:>8} {:>8} {:>8} {:>8}",
+        "instruction", "size", "units", "budget", "baseline", "change"
+    );
+
+    for (index, sample) in samples.iter().enumerate() {
+        let budget = budgets.get(&(sample.instruction.to_string(), sample.size));
+        let over_budget =
+            budget.is_some_and(|budget| sample.units * 100 > budget * (100 + TOLERANCE_PCT));
+        let baseline_units = baseline.as_ref().map(|baseline| baseline[index].units);
+
+        println!(
+            "{:<24} {:>5} {:>8} {:>8} {:>8} {:>8}",
+            sample.instruction,
+            sample.size,
+            sample.units,
+            budget.map_or("-".to_string(), |budget| budget.to_string()),
+            baseline_units.map_or("-".to_string(), |units| units.to_string()),
+            baseline_units.map_or("-".to_string(), |units| format!(
+                "{:+}",
+                sample.units as i64 - units as i64
+            ))
+        );
+
+        if sample.units > COMPUTE_LIMIT {
+            failures.push(format!(
+                "{} at size {} used {} compute units, over the {} unit limit",
+                sample.instruction, sample.size, sample.units, COMPUTE_LIMIT
+            ));
+        } else if over_budget {
+            failures.push(format!(
+                "{} at size {} used {} compute units, over its budget",
+                sample.instruction, sample.size, sample.units
+            ));
+        } else if budget.is_none() {
+            failures.push(format!(
+                "{} at size {} has no budget in {}",
+                sample.instruction, sample.size, BUDGETS
+            ));
+        }
+    }
+
+    if std::env::var_os("UPDATE_BUDGETS").is_some() {
+        write_budgets(&samples);
+
+        println!("budgets written to {}", BUDGETS);
+
+        return;
+    }
+
+    if !failures.is_empty() {
+        for failure in failures {
+            eprintln!("{}", failure);
+        }
+
+        process::exit(1);
+    }
+}
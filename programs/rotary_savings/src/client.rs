@@ -0,0 +1,753 @@
+//! Off-chain helpers for Rust clients, behind the `client` feature: PDA
+//! derivation, one instruction builder per entrypoint and account decoders.
+//!
+//! Builders take the wallets involved and derive every program address
+//! themselves; the caller signs with the keys the instruction marks as
+//! signers. Instructions with long argument lists take the generated
+//! `instruction::*` struct as is.
+
+use crate::{accounts, dot::program::*, instruction, seeds, ID};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, sysvar},
+    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+
+/// Collections stay at the address of the admin who created them, which
+/// `Collection::creator` keeps after the admin role moves.
+pub fn collection_pda(creator: &Pubkey, name: &str) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::COLLECTION, creator.as_ref(), name.as_bytes()], &ID).0
+}
+
+pub fn multisig_pda(collection: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::MULTISIG, collection.as_ref()], &ID).0
+}
+
+pub fn user_pda(collection: &Pubkey, user: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::USER, collection.as_ref(), user.as_ref()], &ID).0
+}
+
+pub fn proposal_pda(collection: &Pubkey, proposal_id: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            seeds::PROPOSAL,
+            collection.as_ref(),
+            seeds::proposal_id(proposal_id).as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// Address of a proposal created before ids were binary-encoded, from the
+/// multisig nonce it was created at.
+pub fn legacy_proposal_pda(collection: &Pubkey, legacy_nonce: u64) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            seeds::PROPOSAL,
+            collection.as_ref(),
+            seeds::legacy_proposal_id(legacy_nonce).as_bytes(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+/// `member` is the member the vote counts for, which may differ from the
+/// delegate who cast it.
+pub fn vote_pda(proposal: &Pubkey, member: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[seeds::VOTE, proposal.as_ref(), member.as_ref()], &ID).0
+}
+
+/// `round` is the collection's `current_round` when the swap was proposed.
+pub fn swap_pda(collection: &Pubkey, proposer: &Pubkey, round: u8) -> Pubkey {
+    Pubkey::find_program_address(
+        &[
+            seeds::SWAP,
+            collection.as_ref(),
+            proposer.as_ref(),
+            seeds::round(round).as_ref(),
+        ],
+        &ID,
+    )
+    .0
+}
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+pub fn accept_admin(new_admin: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::AcceptAdmin {
+            new_admin: *new_admin,
+            collection: *collection,
+        },
+        instruction::AcceptAdmin {},
+    )
+}
+
+pub fn accept_swap(
+    counterparty: &Pubkey,
+    collection: &Pubkey,
+    proposer: &Pubkey,
+    round: u8,
+) -> Instruction {
+    build(
+        accounts::AcceptSwap {
+            counterparty: *counterparty,
+            collection: *collection,
+            swap: swap_pda(collection, proposer, round),
+            proposer: *proposer,
+            proposer_account: user_pda(collection, proposer),
+            counterparty_account: user_pda(collection, counterparty),
+        },
+        instruction::AcceptSwap {},
+    )
+}
+
+pub fn add_user(admin: &Pubkey, collection: &Pubkey, new_user: &Pubkey) -> Instruction {
+    build(
+        accounts::AddUser {
+            admin: *admin,
+            collection: *collection,
+            user: user_pda(collection, new_user),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::AddUser {
+            new_user: *new_user,
+        },
+    )
+}
+
+pub fn adjust_settings(
+    admin: &Pubkey,
+    collection: &Pubkey,
+    args: instruction::AdjustSettings,
+) -> Instruction {
+    build(
+        accounts::AdjustSettings {
+            admin: *admin,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, args.proposal_id),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+/// `authority` is the proposer or an admin; the rent goes back to `proposer`.
+pub fn cancel_proposal(
+    authority: &Pubkey,
+    collection: &Pubkey,
+    proposer: &Pubkey,
+    proposal_id: u64,
+) -> Instruction {
+    build(
+        accounts::CancelProposal {
+            authority: *authority,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            proposer: *proposer,
+        },
+        instruction::CancelProposal {},
+    )
+}
+
+pub fn cancel_swap(proposer: &Pubkey, collection: &Pubkey, round: u8) -> Instruction {
+    build(
+        accounts::CancelSwap {
+            proposer: *proposer,
+            swap: swap_pda(collection, proposer, round),
+        },
+        instruction::CancelSwap {},
+    )
+}
+
+/// `voter` signs, either `member` itself or the delegate it named.
+pub fn cast_vote(
+    voter: &Pubkey,
+    collection: &Pubkey,
+    member: &Pubkey,
+    proposal_id: u64,
+    approve: bool,
+) -> Instruction {
+    let proposal = proposal_pda(collection, proposal_id);
+
+    build(
+        accounts::CastVote {
+            member: *voter,
+            collection: *collection,
+            user_account: user_pda(collection, member),
+            proposal,
+            vote: vote_pda(&proposal, member),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CastVote { approve },
+    )
+}
+
+pub fn claim_dividends(member: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::ClaimDividends {
+            member: *member,
+            collection: *collection,
+            user_account: user_pda(collection, member),
+        },
+        instruction::ClaimDividends {},
+    )
+}
+
+pub fn close_collection(admin: &Pubkey, collection: &Pubkey, proposal_id: u64) -> Instruction {
+    build(
+        accounts::CloseCollection {
+            admin: *admin,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::CloseCollection { proposal_id },
+    )
+}
+
+pub fn create_collection(admin: &Pubkey, args: instruction::CreateCollection) -> Instruction {
+    build(
+        accounts::CreateCollection {
+            admin: *admin,
+            collection: collection_pda(admin, &args.name),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+pub fn create_multisig(
+    admin: &Pubkey,
+    collection: &Pubkey,
+    args: instruction::CreateMultisig,
+) -> Instruction {
+    build(
+        accounts::CreateMultisig {
+            admin: *admin,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+/// `user_account` is only read for withdrawal proposals, where it must be the
+/// withdrawing member's, but the instruction always takes one.
+pub fn create_proposal(
+    proposer: &Pubkey,
+    collection: &Pubkey,
+    user_account: &Pubkey,
+    args: instruction::CreateProposal,
+) -> Instruction {
+    build(
+        accounts::CreateProposal {
+            proposer: *proposer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, args.proposal_id),
+            user_account: *user_account,
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        args,
+    )
+}
+
+pub fn delegate_member_vote(
+    member: &Pubkey,
+    collection: &Pubkey,
+    delegate: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::DelegateMemberVote {
+            member: *member,
+            user_account: user_pda(collection, member),
+        },
+        instruction::DelegateMemberVote {
+            delegate: *delegate,
+        },
+    )
+}
+
+pub fn delegate_vote(signer: &Pubkey, collection: &Pubkey, delegate: &Pubkey) -> Instruction {
+    build(
+        accounts::DelegateVote {
+            signer: *signer,
+            multisig: multisig_pda(collection),
+        },
+        instruction::DelegateVote {
+            delegate: *delegate,
+        },
+    )
+}
+
+pub fn deposit_collateral(member: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::DepositCollateral {
+            member: *member,
+            collection: *collection,
+            user_account: user_pda(collection, member),
+            system_program: system_program::ID,
+        },
+        instruction::DepositCollateral {},
+    )
+}
+
+pub fn disburse_round(collection: &Pubkey, recipient: &Pubkey) -> Instruction {
+    build(
+        accounts::DisburseRound {
+            collection: *collection,
+            recipient_account: user_pda(collection, recipient),
+            recipient: *recipient,
+        },
+        instruction::DisburseRound {},
+    )
+}
+
+pub fn emergency_pause(pause_key: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::EmergencyPause {
+            pause_key: *pause_key,
+            collection: *collection,
+        },
+        instruction::EmergencyPause {},
+    )
+}
+
+/// `user_account` is only read for withdrawals, where it must be the
+/// proposal's member's, but the instruction always takes one.
+pub fn execute_proposal(
+    collection: &Pubkey,
+    proposal_id: u64,
+    user_account: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::ExecuteProposal {
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            user_account: *user_account,
+            clock: sysvar::clock::ID,
+        },
+        instruction::ExecuteProposal {},
+    )
+}
+
+pub fn guardian_freeze(guardian: &Pubkey, collection: &Pubkey, duration: i64) -> Instruction {
+    build(
+        accounts::GuardianFreeze {
+            guardian: *guardian,
+            collection: *collection,
+            clock: sysvar::clock::ID,
+        },
+        instruction::GuardianFreeze { duration },
+    )
+}
+
+/// Joins with an allow-list proof. Invite joins pass an empty proof and put
+/// the invite signer's ed25519 instruction first in the same transaction.
+pub fn join_collection(member: &Pubkey, collection: &Pubkey, proof: Vec<[u8; 32]>) -> Instruction {
+    build(
+        accounts::JoinCollection {
+            member: *member,
+            collection: *collection,
+            user: user_pda(collection, member),
+            instructions: sysvar::instructions::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::JoinCollection { proof },
+    )
+}
+
+pub fn migrate_collection(payer: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateCollection {
+            payer: *payer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            system_program: system_program::ID,
+        },
+        instruction::MigrateCollection {},
+    )
+}
+
+/// The migrated proposal takes the legacy proposal's nonce as its id, and
+/// `proposer` gets the legacy account's rent back.
+pub fn migrate_proposal(
+    payer: &Pubkey,
+    collection: &Pubkey,
+    proposer: &Pubkey,
+    legacy_nonce: u64,
+) -> Instruction {
+    build(
+        accounts::MigrateProposal {
+            payer: *payer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            legacy_proposal: legacy_proposal_pda(collection, legacy_nonce),
+            proposer: *proposer,
+            proposal: proposal_pda(collection, legacy_nonce),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateProposal { legacy_nonce },
+    )
+}
+
+pub fn migrate_user(payer: &Pubkey, collection: &Pubkey, member: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateUser {
+            payer: *payer,
+            collection: *collection,
+            user_account: user_pda(collection, member),
+            system_program: system_program::ID,
+        },
+        instruction::MigrateUser {},
+    )
+}
+
+pub fn pause_collection(proposer: &Pubkey, collection: &Pubkey, proposal_id: u64) -> Instruction {
+    build(
+        accounts::PauseCollection {
+            proposer: *proposer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::PauseCollection { proposal_id },
+    )
+}
+
+pub fn pay(user: &Pubkey, collection: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::Pay {
+            user: *user,
+            collection: *collection,
+            user_account: user_pda(collection, user),
+            clock: sysvar::clock::ID,
+            system_program: system_program::ID,
+        },
+        instruction::Pay { amount },
+    )
+}
+
+pub fn place_bid(member: &Pubkey, collection: &Pubkey, amount: u64) -> Instruction {
+    build(
+        accounts::PlaceBid {
+            member: *member,
+            collection: *collection,
+            user_account: user_pda(collection, member),
+            clock: sysvar::clock::ID,
+        },
+        instruction::PlaceBid { amount },
+    )
+}
+
+/// `round` must be the collection's `current_round`, which seeds the swap.
+pub fn propose_swap(
+    proposer: &Pubkey,
+    collection: &Pubkey,
+    counterparty: &Pubkey,
+    round: u8,
+    side_payment: u64,
+) -> Instruction {
+    build(
+        accounts::ProposeSwap {
+            proposer: *proposer,
+            collection: *collection,
+            proposer_account: user_pda(collection, proposer),
+            counterparty_account: user_pda(collection, counterparty),
+            swap: swap_pda(collection, proposer, round),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ProposeSwap { side_payment },
+    )
+}
+
+pub fn propose_withdraw(user: &Pubkey, collection: &Pubkey, proposal_id: u64) -> Instruction {
+    build(
+        accounts::ProposeWithdraw {
+            user: *user,
+            collection: *collection,
+            user_account: user_pda(collection, user),
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ProposeWithdraw { proposal_id },
+    )
+}
+
+pub fn prune_proposal(collection: &Pubkey, proposal_id: u64) -> Instruction {
+    build(
+        accounts::PruneProposal {
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+        },
+        instruction::PruneProposal {},
+    )
+}
+
+pub fn reclaim_collateral(member: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::ReclaimCollateral {
+            member: *member,
+            collection: *collection,
+            user_account: user_pda(collection, member),
+        },
+        instruction::ReclaimCollateral {},
+    )
+}
+
+pub fn resume_collection(proposer: &Pubkey, collection: &Pubkey, proposal_id: u64) -> Instruction {
+    build(
+        accounts::ResumeCollection {
+            proposer: *proposer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::ResumeCollection { proposal_id },
+    )
+}
+
+pub fn revoke_delegation(signer: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeDelegation {
+            signer: *signer,
+            multisig: multisig_pda(collection),
+        },
+        instruction::RevokeDelegation {},
+    )
+}
+
+pub fn revoke_member_delegation(member: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::RevokeMemberDelegation {
+            member: *member,
+            user_account: user_pda(collection, member),
+        },
+        instruction::RevokeMemberDelegation {},
+    )
+}
+
+pub fn set_co_admins(admin: &Pubkey, collection: &Pubkey, co_admins: Vec<Pubkey>) -> Instruction {
+    build(
+        accounts::SetCoAdmins {
+            admin: *admin,
+            collection: *collection,
+        },
+        instruction::SetCoAdmins { co_admins },
+    )
+}
+
+pub fn set_enrollment(
+    admin: &Pubkey,
+    collection: &Pubkey,
+    allow_list_root: [u8; 32],
+    invite_signer: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::SetEnrollment {
+            admin: *admin,
+            collection: *collection,
+        },
+        instruction::SetEnrollment {
+            allow_list_root,
+            invite_signer: *invite_signer,
+        },
+    )
+}
+
+pub fn set_guardian(admin: &Pubkey, collection: &Pubkey, guardian: &Pubkey) -> Instruction {
+    build(
+        accounts::SetGuardian {
+            admin: *admin,
+            collection: *collection,
+        },
+        instruction::SetGuardian {
+            guardian: *guardian,
+        },
+    )
+}
+
+pub fn set_pause_key(
+    admin: &Pubkey,
+    collection: &Pubkey,
+    emergency_pause_key: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::SetPauseKey {
+            admin: *admin,
+            collection: *collection,
+        },
+        instruction::SetPauseKey {
+            emergency_pause_key: *emergency_pause_key,
+        },
+    )
+}
+
+pub fn set_payout_order(admin: &Pubkey, collection: &Pubkey, order: Vec<Pubkey>) -> Instruction {
+    build(
+        accounts::SetPayoutOrder {
+            admin: *admin,
+            collection: *collection,
+        },
+        instruction::SetPayoutOrder { order },
+    )
+}
+
+pub fn settle_round(collection: &Pubkey, recipient: &Pubkey) -> Instruction {
+    build(
+        accounts::SettleRound {
+            collection: *collection,
+            recipient_account: user_pda(collection, recipient),
+            recipient: *recipient,
+            clock: sysvar::clock::ID,
+        },
+        instruction::SettleRound {},
+    )
+}
+
+pub fn shuffle_payout_order(admin: &Pubkey, collection: &Pubkey) -> Instruction {
+    build(
+        accounts::ShufflePayoutOrder {
+            admin: *admin,
+            collection: *collection,
+            slot_hashes: sysvar::slot_hashes::ID,
+        },
+        instruction::ShufflePayoutOrder {},
+    )
+}
+
+pub fn slash_collateral(admin: &Pubkey, collection: &Pubkey, member: &Pubkey) -> Instruction {
+    build(
+        accounts::SlashCollateral {
+            admin: *admin,
+            collection: *collection,
+            user_account: user_pda(collection, member),
+            clock: sysvar::clock::ID,
+        },
+        instruction::SlashCollateral {},
+    )
+}
+
+pub fn transfer_admin(
+    proposer: &Pubkey,
+    collection: &Pubkey,
+    proposal_id: u64,
+    new_admin: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::TransferAdmin {
+            proposer: *proposer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::TransferAdmin {
+            proposal_id,
+            new_admin: *new_admin,
+        },
+    )
+}
+
+pub fn unfreeze_vault(proposer: &Pubkey, collection: &Pubkey, proposal_id: u64) -> Instruction {
+    build(
+        accounts::UnfreezeVault {
+            proposer: *proposer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::UnfreezeVault { proposal_id },
+    )
+}
+
+pub fn vote_on_proposal(
+    signer: &Pubkey,
+    collection: &Pubkey,
+    proposal_id: u64,
+    approve: bool,
+) -> Instruction {
+    build(
+        accounts::VoteOnProposal {
+            signer: *signer,
+            collection: *collection,
+            multisig: multisig_pda(collection),
+            proposal: proposal_pda(collection, proposal_id),
+            clock: sysvar::clock::ID,
+        },
+        instruction::VoteOnProposal { approve },
+    )
+}
+
+/// Decodes raw account data, checking the discriminator matches `T`.
+pub fn deserialize<T: AccountDeserialize>(data: &[u8]) -> Result<T> {
+    T::try_deserialize(&mut &data[..])
+}
+
+pub fn deserialize_collection(data: &[u8]) -> Result<Collection> {
+    deserialize(data)
+}
+
+pub fn deserialize_multisig(data: &[u8]) -> Result<Multisig> {
+    deserialize(data)
+}
+
+pub fn deserialize_user(data: &[u8]) -> Result<User> {
+    deserialize(data)
+}
+
+pub fn deserialize_proposal(data: &[u8]) -> Result<Proposal> {
+    deserialize(data)
+}
+
+pub fn deserialize_vote(data: &[u8]) -> Result<Vote> {
+    deserialize(data)
+}
+
+pub fn deserialize_swap(data: &[u8]) -> Result<Swap> {
+    deserialize(data)
+}
@@ -1,106 +1,287 @@
-#![allow(unused_imports)]
-#![allow(unused_variables)]
-#![allow(unused_mut)]
-use crate::{id, seahorse_util::*};
-use anchor_lang::{prelude::*, solana_program};
-use anchor_spl::token::{self, Mint, Token, TokenAccount};
-use std::{cell::RefCell, rc::Rc};
+#![allow(clippy::too_many_arguments)]
+use crate::rules;
+use anchor_lang::{
+    prelude::*,
+    solana_program::{self, ed25519_program, hash::hashv, sysvar::instructions},
+};
 
-#[account]
-#[derive(Debug)]
-pub struct Collection {
-    pub admin: Pubkey,
-    pub name: String,
-    pub duration: i64,
-    pub period: i64,
-    pub amount_per_period: u64,
-    pub total_members: u8,
-    pub active_members: u8,
-    pub total_balance: u64,
-    pub is_active: bool,
-    pub early_withdrawal_penalty_rate: u8,
-}
+pub const BPS_DENOMINATOR: u16 = 10000;
 
-impl<'info, 'entrypoint> Collection {
-    pub fn load(
-        account: &'entrypoint mut Box<Account<'info, Self>>,
-        programs_map: &'entrypoint ProgramsMap<'info>,
-    ) -> Mutable<LoadedCollection<'info, 'entrypoint>> {
-        let admin = account.admin.clone();
-        let name = account.name.clone();
-        let duration = account.duration;
-        let period = account.period;
-        let amount_per_period = account.amount_per_period;
-        let total_members = account.total_members;
-        let active_members = account.active_members;
-        let total_balance = account.total_balance;
-        let is_active = account.is_active.clone();
-        let early_withdrawal_penalty_rate = account.early_withdrawal_penalty_rate;
-
-        Mutable::new(LoadedCollection {
-            __account__: account,
-            __programs__: programs_map,
-            admin,
-            name,
-            duration,
-            period,
-            amount_per_period,
-            total_members,
-            active_members,
-            total_balance,
-            is_active,
-            early_withdrawal_penalty_rate,
-        })
-    }
+pub const MAX_NAME_LEN: usize = 32;
 
-    pub fn store(loaded: Mutable<LoadedCollection>) {
-        let mut loaded = loaded.borrow_mut();
-        let admin = loaded.admin.clone();
+pub const PENALTY_SCHEDULE_FLAT: u8 = 0;
 
-        loaded.__account__.admin = admin;
+pub const PENALTY_SCHEDULE_LINEAR: u8 = 1;
 
-        let name = loaded.name.clone();
+pub const SETTING_DURATION: u8 = 1;
 
-        loaded.__account__.name = name;
+pub const SETTING_PERIOD: u8 = 2;
 
-        let duration = loaded.duration;
+pub const SETTING_AMOUNT_PER_PERIOD: u8 = 4;
 
-        loaded.__account__.duration = duration;
+pub const SETTING_PENALTY_RATE: u8 = 8;
 
-        let period = loaded.period;
+pub const SETTING_PENALTY_SCHEDULE: u8 = 16;
 
-        loaded.__account__.period = period;
+pub const SETTING_MIN_PENALTY_RATE: u8 = 32;
 
-        let amount_per_period = loaded.amount_per_period;
+pub const ALL_SETTINGS: u8 = 63;
 
-        loaded.__account__.amount_per_period = amount_per_period;
+pub const PAYOUT_NONE: u8 = 0;
 
-        let total_members = loaded.total_members;
+pub const PAYOUT_FIXED: u8 = 1;
 
-        loaded.__account__.total_members = total_members;
+pub const PAYOUT_JOIN_ORDER: u8 = 2;
 
-        let active_members = loaded.active_members;
+pub const PAYOUT_RANDOM: u8 = 3;
 
-        loaded.__account__.active_members = active_members;
+pub const PAYOUT_AUCTION: u8 = 4;
 
-        let total_balance = loaded.total_balance;
+pub const MAX_OPEN_PROPOSALS: usize = 16;
 
-        loaded.__account__.total_balance = total_balance;
+pub const MAX_CO_ADMINS: usize = 3;
 
-        let is_active = loaded.is_active.clone();
+pub const PROPOSAL_WITHDRAW: u8 = 0;
 
-        loaded.__account__.is_active = is_active;
+pub const PROPOSAL_CLOSE: u8 = 1;
 
-        let early_withdrawal_penalty_rate = loaded.early_withdrawal_penalty_rate;
+pub const PROPOSAL_ADJUST_SETTINGS: u8 = 2;
 
-        loaded.__account__.early_withdrawal_penalty_rate = early_withdrawal_penalty_rate;
-    }
+pub const PROPOSAL_EARLY_WITHDRAW: u8 = 3;
+
+pub const PROPOSAL_TRANSFER_ADMIN: u8 = 4;
+
+pub const PROPOSAL_PAUSE: u8 = 5;
+
+pub const PROPOSAL_RESUME: u8 = 6;
+
+pub const PROPOSAL_UNFREEZE: u8 = 7;
+
+pub const PROPOSAL_LIFETIME: i64 = 7 * 24 * 60 * 60;
+
+pub const MAX_FREEZE_DURATION: i64 = 3 * 24 * 60 * 60;
+
+pub const FREEZE_COOLDOWN: i64 = 7 * 24 * 60 * 60;
+
+pub const GOVERNANCE_MULTISIG: u8 = 0;
+
+pub const GOVERNANCE_MEMBERS: u8 = 1;
+
+pub const PASS_THRESHOLD: u8 = 0;
+
+pub const PASS_MAJORITY: u8 = 1;
+
+pub const PASS_SUPERMAJORITY: u8 = 2;
+
+pub const SWAP_OPEN: u8 = 0;
+
+pub const SWAP_ACCEPTED: u8 = 1;
+
+pub const SWAP_CANCELLED: u8 = 2;
+
+#[error_code]
+pub enum RotarySavingsError {
+    #[msg("Collection name must not be empty")]
+    EmptyName,
+    #[msg("Collection name exceeds 32 bytes")]
+    NameTooLong,
+    #[msg("Duration must be positive")]
+    InvalidDuration,
+    #[msg("Period must be positive")]
+    InvalidPeriod,
+    #[msg("Duration must be a multiple of period")]
+    DurationNotMultipleOfPeriod,
+    #[msg("Amount per period must be positive")]
+    InvalidAmountPerPeriod,
+    #[msg("Total members must be positive")]
+    InvalidTotalMembers,
+    #[msg("Penalty rate exceeds 10000 basis points")]
+    PenaltyRateTooHigh,
+    #[msg("Invalid penalty schedule")]
+    InvalidPenaltySchedule,
+    #[msg("Minimum penalty rate exceeds penalty rate")]
+    MinPenaltyRateTooHigh,
+    #[msg("Settings proposal does not change any setting")]
+    NoSettingsChanges,
+    #[msg("Settings proposal contains unknown settings")]
+    InvalidSettingsChanges,
+    #[msg("Collection is not active")]
+    CollectionNotActive,
+    #[msg("Collection is full")]
+    CollectionFull,
+    #[msg("Collection does not accept self-enrollment")]
+    EnrollmentClosed,
+    #[msg("Member is not on the allow-list")]
+    NotOnAllowList,
+    #[msg("Join requires an allow-list proof or a signed invite")]
+    MissingInvite,
+    #[msg("Invite was not signed by the collection's invite signer")]
+    InvalidInvite,
+    #[msg("Only admin can change enrollment")]
+    NotAdmin,
+    #[msg("Signer does not own this membership")]
+    InvalidUser,
+    #[msg("Collateral must be deposited before paying")]
+    CollateralRequired,
+    #[msg("Collateral already deposited")]
+    CollateralAlreadyDeposited,
+    #[msg("Member has no collateral held")]
+    NoCollateral,
+    #[msg("Member is not in default")]
+    MemberNotInDefault,
+    #[msg("Collateral is returned at the end of the cycle")]
+    CollateralLocked,
+    #[msg("Invalid payout order mode")]
+    InvalidPayoutOrderMode,
+    #[msg("A rotating collection needs one period per member")]
+    RoundsMustMatchMembers,
+    #[msg("Rotating collections pay out through rounds")]
+    RotatingCollection,
+    #[msg("Collection does not rotate payouts")]
+    NotRotating,
+    #[msg("Payout order cannot be set in this mode")]
+    WrongPayoutOrderMode,
+    #[msg("Payout order is not finalized")]
+    PayoutOrderNotFinalized,
+    #[msg("Payout order can no longer change")]
+    PayoutOrderLocked,
+    #[msg("Payout order must be a permutation of the members")]
+    InvalidPayoutOrder,
+    #[msg("Collection is not full")]
+    CollectionNotFull,
+    #[msg("Every round has been paid out")]
+    RotationComplete,
+    #[msg("Pot for this round is not fully funded")]
+    PotNotFunded,
+    #[msg("Recipient does not hold this round's slot")]
+    WrongRecipient,
+    #[msg("Auction rounds are settled by bids")]
+    AuctionRound,
+    #[msg("Collection does not auction its rounds")]
+    NotAuction,
+    #[msg("Bidding window for this round is not open")]
+    BiddingClosed,
+    #[msg("Bidding window for this round is still open")]
+    BiddingOpen,
+    #[msg("Bid cannot exceed the pot")]
+    BidTooHigh,
+    #[msg("Member has already bid this round")]
+    AlreadyBid,
+    #[msg("Member has already received a payout")]
+    AlreadyReceivedPayout,
+    #[msg("No dividends to claim")]
+    NoDividends,
+    #[msg("Payout slots cannot be swapped in this mode")]
+    SwapsUnavailable,
+    #[msg("Cannot swap with yourself")]
+    SelfSwap,
+    #[msg("Swap is no longer open")]
+    SwapNotOpen,
+    #[msg("Only the counterparty can accept this swap")]
+    NotSwapCounterparty,
+    #[msg("Only the proposer can cancel this swap")]
+    NotSwapProposer,
+    #[msg("Too many open proposals")]
+    TooManyOpenProposals,
+    #[msg("Proposal id is already open")]
+    DuplicateProposalId,
+    #[msg("Proposal does not belong to this multisig")]
+    ProposalMismatch,
+    #[msg("Proposal is still open")]
+    ProposalStillOpen,
+    #[msg("Invalid proposal type")]
+    InvalidProposalType,
+    #[msg("Withdrawal proposals need the member's account")]
+    MissingUserAccount,
+    #[msg("Member cannot withdraw yet")]
+    CannotWithdrawYet,
+    #[msg("Member is eligible for regular withdrawal")]
+    EligibleForRegularWithdrawal,
+    #[msg("Early withdrawal already requested")]
+    EarlyWithdrawalAlreadyRequested,
+    #[msg("Withdraw amount must equal the member's total paid")]
+    InvalidWithdrawAmount,
+    #[msg("Weights must be positive and match the signers")]
+    InvalidWeights,
+    #[msg("Weight threshold must be positive and reachable")]
+    InvalidWeightThreshold,
+    #[msg("Not enough approval weight")]
+    NotEnoughApprovalWeight,
+    #[msg("Invalid governance settings")]
+    InvalidGovernance,
+    #[msg("Collection is not member-governed")]
+    NotMemberGoverned,
+    #[msg("Proposal already executed")]
+    ProposalExecuted,
+    #[msg("Proposal expired")]
+    ProposalExpired,
+    #[msg("Quorum not reached")]
+    QuorumNotReached,
+    #[msg("Proposal did not pass")]
+    ProposalRejected,
+    #[msg("Cannot delegate to yourself")]
+    SelfDelegation,
+    #[msg("Not a multisig signer")]
+    NotASigner,
+    #[msg("Invalid pass rule")]
+    InvalidPassRule,
+    #[msg("Quorum exceeds the number of signers")]
+    InvalidQuorum,
+    #[msg("New admin must be a non-default key other than the current admin")]
+    InvalidNewAdmin,
+    #[msg("Signer is not the pending admin")]
+    NotPendingAdmin,
+    #[msg("Co-admins must be unique, non-default and at most three")]
+    InvalidCoAdmins,
+    #[msg("Collection is paused")]
+    CollectionPaused,
+    #[msg("Collection is already paused")]
+    AlreadyPaused,
+    #[msg("Collection is not paused")]
+    NotPaused,
+    #[msg("Signer is not the emergency pause key")]
+    NotPauseKey,
+    #[msg("Signer is not the guardian")]
+    NotGuardian,
+    #[msg("Freeze duration must be positive and at most three days")]
+    InvalidFreezeDuration,
+    #[msg("Vault is already frozen")]
+    AlreadyFrozen,
+    #[msg("Vault is frozen")]
+    VaultFrozen,
+    #[msg("Collection already has a multisig")]
+    MultisigAlreadySet,
+    #[msg("Collection has no multisig yet")]
+    GovernanceNotConfigured,
+    #[msg("Multisig does not belong to this collection")]
+    WrongMultisig,
+    #[msg("Signers must be unique")]
+    DuplicateSigner,
+    #[msg("Threshold must be positive")]
+    InvalidThreshold,
+    #[msg("Only the proposer or an admin can cancel this proposal")]
+    NotProposer,
+    #[msg("Not enough approvals")]
+    NotEnoughApprovals,
+    #[msg("The guardian froze the vault too recently")]
+    FreezeCooldown,
+    #[msg("Signer has no vote slot on this proposal")]
+    VoteSlotOutOfRange,
+    #[msg("Account is not in the original layout")]
+    NotLegacyAccount,
+    #[msg("Account is not the proposal's proposer")]
+    WrongProposer,
+    #[msg("Early withdrawal not requested")]
+    EarlyWithdrawalNotRequested,
+    #[msg("Payment must equal the amount per period")]
+    InvalidPaymentAmount,
+    #[msg("Too early for next payment")]
+    PaymentNotDue,
 }
 
+#[account]
 #[derive(Debug)]
-pub struct LoadedCollection<'info, 'entrypoint> {
-    pub __account__: &'entrypoint mut Box<Account<'info, Collection>>,
-    pub __programs__: &'entrypoint ProgramsMap<'info>,
+pub struct Collection {
     pub admin: Pubkey,
     pub name: String,
     pub duration: i64,
@@ -110,7 +291,36 @@ pub struct LoadedCollection<'info, 'entrypoint> {
     pub active_members: u8,
     pub total_balance: u64,
     pub is_active: bool,
-    pub early_withdrawal_penalty_rate: u8,
+    pub early_withdrawal_penalty_rate: u16,
+    pub penalty_schedule: u8,
+    pub min_early_withdrawal_penalty_rate: u16,
+    pub allow_list_root: [u8; 32],
+    pub invite_signer: Pubkey,
+    pub collateral_amount: u64,
+    pub total_collateral: u64,
+    pub payout_order_mode: u8,
+    pub payout_order: Vec<Pubkey>,
+    pub payout_order_finalized: bool,
+    pub payout_seed: [u8; 32],
+    pub current_round: u8,
+    pub rotation_started_at: i64,
+    pub bid_count: u8,
+    pub best_bid: u64,
+    pub best_bidder: Pubkey,
+    pub dividend_per_member: u64,
+    pub dividends_outstanding: u64,
+    pub governance_mode: u8,
+    pub quorum_pct: u8,
+    pub approval_pct: u8,
+    pub creator: Pubkey,
+    pub pending_admin: Pubkey,
+    pub co_admins: Vec<Pubkey>,
+    pub is_paused: bool,
+    pub emergency_pause_key: Pubkey,
+    pub guardian: Pubkey,
+    pub frozen_until: i64,
+    pub last_frozen_at: i64,
+    pub multisig: Pubkey,
 }
 
 #[account]
@@ -120,76 +330,84 @@ pub struct Multisig {
     pub signers: Vec<Pubkey>,
     pub threshold: u8,
     pub nonce: u64,
+    pub open_proposals: Vec<u64>,
+    pub weights: Vec<u16>,
+    pub weight_threshold: u32,
+    pub delegates: Vec<Pubkey>,
+    pub quorum: u8,
+    pub pass_rule: u8,
 }
 
-impl<'info, 'entrypoint> Multisig {
-    pub fn load(
-        account: &'entrypoint mut Box<Account<'info, Self>>,
-        programs_map: &'entrypoint ProgramsMap<'info>,
-    ) -> Mutable<LoadedMultisig<'info, 'entrypoint>> {
-        let collection = account.collection.clone();
-        let signers = Mutable::new(
-            account
-                .signers
-                .clone()
-                .into_iter()
-                .map(|element| element)
-                .collect(),
-        );
-
-        let threshold = account.threshold;
-        let nonce = account.nonce;
-
-        Mutable::new(LoadedMultisig {
-            __account__: account,
-            __programs__: programs_map,
-            collection,
-            signers,
-            threshold,
-            nonce,
-        })
-    }
-
-    pub fn store(loaded: Mutable<LoadedMultisig>) {
-        let mut loaded = loaded.borrow_mut();
-        let collection = loaded.collection.clone();
-
-        loaded.__account__.collection = collection;
-
-        let signers = loaded
-            .signers
-            .clone()
-            .borrow()
-            .clone()
-            .into_iter()
-            .map(|element| element)
-            .collect();
-
-        loaded.__account__.signers = signers;
-
-        let threshold = loaded.threshold;
-
-        loaded.__account__.threshold = threshold;
-
-        let nonce = loaded.nonce;
+#[account]
+#[derive(Debug)]
+pub struct Proposal {
+    pub collection: Pubkey,
+    pub proposal_type: u8,
+    pub proposer: Pubkey,
+    pub created_at: i64,
+    pub expires_at: i64,
+    pub executed: bool,
+    pub approvals: Vec<bool>,
+    pub disapprovals: Vec<bool>,
+    pub withdraw_user: Pubkey,
+    pub withdraw_amount: u64,
+    pub new_duration: i64,
+    pub new_period: i64,
+    pub new_amount_per_period: u64,
+    pub new_early_withdrawal_penalty_rate: u16,
+    pub settings_changes: u8,
+    pub new_penalty_schedule: u8,
+    pub new_min_early_withdrawal_penalty_rate: u16,
+    pub proposal_id: u64,
+    pub member_approvals: u32,
+    pub member_disapprovals: u32,
+    pub new_admin: Pubkey,
+}
 
-        loaded.__account__.nonce = nonce;
-    }
+/// A collection as the original program laid it out, before everything after
+/// the penalty rate.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyCollection {
+    pub admin: Pubkey,
+    pub name: String,
+    pub duration: i64,
+    pub period: i64,
+    pub amount_per_period: u64,
+    pub total_members: u8,
+    pub active_members: u8,
+    pub total_balance: u64,
+    pub is_active: bool,
+    /// In percent.
+    pub early_withdrawal_penalty_rate: u8,
 }
 
-#[derive(Debug)]
-pub struct LoadedMultisig<'info, 'entrypoint> {
-    pub __account__: &'entrypoint mut Box<Account<'info, Multisig>>,
-    pub __programs__: &'entrypoint ProgramsMap<'info>,
+/// A multisig as the original program laid it out, before the proposal index,
+/// weights, delegates and pass rules.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyMultisig {
     pub collection: Pubkey,
-    pub signers: Mutable<Vec<Pubkey>>,
+    pub signers: Vec<Pubkey>,
     pub threshold: u8,
     pub nonce: u64,
 }
 
-#[account]
-#[derive(Debug)]
-pub struct Proposal {
+/// A member as the original program laid it out, before collateral, payouts,
+/// dividends and delegation.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyUser {
+    pub collection: Pubkey,
+    pub user: Pubkey,
+    pub paid_periods: u8,
+    pub last_paid: i64,
+    pub total_paid: u64,
+    pub can_withdraw: bool,
+    pub early_withdrawal_requested: bool,
+}
+
+/// A proposal as laid out before ids, settings masks and basis-point rates.
+/// These live at the Debug-formatted nonce address until migrated.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug)]
+pub struct LegacyProposal {
     pub collection: Pubkey,
     pub proposal_type: u8,
     pub proposer: Pubkey,
@@ -203,865 +421,2615 @@ pub struct Proposal {
     pub new_duration: i64,
     pub new_period: i64,
     pub new_amount_per_period: u64,
+    /// In percent.
     pub new_early_withdrawal_penalty_rate: u8,
 }
 
-impl<'info, 'entrypoint> Proposal {
-    pub fn load(
-        account: &'entrypoint mut Box<Account<'info, Self>>,
-        programs_map: &'entrypoint ProgramsMap<'info>,
-    ) -> Mutable<LoadedProposal<'info, 'entrypoint>> {
-        let collection = account.collection.clone();
-        let proposal_type = account.proposal_type;
-        let proposer = account.proposer.clone();
-        let created_at = account.created_at;
-        let expires_at = account.expires_at;
-        let executed = account.executed.clone();
-        let approvals = Mutable::new(
-            account
-                .approvals
-                .clone()
-                .into_iter()
-                .map(|element| element)
-                .collect(),
+/// Payload of a proposal; fields a kind does not use stay at their defaults.
+#[derive(Clone, Debug, Default)]
+pub struct ProposalArgs {
+    pub proposal_type: u8,
+    pub withdraw_user: Pubkey,
+    pub withdraw_amount: u64,
+    pub settings_changes: u8,
+    pub new_duration: i64,
+    pub new_period: i64,
+    pub new_amount_per_period: u64,
+    pub new_early_withdrawal_penalty_rate: u16,
+    pub new_penalty_schedule: u8,
+    pub new_min_early_withdrawal_penalty_rate: u16,
+    pub new_admin: Pubkey,
+}
+
+#[account]
+#[derive(Debug)]
+pub struct Swap {
+    pub collection: Pubkey,
+    pub proposer: Pubkey,
+    pub counterparty: Pubkey,
+    pub side_payment: u64,
+    pub round: u8,
+    pub created_at: i64,
+    pub status: u8,
+}
+
+#[account]
+#[derive(Debug)]
+pub struct User {
+    pub collection: Pubkey,
+    pub user: Pubkey,
+    pub paid_periods: u8,
+    pub last_paid: i64,
+    pub total_paid: u64,
+    pub can_withdraw: bool,
+    pub early_withdrawal_requested: bool,
+    pub joined_at: i64,
+    pub collateral: u64,
+    pub received_payout: bool,
+    pub bid_round: u8,
+    pub dividend_debt: u64,
+    pub dividends_claimed: u64,
+    pub delegate: Pubkey,
+}
+
+#[account]
+#[derive(Debug)]
+pub struct Vote {
+    pub proposal: Pubkey,
+    pub voter: Pubkey,
+    pub approve: bool,
+}
+
+/// Second step of an admin transfer: the key chosen by the multisig takes over.
+pub fn accept_admin_handler<'info>(
+    new_admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+) -> Result<()> {
+    require!(
+        (collection.pending_admin != Pubkey::default())
+            && (new_admin.key() == collection.pending_admin),
+        RotarySavingsError::NotPendingAdmin
+    );
+
+    collection.admin = new_admin.key();
+    collection.pending_admin = Pubkey::default();
+
+    collection
+        .co_admins
+        .retain(|co_admin| *co_admin != new_admin.key());
+
+    Ok(())
+}
+
+pub fn accept_swap_handler<'info>(
+    counterparty: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    swap: &mut Account<'info, Swap>,
+    proposer_account: &mut Account<'info, User>,
+    counterparty_account: &mut Account<'info, User>,
+) -> Result<()> {
+    require!(swap.status == SWAP_OPEN, RotarySavingsError::SwapNotOpen);
+
+    require!(
+        counterparty.key() == swap.counterparty,
+        RotarySavingsError::NotSwapCounterparty
+    );
+
+    require!(
+        (swap.collection == collection.key())
+            && (proposer_account.collection == swap.collection)
+            && (proposer_account.user == swap.proposer)
+            && (counterparty_account.collection == swap.collection)
+            && (counterparty_account.user == swap.counterparty),
+        RotarySavingsError::InvalidUser
+    );
+
+    // Either slot may have been paid out since the swap was proposed
+    require!(
+        !proposer_account.received_payout && !counterparty_account.received_payout,
+        RotarySavingsError::AlreadyReceivedPayout
+    );
+
+    let mut order = collection.payout_order.clone();
+    let proposer_slot = order
+        .iter()
+        .position(|member| *member == swap.proposer)
+        .unwrap();
+
+    let counterparty_slot = order
+        .iter()
+        .position(|member| *member == swap.counterparty)
+        .unwrap();
+
+    order.swap(proposer_slot, counterparty_slot);
+
+    collection.payout_order = order;
+    swap.status = SWAP_ACCEPTED;
+
+    release_side_payment(swap, &counterparty.to_account_info());
+
+    Ok(())
+}
+
+pub fn add_user_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user: &mut Account<'info, User>,
+    new_user: Pubkey,
+) -> Result<()> {
+    require!(
+        is_admin(collection, admin.key()),
+        RotarySavingsError::NotAdmin
+    );
+
+    require!(
+        collection.active_members < collection.total_members,
+        RotarySavingsError::CollectionFull
+    );
+
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
+
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
+
+    // Admin-added members post their own collateral via deposit_collateral
+    init_member(collection, user, new_user)
+}
+
+pub fn adjust_settings_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    proposal_id: u64,
+    settings_changes: u8,
+    new_duration: i64,
+    new_period: i64,
+    new_amount_per_period: u64,
+    new_early_withdrawal_penalty_rate: u16,
+    new_penalty_schedule: u8,
+    new_min_early_withdrawal_penalty_rate: u16,
+) -> Result<()> {
+    init_proposal(
+        admin.key(),
+        collection,
+        multisig,
+        proposal,
+        None,
+        clock,
+        proposal_id,
+        ProposalArgs {
+            proposal_type: PROPOSAL_ADJUST_SETTINGS,
+            settings_changes,
+            new_duration,
+            new_period,
+            new_amount_per_period,
+            new_early_withdrawal_penalty_rate,
+            new_penalty_schedule,
+            new_min_early_withdrawal_penalty_rate,
+            ..Default::default()
+        },
+    )
+}
+
+/// Withdraws an unexecuted proposal and frees its slot on the multisig. The
+/// proposer may cancel their own and the admin or a co-admin any, so no one
+/// signer can hold every open slot. The instruction closes the account back
+/// to the proposer.
+pub fn cancel_proposal_handler<'info>(
+    authority: &Signer<'info>,
+    collection: &Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+) -> Result<()> {
+    require!(
+        multisig.key() == collection.multisig,
+        RotarySavingsError::WrongMultisig
+    );
+
+    require!(
+        proposal.collection == collection.key(),
+        RotarySavingsError::ProposalMismatch
+    );
+
+    require!(!proposal.executed, RotarySavingsError::ProposalExecuted);
+
+    require!(
+        (authority.key() == proposal.proposer) || is_admin(collection, authority.key()),
+        RotarySavingsError::NotProposer
+    );
+
+    multisig
+        .open_proposals
+        .retain(|id| *id != proposal.proposal_id);
+
+    Ok(())
+}
+
+pub fn cancel_swap_handler<'info>(
+    proposer: &Signer<'info>,
+    swap: &mut Account<'info, Swap>,
+) -> Result<()> {
+    require!(swap.status == SWAP_OPEN, RotarySavingsError::SwapNotOpen);
+
+    require!(
+        proposer.key() == swap.proposer,
+        RotarySavingsError::NotSwapProposer
+    );
+
+    swap.status = SWAP_CANCELLED;
+
+    release_side_payment(swap, &proposer.to_account_info());
+
+    Ok(())
+}
+
+/// Records a member's vote on a proposal of a member-governed collection.
+/// The vote account's address makes a second vote by the same member fail.
+pub fn cast_vote_handler<'info>(
+    member: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+    proposal: &mut Account<'info, Proposal>,
+    vote: &mut Account<'info, Vote>,
+    clock: &Clock,
+    approve: bool,
+) -> Result<()> {
+    require!(
+        collection.governance_mode == GOVERNANCE_MEMBERS,
+        RotarySavingsError::NotMemberGoverned
+    );
+
+    // A delegate signs for the member; the vote is still recorded under the member
+    require!(
+        ((member.key() == user_account.user) || (member.key() == user_account.delegate))
+            && (user_account.collection == collection.key()),
+        RotarySavingsError::InvalidUser
+    );
+
+    require!(
+        proposal.collection == collection.key(),
+        RotarySavingsError::ProposalMismatch
+    );
+
+    require!(!proposal.executed, RotarySavingsError::ProposalExecuted);
+
+    require!(
+        clock.unix_timestamp < proposal.expires_at,
+        RotarySavingsError::ProposalExpired
+    );
+
+    vote.proposal = proposal.key();
+    vote.voter = user_account.user;
+    vote.approve = approve;
+
+    if approve {
+        proposal.member_approvals += 1;
+    } else {
+        proposal.member_disapprovals += 1;
+    }
+
+    Ok(())
+}
+
+pub fn claim_dividends_handler<'info>(
+    member: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+) -> Result<()> {
+    require!(
+        (member.key() == user_account.user) && (user_account.collection == collection.key()),
+        RotarySavingsError::InvalidUser
+    );
+
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
+
+    let owed = collection.dividend_per_member
+        - user_account.dividend_debt
+        - user_account.dividends_claimed;
+
+    require!(owed > 0, RotarySavingsError::NoDividends);
+
+    user_account.dividends_claimed += owed;
+    collection.dividends_outstanding -= owed;
+
+    {
+        **collection
+            .to_account_info()
+            .try_borrow_mut_lamports()
+            .unwrap() -= owed;
+
+        **member.to_account_info().try_borrow_mut_lamports().unwrap() += owed;
+    };
+
+    Ok(())
+}
+
+pub fn close_collection_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    proposal_id: u64,
+) -> Result<()> {
+    init_proposal(
+        admin.key(),
+        collection,
+        multisig,
+        proposal,
+        None,
+        clock,
+        proposal_id,
+        ProposalArgs {
+            proposal_type: PROPOSAL_CLOSE,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn create_collection_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    name: String,
+    duration: i64,
+    period: i64,
+    amount_per_period: u64,
+    total_members: u8,
+    early_withdrawal_penalty_rate: u16,
+    penalty_schedule: u8,
+    min_early_withdrawal_penalty_rate: u16,
+    collateral_amount: u64,
+    payout_order_mode: u8,
+    governance_mode: u8,
+    quorum_pct: u8,
+    approval_pct: u8,
+) -> Result<()> {
+    require!(!name.is_empty(), RotarySavingsError::EmptyName);
+
+    require!(name.len() <= MAX_NAME_LEN, RotarySavingsError::NameTooLong);
+
+    require!(total_members > 0, RotarySavingsError::InvalidTotalMembers);
+
+    validate_collection_settings(
+        duration,
+        period,
+        amount_per_period,
+        early_withdrawal_penalty_rate,
+        penalty_schedule,
+        min_early_withdrawal_penalty_rate,
+    )?;
+
+    require!(
+        payout_order_mode <= PAYOUT_AUCTION,
+        RotarySavingsError::InvalidPayoutOrderMode
+    );
+
+    validate_rotation(payout_order_mode, duration, period, total_members)?;
+
+    validate_governance(governance_mode, quorum_pct, approval_pct)?;
+
+    collection.admin = admin.key();
+    collection.name = name;
+    collection.duration = duration;
+    collection.period = period;
+    collection.amount_per_period = amount_per_period;
+    collection.total_members = total_members;
+    collection.active_members = 0;
+    collection.total_balance = 0;
+    collection.is_active = true;
+    collection.early_withdrawal_penalty_rate = early_withdrawal_penalty_rate;
+    collection.penalty_schedule = penalty_schedule;
+    collection.min_early_withdrawal_penalty_rate = min_early_withdrawal_penalty_rate;
+    collection.allow_list_root = [0; 32];
+    collection.invite_signer = Pubkey::default();
+    collection.collateral_amount = collateral_amount;
+    collection.total_collateral = 0;
+    collection.payout_order_mode = payout_order_mode;
+
+    // Join order is final as soon as the collection fills; fixed and random
+    // orders are finalized by set_payout_order and shuffle_payout_order
+    collection.payout_order_finalized = payout_order_mode == PAYOUT_JOIN_ORDER;
+    collection.payout_seed = [0; 32];
+    collection.current_round = 0;
+    collection.rotation_started_at = 0;
+    collection.bid_count = 0;
+    collection.best_bid = 0;
+    collection.best_bidder = Pubkey::default();
+    collection.dividend_per_member = 0;
+    collection.dividends_outstanding = 0;
+    collection.governance_mode = governance_mode;
+    collection.quorum_pct = quorum_pct;
+    collection.approval_pct = approval_pct;
+
+    // The PDA stays derived from the creator even after the admin changes
+    collection.creator = admin.key();
+    collection.pending_admin = Pubkey::default();
+    collection.co_admins = vec![];
+    collection.is_paused = false;
+    collection.emergency_pause_key = Pubkey::default();
+    collection.guardian = Pubkey::default();
+    collection.frozen_until = 0;
+    collection.last_frozen_at = 0;
+    collection.multisig = Pubkey::default();
+
+    Ok(())
+}
+
+/// Only the admin picks the signers; a co-admin could otherwise seat
+/// themselves on the multisig that governs the vault.
+pub fn create_multisig_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    signers: Vec<Pubkey>,
+    threshold: u8,
+    weights: Vec<u16>,
+    weight_threshold: u32,
+    quorum: u8,
+    pass_rule: u8,
+) -> Result<()> {
+    require!(
+        admin.key() == collection.admin,
+        RotarySavingsError::NotAdmin
+    );
+
+    require!(
+        (threshold as usize) <= signers.len(),
+        RotarySavingsError::InvalidThreshold
+    );
+
+    require!(
+        collection.multisig == Pubkey::default(),
+        RotarySavingsError::MultisigAlreadySet
+    );
+
+    // A repeated signer would have its vote counted once per entry
+    require!(
+        signers
+            .iter()
+            .enumerate()
+            .all(|(i, signer)| !signers[..i].contains(signer)),
+        RotarySavingsError::DuplicateSigner
+    );
+
+    // Member-governed collections only use the multisig as a proposal index
+    if collection.governance_mode == GOVERNANCE_MULTISIG {
+        require!(
+            !signers.is_empty() && (!weights.is_empty() || (threshold > 0)),
+            RotarySavingsError::InvalidThreshold
+        );
+    }
+
+    // Without weights every signer counts once against `threshold`
+    if !weights.is_empty() {
+        require!(
+            (weights.len() == signers.len()) && weights.iter().all(|weight| *weight > 0),
+            RotarySavingsError::InvalidWeights
         );
 
-        let disapprovals = Mutable::new(
-            account
-                .disapprovals
-                .clone()
-                .into_iter()
-                .map(|element| element)
-                .collect(),
+        require!(
+            (weight_threshold > 0)
+                && (weight_threshold <= weights.iter().map(|weight| *weight as u32).sum()),
+            RotarySavingsError::InvalidWeightThreshold
         );
+    }
 
-        let withdraw_user = account.withdraw_user.clone();
-        let withdraw_amount = account.withdraw_amount;
-        let new_duration = account.new_duration;
-        let new_period = account.new_period;
-        let new_amount_per_period = account.new_amount_per_period;
-        let new_early_withdrawal_penalty_rate = account.new_early_withdrawal_penalty_rate;
+    require!(
+        pass_rule <= PASS_SUPERMAJORITY,
+        RotarySavingsError::InvalidPassRule
+    );
 
-        Mutable::new(LoadedProposal {
-            __account__: account,
-            __programs__: programs_map,
-            collection,
+    require!(
+        (quorum as usize) <= signers.len(),
+        RotarySavingsError::InvalidQuorum
+    );
+
+    // Without a quorum a single approval is a majority of the votes cast
+    require!(
+        (collection.governance_mode == GOVERNANCE_MEMBERS)
+            || (pass_rule == PASS_THRESHOLD)
+            || (quorum > 0),
+        RotarySavingsError::InvalidQuorum
+    );
+
+    multisig.collection = collection.key();
+    multisig.signers = signers;
+    multisig.threshold = threshold;
+    multisig.nonce = 0;
+    multisig.open_proposals = vec![];
+    multisig.weights = weights;
+    multisig.weight_threshold = weight_threshold;
+    multisig.delegates = vec![Pubkey::default(); multisig.signers.len()];
+    multisig.quorum = quorum;
+    multisig.pass_rule = pass_rule;
+    collection.multisig = multisig.key();
+
+    Ok(())
+}
+
+pub fn create_proposal_handler<'info>(
+    proposer: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    user_account: &mut Account<'info, User>,
+    clock: &Clock,
+    proposal_id: u64,
+    proposal_type: u8,
+    withdraw_user: Pubkey,
+    withdraw_amount: u64,
+    new_duration: i64,
+    new_period: i64,
+    new_amount_per_period: u64,
+    new_early_withdrawal_penalty_rate: u16,
+    settings_changes: u8,
+    new_penalty_schedule: u8,
+    new_min_early_withdrawal_penalty_rate: u16,
+) -> Result<()> {
+    init_proposal(
+        proposer.key(),
+        collection,
+        multisig,
+        proposal,
+        Some(user_account),
+        clock,
+        proposal_id,
+        ProposalArgs {
             proposal_type,
-            proposer,
-            created_at,
-            expires_at,
-            executed,
-            approvals,
-            disapprovals,
             withdraw_user,
             withdraw_amount,
+            settings_changes,
             new_duration,
             new_period,
             new_amount_per_period,
             new_early_withdrawal_penalty_rate,
-        })
+            new_penalty_schedule,
+            new_min_early_withdrawal_penalty_rate,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn delegate_member_vote_handler<'info>(
+    member: &Signer<'info>,
+    user_account: &mut Account<'info, User>,
+    delegate: Pubkey,
+) -> Result<()> {
+    require!(
+        member.key() == user_account.user,
+        RotarySavingsError::InvalidUser
+    );
+
+    require!(delegate != member.key(), RotarySavingsError::SelfDelegation);
+
+    user_account.delegate = delegate;
+
+    Ok(())
+}
+
+pub fn delegate_vote_handler<'info>(
+    signer: &Signer<'info>,
+    multisig: &mut Account<'info, Multisig>,
+    delegate: Pubkey,
+) -> Result<()> {
+    require!(delegate != signer.key(), RotarySavingsError::SelfDelegation);
+
+    set_signer_delegate(multisig, signer.key(), delegate)
+}
+
+pub fn deposit_collateral_handler<'info>(
+    member: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(
+        (member.key() == user_account.user) && (user_account.collection == collection.key()),
+        RotarySavingsError::InvalidUser
+    );
+
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
+
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
+
+    require_governance(collection)?;
+
+    require!(
+        user_account.collateral == 0,
+        RotarySavingsError::CollateralAlreadyDeposited
+    );
+
+    transfer_collateral_in(member, collection, user_account, system_program)
+}
+
+pub fn disburse_round_handler<'info>(
+    collection: &mut Account<'info, Collection>,
+    recipient_account: &mut Account<'info, User>,
+    recipient: AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
+
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
+
+    require!(
+        collection.payout_order_mode != PAYOUT_NONE,
+        RotarySavingsError::NotRotating
+    );
+
+    require!(
+        collection.payout_order_mode != PAYOUT_AUCTION,
+        RotarySavingsError::AuctionRound
+    );
+
+    require!(
+        collection.active_members == collection.total_members,
+        RotarySavingsError::CollectionNotFull
+    );
+
+    require!(
+        collection.payout_order_finalized,
+        RotarySavingsError::PayoutOrderNotFinalized
+    );
+
+    let round = collection.current_round;
+
+    require!(
+        round < collection.total_members,
+        RotarySavingsError::RotationComplete
+    );
+
+    let slot_holder = collection.payout_order[round as usize];
+
+    require!(
+        (recipient_account.user == slot_holder)
+            && (recipient_account.collection == collection.key())
+            && (recipient.key() == slot_holder),
+        RotarySavingsError::WrongRecipient
+    );
+
+    let pot = round_pot(collection);
+
+    require!(
+        collection.total_balance >= pot,
+        RotarySavingsError::PotNotFunded
+    );
+
+    pay_out_round(collection, recipient_account, &recipient, pot);
+
+    Ok(())
+}
+
+pub fn early_withdrawal_penalty<'info>(
+    collection: &Account<'info, Collection>,
+    user_account: &Account<'info, User>,
+    amount: u64,
+) -> u64 {
+    let terms = rules::PenaltyTerms {
+        rate: collection.early_withdrawal_penalty_rate,
+        schedule: collection.penalty_schedule,
+        min_rate: collection.min_early_withdrawal_penalty_rate,
+    };
+
+    terms.penalty(
+        &payment_schedule(collection),
+        user_account.paid_periods,
+        amount,
+    )
+}
+
+/// Lets the emergency key pause immediately, without waiting for a vote.
+pub fn emergency_pause_handler<'info>(
+    pause_key: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+) -> Result<()> {
+    require!(
+        (collection.emergency_pause_key != Pubkey::default())
+            && (pause_key.key() == collection.emergency_pause_key),
+        RotarySavingsError::NotPauseKey
+    );
+
+    require!(!collection.is_paused, RotarySavingsError::AlreadyPaused);
+
+    collection.is_paused = true;
+
+    Ok(())
+}
+
+pub fn execute_proposal_handler<'info>(
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    user_account: &mut Account<'info, User>,
+    clock: &Clock,
+) -> Result<()> {
+    require!(!proposal.executed, RotarySavingsError::ProposalExecuted);
+
+    require!(
+        clock.unix_timestamp < proposal.expires_at,
+        RotarySavingsError::ProposalExpired
+    );
+
+    require!(
+        multisig.key() == collection.multisig,
+        RotarySavingsError::WrongMultisig
+    );
+
+    require!(
+        proposal.collection == collection.key(),
+        RotarySavingsError::ProposalMismatch
+    );
+
+    if collection.governance_mode == GOVERNANCE_MEMBERS {
+        require_member_majority(collection, proposal)?;
+    } else {
+        require_signer_majority(multisig, proposal)?;
+    }
+
+    let amount_to_withdraw;
+
+    if (proposal.proposal_type == PROPOSAL_WITHDRAW)
+        || (proposal.proposal_type == PROPOSAL_EARLY_WITHDRAW)
+    {
+        // Voting continues while paused; only the payout is held back
+        require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
+
+        require!(
+            clock.unix_timestamp >= collection.frozen_until,
+            RotarySavingsError::VaultFrozen
+        );
+
+        // Only the proposal's member can be paid
+        require!(
+            (user_account.user == proposal.withdraw_user)
+                && (user_account.collection == collection.key()),
+            RotarySavingsError::InvalidUser
+        );
+
+        // The member may have withdrawn or paid in since the proposal was made
+        require!(
+            user_account.total_paid == proposal.withdraw_amount,
+            RotarySavingsError::InvalidWithdrawAmount
+        );
+
+        if proposal.proposal_type == PROPOSAL_WITHDRAW {
+            require!(
+                user_account.can_withdraw,
+                RotarySavingsError::CannotWithdrawYet
+            );
+
+            amount_to_withdraw = proposal.withdraw_amount;
+        } else {
+            require!(
+                user_account.early_withdrawal_requested,
+                RotarySavingsError::EarlyWithdrawalNotRequested
+            );
+
+            let penalty =
+                early_withdrawal_penalty(collection, user_account, proposal.withdraw_amount);
+
+            // The penalty is shared between the other active members as
+            // dividends. Whatever cannot be split evenly, or all of it when
+            // nobody else is active, stays in the vault's balance
+            let (share, shared) = rules::dividend_split(penalty, 0, collection.active_members);
+
+            collection.dividend_per_member += share;
+            collection.dividends_outstanding += shared;
+            user_account.dividend_debt += share;
+            amount_to_withdraw = proposal.withdraw_amount - penalty;
+            collection.total_balance -= shared;
+        }
+
+        user_account.total_paid = 0;
+        user_account.can_withdraw = false;
+        user_account.early_withdrawal_requested = false;
+        collection.total_balance -= amount_to_withdraw;
+
+        {
+            let amount = amount_to_withdraw;
+
+            **collection
+                .to_account_info()
+                .try_borrow_mut_lamports()
+                .unwrap() -= amount;
+
+            **user_account
+                .to_account_info()
+                .try_borrow_mut_lamports()
+                .unwrap() += amount;
+        };
+    } else if proposal.proposal_type == PROPOSAL_CLOSE {
+        collection.is_active = false;
+    } else if proposal.proposal_type == PROPOSAL_ADJUST_SETTINGS {
+        let settings_changes = proposal.settings_changes;
+
+        // Settings may have moved since the proposal was created, so
+        // the combined result is validated again before applying it
+        validate_settings_changes(
+            collection,
+            settings_changes,
+            proposal.new_duration,
+            proposal.new_period,
+            proposal.new_amount_per_period,
+            proposal.new_early_withdrawal_penalty_rate,
+            proposal.new_penalty_schedule,
+            proposal.new_min_early_withdrawal_penalty_rate,
+        )?;
+
+        if (settings_changes & SETTING_DURATION) != 0 {
+            collection.duration = proposal.new_duration;
+        }
+
+        if (settings_changes & SETTING_PERIOD) != 0 {
+            collection.period = proposal.new_period;
+        }
+
+        if (settings_changes & SETTING_AMOUNT_PER_PERIOD) != 0 {
+            collection.amount_per_period = proposal.new_amount_per_period;
+        }
+
+        if (settings_changes & SETTING_PENALTY_RATE) != 0 {
+            collection.early_withdrawal_penalty_rate = proposal.new_early_withdrawal_penalty_rate;
+        }
+
+        if (settings_changes & SETTING_PENALTY_SCHEDULE) != 0 {
+            collection.penalty_schedule = proposal.new_penalty_schedule;
+        }
+
+        if (settings_changes & SETTING_MIN_PENALTY_RATE) != 0 {
+            collection.min_early_withdrawal_penalty_rate =
+                proposal.new_min_early_withdrawal_penalty_rate;
+        }
+    } else if proposal.proposal_type == PROPOSAL_TRANSFER_ADMIN {
+        collection.pending_admin = proposal.new_admin;
+    } else if proposal.proposal_type == PROPOSAL_PAUSE {
+        collection.is_paused = true;
+    } else if proposal.proposal_type == PROPOSAL_RESUME {
+        collection.is_paused = false;
+    } else if proposal.proposal_type == PROPOSAL_UNFREEZE {
+        collection.frozen_until = 0;
+    }
+
+    proposal.executed = true;
+
+    multisig
+        .open_proposals
+        .retain(|id| *id != proposal.proposal_id);
+
+    Ok(())
+}
+
+/// Holds back withdrawals from `execute_proposal` for up to
+/// `MAX_FREEZE_DURATION`. The guardian cannot extend a running freeze, and only
+/// a multisig proposal can lift it early. Freezes start at least
+/// `FREEZE_COOLDOWN` apart, whether or not the last one was lifted early.
+pub fn guardian_freeze_handler<'info>(
+    guardian: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    clock: &Clock,
+    duration: i64,
+) -> Result<()> {
+    require!(
+        (collection.guardian != Pubkey::default()) && (guardian.key() == collection.guardian),
+        RotarySavingsError::NotGuardian
+    );
+
+    require!(
+        (duration > 0) && (duration <= MAX_FREEZE_DURATION),
+        RotarySavingsError::InvalidFreezeDuration
+    );
+
+    require!(
+        clock.unix_timestamp >= collection.frozen_until,
+        RotarySavingsError::AlreadyFrozen
+    );
+
+    require!(
+        (collection.last_frozen_at == 0)
+            || (clock.unix_timestamp >= collection.last_frozen_at + FREEZE_COOLDOWN),
+        RotarySavingsError::FreezeCooldown
+    );
+
+    collection.frozen_until = clock.unix_timestamp + duration;
+    collection.last_frozen_at = clock.unix_timestamp;
+
+    Ok(())
+}
+
+/// Fills in a new member's account and takes their place in the payout order.
+/// The rotation clock starts when the last member joins.
+pub fn init_member<'info>(
+    collection: &mut Account<'info, Collection>,
+    user: &mut Account<'info, User>,
+    member: Pubkey,
+) -> Result<()> {
+    user.collection = collection.key();
+    user.user = member;
+    user.paid_periods = 0;
+    user.last_paid = 0;
+    user.total_paid = 0;
+    user.can_withdraw = false;
+    user.joined_at = Clock::get()?.unix_timestamp;
+    user.collateral = 0;
+    user.received_payout = false;
+    user.bid_round = 0;
+
+    // Dividends booked before the member joined are not theirs to claim
+    user.dividend_debt = collection.dividend_per_member;
+    user.dividends_claimed = 0;
+    user.delegate = Pubkey::default();
+
+    collection.payout_order.push(member);
+
+    collection.active_members += 1;
+
+    if collection.active_members == collection.total_members {
+        collection.rotation_started_at = user.joined_at;
+    }
+
+    Ok(())
+}
+
+/// Single entry point for creating proposals: validates the payload for its
+/// kind, fills in the shared fields and lists the proposal on the multisig.
+pub fn init_proposal<'info>(
+    proposer: Pubkey,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    user_account: Option<&mut Account<'info, User>>,
+    clock: &Clock,
+    proposal_id: u64,
+    args: ProposalArgs,
+) -> Result<()> {
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
+
+    require!(
+        multisig.key() == collection.multisig,
+        RotarySavingsError::WrongMultisig
+    );
+
+    validate_proposal(
+        proposer,
+        collection,
+        multisig,
+        user_account.as_deref(),
+        &args,
+    )?;
+
+    proposal.collection = collection.key();
+    proposal.proposal_type = args.proposal_type;
+    proposal.proposer = proposer;
+    proposal.created_at = clock.unix_timestamp;
+    proposal.expires_at = proposal.created_at + PROPOSAL_LIFETIME;
+    proposal.executed = false;
+    proposal.approvals = vec![false; multisig.signers.len()];
+    proposal.disapprovals = vec![false; multisig.signers.len()];
+    proposal.withdraw_user = args.withdraw_user;
+    proposal.withdraw_amount = args.withdraw_amount;
+    proposal.new_duration = args.new_duration;
+    proposal.new_period = args.new_period;
+    proposal.new_amount_per_period = args.new_amount_per_period;
+    proposal.new_early_withdrawal_penalty_rate = args.new_early_withdrawal_penalty_rate;
+    proposal.settings_changes = args.settings_changes;
+    proposal.new_penalty_schedule = args.new_penalty_schedule;
+    proposal.new_min_early_withdrawal_penalty_rate = args.new_min_early_withdrawal_penalty_rate;
+    proposal.member_approvals = 0;
+    proposal.member_disapprovals = 0;
+    proposal.new_admin = args.new_admin;
+
+    if args.proposal_type == PROPOSAL_EARLY_WITHDRAW {
+        user_account.unwrap().early_withdrawal_requested = true;
+    }
+
+    multisig.nonce += 1;
+
+    open_proposal(multisig, proposal, proposal_id)?;
+
+    Ok(())
+}
+
+/// The admin and any co-admin may run the day-to-day admin instructions.
+pub fn is_admin<'info>(collection: &Account<'info, Collection>, key: Pubkey) -> bool {
+    (key == collection.admin) || collection.co_admins.contains(&key)
+}
+
+pub fn join_collection_handler<'info>(
+    member: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user: &mut Account<'info, User>,
+    instructions_sysvar: AccountInfo<'info>,
+    proof: Vec<[u8; 32]>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
+
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
+
+    require_governance(collection)?;
+
+    require!(
+        collection.active_members < collection.total_members,
+        RotarySavingsError::CollectionFull
+    );
+
+    let allow_list_root = collection.allow_list_root;
+    let invite_signer = collection.invite_signer;
+
+    require!(
+        (allow_list_root != [0; 32]) || (invite_signer != Pubkey::default()),
+        RotarySavingsError::EnrollmentClosed
+    );
+
+    // A proof selects the allow-list; without one the join must carry an invite
+    if !proof.is_empty() || (invite_signer == Pubkey::default()) {
+        require!(
+            verify_allow_list_proof(allow_list_root, member.key(), &proof),
+            RotarySavingsError::NotOnAllowList
+        );
+    } else {
+        verify_invite(
+            &instructions_sysvar,
+            invite_signer,
+            collection.key(),
+            member.key(),
+        )?;
+    }
+
+    init_member(collection, user, member.key())?;
+
+    if collection.collateral_amount > 0 {
+        transfer_collateral_in(member, collection, user, system_program)?;
+    }
+
+    Ok(())
+}
+
+/// Rewrites a collection created by the original program, and its multisig if
+/// it has one, in the current layout. Everything added since starts where
+/// `create_collection` and `create_multisig` would start it, the creator is the
+/// admin the address was derived from, and the penalty rate is converted from
+/// percent to basis points. `payer` covers the extra rent.
+pub fn migrate_collection_handler<'info>(
+    payer: &Signer<'info>,
+    collection: &AccountInfo<'info>,
+    multisig: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let legacy = read_legacy::<LegacyCollection>(
+        collection,
+        <Collection as anchor_lang::Discriminator>::DISCRIMINATOR,
+    )?;
+
+    let mut multisig_key = Pubkey::default();
+
+    // Collections could be created without a multisig; its address is then empty
+    if multisig.owner == &crate::ID {
+        let legacy_multisig = read_legacy::<LegacyMultisig>(
+            multisig,
+            <Multisig as anchor_lang::Discriminator>::DISCRIMINATOR,
+        )?;
+
+        let signer_count = legacy_multisig.signers.len();
+
+        rewrite_legacy(
+            payer,
+            multisig,
+            system_program,
+            std::mem::size_of::<Multisig>() + 8 + MAX_OPEN_PROPOSALS * 8 + signer_count * 2 * 32,
+            &Multisig {
+                collection: legacy_multisig.collection,
+                signers: legacy_multisig.signers,
+                threshold: legacy_multisig.threshold,
+                nonce: legacy_multisig.nonce,
+                open_proposals: vec![],
+                weights: vec![],
+                weight_threshold: 0,
+                delegates: vec![Pubkey::default(); signer_count],
+                quorum: 0,
+                pass_rule: PASS_THRESHOLD,
+            },
+        )?;
+
+        multisig_key = multisig.key();
     }
 
-    pub fn store(loaded: Mutable<LoadedProposal>) {
-        let mut loaded = loaded.borrow_mut();
-        let collection = loaded.collection.clone();
+    let total_members = legacy.total_members;
+
+    rewrite_legacy(
+        payer,
+        collection,
+        system_program,
+        std::mem::size_of::<Collection>()
+            + 8
+            + MAX_NAME_LEN
+            + (total_members as usize) * 32
+            + MAX_CO_ADMINS * 32,
+        &Collection {
+            admin: legacy.admin,
+            name: legacy.name,
+            duration: legacy.duration,
+            period: legacy.period,
+            amount_per_period: legacy.amount_per_period,
+            total_members,
+            active_members: legacy.active_members,
+            total_balance: legacy.total_balance,
+            is_active: legacy.is_active,
+            early_withdrawal_penalty_rate: (legacy.early_withdrawal_penalty_rate as u16) * 100,
+            penalty_schedule: PENALTY_SCHEDULE_FLAT,
+            min_early_withdrawal_penalty_rate: 0,
+            allow_list_root: [0; 32],
+            invite_signer: Pubkey::default(),
+            collateral_amount: 0,
+            total_collateral: 0,
+            payout_order_mode: PAYOUT_NONE,
+            payout_order: vec![],
+            payout_order_finalized: false,
+            payout_seed: [0; 32],
+            current_round: 0,
+            rotation_started_at: 0,
+            bid_count: 0,
+            best_bid: 0,
+            best_bidder: Pubkey::default(),
+            dividend_per_member: 0,
+            dividends_outstanding: 0,
+            governance_mode: GOVERNANCE_MULTISIG,
+            quorum_pct: 0,
+            approval_pct: 0,
+            creator: legacy.admin,
+            pending_admin: Pubkey::default(),
+            co_admins: vec![],
+            is_paused: false,
+            emergency_pause_key: Pubkey::default(),
+            guardian: Pubkey::default(),
+            frozen_until: 0,
+            last_frozen_at: 0,
+            multisig: multisig_key,
+        },
+    )
+}
+
+/// Moves a proposal created before ids were binary-encoded from its
+/// Debug-formatted nonce address onto the little-endian id address, once its
+/// collection has been migrated. The old account is read in its original
+/// layout: the nonce becomes the id, an adjust-settings proposal changes every
+/// setting it could then, and the penalty rate is converted from percent to
+/// basis points. An unexecuted proposal is listed on the multisig, and the old
+/// account's rent goes back to its proposer.
+pub fn migrate_proposal_handler<'info>(
+    collection: &Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    legacy_proposal: &AccountInfo<'info>,
+    proposer: &AccountInfo<'info>,
+    proposal: &mut Account<'info, Proposal>,
+    legacy_nonce: u64,
+) -> Result<()> {
+    let legacy = read_legacy::<LegacyProposal>(
+        legacy_proposal,
+        <Proposal as anchor_lang::Discriminator>::DISCRIMINATOR,
+    )?;
+
+    require!(
+        multisig.key() == collection.multisig,
+        RotarySavingsError::WrongMultisig
+    );
+
+    require!(
+        legacy.collection == collection.key(),
+        RotarySavingsError::ProposalMismatch
+    );
+
+    require!(
+        proposer.key() == legacy.proposer,
+        RotarySavingsError::WrongProposer
+    );
+
+    proposal.collection = legacy.collection;
+    proposal.proposal_type = legacy.proposal_type;
+    proposal.proposer = legacy.proposer;
+    proposal.created_at = legacy.created_at;
+    proposal.expires_at = legacy.expires_at;
+    proposal.executed = legacy.executed;
+    proposal.approvals = legacy.approvals;
+    proposal.disapprovals = legacy.disapprovals;
+    proposal.withdraw_user = legacy.withdraw_user;
+    proposal.withdraw_amount = legacy.withdraw_amount;
+    proposal.new_duration = legacy.new_duration;
+    proposal.new_period = legacy.new_period;
+    proposal.new_amount_per_period = legacy.new_amount_per_period;
+    proposal.new_early_withdrawal_penalty_rate =
+        (legacy.new_early_withdrawal_penalty_rate as u16) * 100;
+
+    proposal.settings_changes = if legacy.proposal_type == PROPOSAL_ADJUST_SETTINGS {
+        SETTING_DURATION | SETTING_PERIOD | SETTING_AMOUNT_PER_PERIOD | SETTING_PENALTY_RATE
+    } else {
+        0
+    };
+
+    proposal.new_penalty_schedule = PENALTY_SCHEDULE_FLAT;
+    proposal.new_min_early_withdrawal_penalty_rate = 0;
+    proposal.proposal_id = legacy_nonce;
+    proposal.member_approvals = 0;
+    proposal.member_disapprovals = 0;
+    proposal.new_admin = Pubkey::default();
+
+    if !proposal.executed {
+        open_proposal(multisig, proposal, legacy_nonce)?;
+    }
+
+    let lamports = legacy_proposal.lamports();
+
+    **proposer.try_borrow_mut_lamports()? += lamports;
+    **legacy_proposal.try_borrow_mut_lamports()? = 0;
+
+    legacy_proposal.assign(&solana_program::system_program::ID);
+    legacy_proposal.realloc(0, false)?;
+
+    Ok(())
+}
+
+/// Rewrites a member account created by the original program in the current
+/// layout, once its collection has been migrated. The join time was not
+/// recorded then, so it stays zero. `payer` covers the extra rent.
+pub fn migrate_user_handler<'info>(
+    payer: &Signer<'info>,
+    collection: &Account<'info, Collection>,
+    user_account: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let legacy = read_legacy::<LegacyUser>(
+        user_account,
+        <User as anchor_lang::Discriminator>::DISCRIMINATOR,
+    )?;
+
+    require!(
+        legacy.collection == collection.key(),
+        RotarySavingsError::InvalidUser
+    );
+
+    rewrite_legacy(
+        payer,
+        user_account,
+        system_program,
+        std::mem::size_of::<User>() + 8,
+        &User {
+            collection: legacy.collection,
+            user: legacy.user,
+            paid_periods: legacy.paid_periods,
+            last_paid: legacy.last_paid,
+            total_paid: legacy.total_paid,
+            can_withdraw: legacy.can_withdraw,
+            early_withdrawal_requested: legacy.early_withdrawal_requested,
+            joined_at: 0,
+            collateral: 0,
+            received_payout: false,
+            bid_round: 0,
+            dividend_debt: 0,
+            dividends_claimed: 0,
+            delegate: Pubkey::default(),
+        },
+    )
+}
+
+/// Stamps a new proposal with its client-chosen id and lists it on the
+/// multisig so clients can enumerate what is still open.
+pub fn open_proposal<'info>(
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    proposal_id: u64,
+) -> Result<()> {
+    require!(
+        !multisig.open_proposals.contains(&proposal_id),
+        RotarySavingsError::DuplicateProposalId
+    );
+
+    require!(
+        multisig.open_proposals.len() < MAX_OPEN_PROPOSALS,
+        RotarySavingsError::TooManyOpenProposals
+    );
+
+    proposal.proposal_id = proposal_id;
+
+    multisig.open_proposals.push(proposal_id);
+
+    Ok(())
+}
+
+pub fn pause_collection_handler<'info>(
+    proposer: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    proposal_id: u64,
+) -> Result<()> {
+    init_proposal(
+        proposer.key(),
+        collection,
+        multisig,
+        proposal,
+        None,
+        clock,
+        proposal_id,
+        ProposalArgs {
+            proposal_type: PROPOSAL_PAUSE,
+            ..Default::default()
+        },
+    )
+}
+
+pub fn pay_handler<'info>(
+    user: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+    clock: &Clock,
+    amount: u64,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(
+        user.key() == user_account.user,
+        RotarySavingsError::InvalidUser
+    );
+
+    require!(
+        user_account.collection == collection.key(),
+        RotarySavingsError::InvalidUser
+    );
+
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
+
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
+
+    require_governance(collection)?;
+
+    require!(
+        amount == collection.amount_per_period,
+        RotarySavingsError::InvalidPaymentAmount
+    );
+
+    require!(
+        user_account.collateral >= collection.collateral_amount,
+        RotarySavingsError::CollateralRequired
+    );
+
+    let current_time = clock.unix_timestamp;
+
+    require!(
+        payment_schedule(collection).payment_due(user_account.last_paid, current_time),
+        RotarySavingsError::PaymentNotDue
+    );
+
+    user_account.paid_periods += 1;
+    user_account.last_paid = current_time;
+    user_account.total_paid += amount;
+    collection.total_balance += amount;
+
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(&user.key(), &collection.key(), amount),
+        &[
+            user.to_account_info(),
+            collection.to_account_info(),
+            system_program.to_account_info(),
+        ],
+    )?;
+
+    if payment_schedule(collection).cycle_complete(user_account.paid_periods) {
+        user_account.can_withdraw = true;
+    }
 
-        loaded.__account__.collection = collection;
+    Ok(())
+}
 
-        let proposal_type = loaded.proposal_type;
+/// Moves `amount` out of the pot to the round's recipient and advances the round.
+pub fn pay_out_round<'info>(
+    collection: &mut Account<'info, Collection>,
+    recipient_account: &mut Account<'info, User>,
+    recipient: &AccountInfo<'info>,
+    amount: u64,
+) {
+    collection.total_balance -= amount;
+    recipient_account.received_payout = true;
+    collection.current_round += 1;
 
-        loaded.__account__.proposal_type = proposal_type;
+    {
+        **collection
+            .to_account_info()
+            .try_borrow_mut_lamports()
+            .unwrap() -= amount;
 
-        let proposer = loaded.proposer.clone();
+        **recipient.try_borrow_mut_lamports().unwrap() += amount;
+    };
+}
 
-        loaded.__account__.proposer = proposer;
+pub fn payment_schedule<'info>(collection: &Account<'info, Collection>) -> rules::Schedule {
+    rules::Schedule {
+        period: collection.period,
+        duration: collection.duration,
+    }
+}
 
-        let created_at = loaded.created_at;
+pub fn place_bid_handler<'info>(
+    member: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+    clock: &Clock,
+    amount: u64,
+) -> Result<()> {
+    require!(
+        (member.key() == user_account.user) && (user_account.collection == collection.key()),
+        RotarySavingsError::InvalidUser
+    );
 
-        loaded.__account__.created_at = created_at;
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
 
-        let expires_at = loaded.expires_at;
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
 
-        loaded.__account__.expires_at = expires_at;
+    require!(
+        collection.payout_order_mode == PAYOUT_AUCTION,
+        RotarySavingsError::NotAuction
+    );
 
-        let executed = loaded.executed.clone();
+    require!(
+        collection.active_members == collection.total_members,
+        RotarySavingsError::CollectionNotFull
+    );
 
-        loaded.__account__.executed = executed;
+    let round = collection.current_round;
 
-        let approvals = loaded
-            .approvals
-            .clone()
-            .borrow()
-            .clone()
-            .into_iter()
-            .map(|element| element)
-            .collect();
+    require!(
+        round < collection.total_members,
+        RotarySavingsError::RotationComplete
+    );
 
-        loaded.__account__.approvals = approvals;
+    require!(
+        !user_account.received_payout,
+        RotarySavingsError::AlreadyReceivedPayout
+    );
 
-        let disapprovals = loaded
-            .disapprovals
-            .clone()
-            .borrow()
-            .clone()
-            .into_iter()
-            .map(|element| element)
-            .collect();
+    // bid_round holds the last round bid on plus one so zero means never
+    require!(
+        user_account.bid_round != (round + 1),
+        RotarySavingsError::AlreadyBid
+    );
 
-        loaded.__account__.disapprovals = disapprovals;
+    let (opens_at, closes_at) =
+        payment_schedule(collection).round_window(collection.rotation_started_at, round);
 
-        let withdraw_user = loaded.withdraw_user.clone();
+    require!(
+        (clock.unix_timestamp >= opens_at) && (clock.unix_timestamp < closes_at),
+        RotarySavingsError::BiddingClosed
+    );
 
-        loaded.__account__.withdraw_user = withdraw_user;
+    require!(
+        amount <= round_pot(collection),
+        RotarySavingsError::BidTooHigh
+    );
 
-        let withdraw_amount = loaded.withdraw_amount;
+    // Ties go to the earlier bid
+    if (collection.bid_count == 0) || (amount < collection.best_bid) {
+        collection.best_bid = amount;
+        collection.best_bidder = member.key();
+    }
 
-        loaded.__account__.withdraw_amount = withdraw_amount;
+    collection.bid_count += 1;
+    user_account.bid_round = round + 1;
 
-        let new_duration = loaded.new_duration;
+    Ok(())
+}
 
-        loaded.__account__.new_duration = new_duration;
+/// Records an offer to trade payout slots with another member. Any side
+/// payment is escrowed on the swap account until it is accepted or cancelled,
+/// which closes the account back to the proposer so they can offer again.
+pub fn propose_swap_handler<'info>(
+    proposer: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    proposer_account: &mut Account<'info, User>,
+    counterparty_account: &mut Account<'info, User>,
+    swap: &mut Account<'info, Swap>,
+    clock: &Clock,
+    side_payment: u64,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    require!(
+        (proposer.key() == proposer_account.user)
+            && (proposer_account.collection == collection.key())
+            && (counterparty_account.collection == collection.key()),
+        RotarySavingsError::InvalidUser
+    );
 
-        let new_period = loaded.new_period;
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
 
-        loaded.__account__.new_period = new_period;
+    // Auction slots are decided by bidding each round
+    require!(
+        (collection.payout_order_mode != PAYOUT_NONE)
+            && (collection.payout_order_mode != PAYOUT_AUCTION),
+        RotarySavingsError::SwapsUnavailable
+    );
 
-        let new_amount_per_period = loaded.new_amount_per_period;
+    require!(
+        collection.payout_order_finalized,
+        RotarySavingsError::PayoutOrderNotFinalized
+    );
 
-        loaded.__account__.new_amount_per_period = new_amount_per_period;
+    require!(
+        proposer_account.user != counterparty_account.user,
+        RotarySavingsError::SelfSwap
+    );
 
-        let new_early_withdrawal_penalty_rate = loaded.new_early_withdrawal_penalty_rate;
+    require!(
+        !proposer_account.received_payout && !counterparty_account.received_payout,
+        RotarySavingsError::AlreadyReceivedPayout
+    );
 
-        loaded.__account__.new_early_withdrawal_penalty_rate = new_early_withdrawal_penalty_rate;
+    swap.collection = collection.key();
+    swap.proposer = proposer.key();
+    swap.counterparty = counterparty_account.user;
+    swap.side_payment = side_payment;
+    swap.round = collection.current_round;
+    swap.created_at = clock.unix_timestamp;
+    swap.status = SWAP_OPEN;
+
+    if side_payment > 0 {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(
+                &proposer.key(),
+                &swap.key(),
+                side_payment,
+            ),
+            &[
+                proposer.to_account_info(),
+                swap.to_account_info(),
+                system_program.to_account_info(),
+            ],
+        )?;
     }
-}
 
-#[derive(Debug)]
-pub struct LoadedProposal<'info, 'entrypoint> {
-    pub __account__: &'entrypoint mut Box<Account<'info, Proposal>>,
-    pub __programs__: &'entrypoint ProgramsMap<'info>,
-    pub collection: Pubkey,
-    pub proposal_type: u8,
-    pub proposer: Pubkey,
-    pub created_at: i64,
-    pub expires_at: i64,
-    pub executed: bool,
-    pub approvals: Mutable<Vec<bool>>,
-    pub disapprovals: Mutable<Vec<bool>>,
-    pub withdraw_user: Pubkey,
-    pub withdraw_amount: u64,
-    pub new_duration: i64,
-    pub new_period: i64,
-    pub new_amount_per_period: u64,
-    pub new_early_withdrawal_penalty_rate: u8,
+    Ok(())
 }
 
-#[account]
-#[derive(Debug)]
-pub struct User {
-    pub collection: Pubkey,
-    pub user: Pubkey,
-    pub paid_periods: u8,
-    pub last_paid: i64,
-    pub total_paid: u64,
-    pub can_withdraw: bool,
-    pub early_withdrawal_requested: bool,
+pub fn propose_withdraw_handler<'info>(
+    user: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    proposal_id: u64,
+) -> Result<()> {
+    let withdraw_amount = user_account.total_paid;
+
+    init_proposal(
+        user.key(),
+        collection,
+        multisig,
+        proposal,
+        Some(user_account),
+        clock,
+        proposal_id,
+        ProposalArgs {
+            proposal_type: PROPOSAL_WITHDRAW,
+            withdraw_user: user.key(),
+            withdraw_amount,
+            ..Default::default()
+        },
+    )
 }
 
-impl<'info, 'entrypoint> User {
-    pub fn load(
-        account: &'entrypoint mut Box<Account<'info, Self>>,
-        programs_map: &'entrypoint ProgramsMap<'info>,
-    ) -> Mutable<LoadedUser<'info, 'entrypoint>> {
-        let collection = account.collection.clone();
-        let user = account.user.clone();
-        let paid_periods = account.paid_periods;
-        let last_paid = account.last_paid;
-        let total_paid = account.total_paid;
-        let can_withdraw = account.can_withdraw.clone();
-        let early_withdrawal_requested = account.early_withdrawal_requested.clone();
-
-        Mutable::new(LoadedUser {
-            __account__: account,
-            __programs__: programs_map,
-            collection,
-            user,
-            paid_periods,
-            last_paid,
-            total_paid,
-            can_withdraw,
-            early_withdrawal_requested,
-        })
-    }
-
-    pub fn store(loaded: Mutable<LoadedUser>) {
-        let mut loaded = loaded.borrow_mut();
-        let collection = loaded.collection.clone();
+/// Drops an expired, unexecuted proposal from the open index to free its slot.
+pub fn prune_proposal_handler<'info>(
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+) -> Result<()> {
+    require!(
+        proposal.collection == multisig.collection,
+        RotarySavingsError::ProposalMismatch
+    );
 
-        loaded.__account__.collection = collection;
+    require!(
+        clock.unix_timestamp >= proposal.expires_at,
+        RotarySavingsError::ProposalStillOpen
+    );
 
-        let user = loaded.user.clone();
+    multisig
+        .open_proposals
+        .retain(|id| *id != proposal.proposal_id);
 
-        loaded.__account__.user = user;
+    Ok(())
+}
 
-        let paid_periods = loaded.paid_periods;
+pub fn reclaim_collateral_handler<'info>(
+    member: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+) -> Result<()> {
+    require!(
+        (member.key() == user_account.user) && (user_account.collection == collection.key()),
+        RotarySavingsError::InvalidUser
+    );
 
-        loaded.__account__.paid_periods = paid_periods;
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
 
-        let last_paid = loaded.last_paid;
+    let collateral = user_account.collateral;
 
-        loaded.__account__.last_paid = last_paid;
+    require!(collateral > 0, RotarySavingsError::NoCollateral);
 
-        let total_paid = loaded.total_paid;
+    let cycle_complete = payment_schedule(collection).cycle_complete(user_account.paid_periods);
 
-        loaded.__account__.total_paid = total_paid;
+    require!(
+        cycle_complete || !collection.is_active,
+        RotarySavingsError::CollateralLocked
+    );
 
-        let can_withdraw = loaded.can_withdraw.clone();
+    user_account.collateral = 0;
+    collection.total_collateral -= collateral;
 
-        loaded.__account__.can_withdraw = can_withdraw;
+    {
+        let amount = collateral;
 
-        let early_withdrawal_requested = loaded.early_withdrawal_requested.clone();
+        **collection
+            .to_account_info()
+            .try_borrow_mut_lamports()
+            .unwrap() -= amount;
 
-        loaded.__account__.early_withdrawal_requested = early_withdrawal_requested;
-    }
-}
+        **member.to_account_info().try_borrow_mut_lamports().unwrap() += amount;
+    };
 
-#[derive(Debug)]
-pub struct LoadedUser<'info, 'entrypoint> {
-    pub __account__: &'entrypoint mut Box<Account<'info, User>>,
-    pub __programs__: &'entrypoint ProgramsMap<'info>,
-    pub collection: Pubkey,
-    pub user: Pubkey,
-    pub paid_periods: u8,
-    pub last_paid: i64,
-    pub total_paid: u64,
-    pub can_withdraw: bool,
-    pub early_withdrawal_requested: bool,
+    Ok(())
 }
 
-pub fn add_user_handler<'info>(
-    mut admin: SeahorseSigner<'info, '_>,
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut user: Empty<Mutable<LoadedUser<'info, '_>>>,
-    mut new_user: Pubkey,
-) -> () {
-    if !(admin.key() == collection.borrow().admin) {
-        panic!("Only admin can add users");
-    }
+/// Reads an account written by the original program. It carries the current
+/// type's `discriminator`, and is still the `size_of::<T>() + 8` bytes the
+/// original program allocated; every current layout is larger.
+pub fn read_legacy<T: AnchorDeserialize>(info: &AccountInfo, discriminator: [u8; 8]) -> Result<T> {
+    let data = info.try_borrow_data()?;
 
-    if !(collection.borrow().active_members < collection.borrow().total_members) {
-        panic!("Collection is full");
-    }
+    require!(
+        (data.len() >= 8) && (data[..8] == discriminator),
+        anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+    );
 
-    if !collection.borrow().is_active {
-        panic!("Collection is not active");
-    }
+    require!(
+        data.len() == std::mem::size_of::<T>() + 8,
+        RotarySavingsError::NotLegacyAccount
+    );
 
-    let mut user = user.account.clone();
+    T::deserialize(&mut &data[8..])
+        .map_err(|_| error!(anchor_lang::error::ErrorCode::AccountDidNotDeserialize))
+}
 
-    assign!(
-        user.borrow_mut().collection,
-        collection.borrow().__account__.key()
-    );
+/// Pays an escrowed side payment out of the swap account.
+pub fn release_side_payment<'info>(
+    swap: &mut Account<'info, Swap>,
+    recipient: &AccountInfo<'info>,
+) {
+    let amount = swap.side_payment;
 
-    assign!(user.borrow_mut().user, new_user);
+    if amount > 0 {
+        **swap.to_account_info().try_borrow_mut_lamports().unwrap() -= amount;
 
-    assign!(user.borrow_mut().paid_periods, 0);
+        **recipient.try_borrow_mut_lamports().unwrap() += amount;
+    }
+}
 
-    assign!(user.borrow_mut().last_paid, 0);
+/// Funds may only come in once there is a multisig to vote them back out.
+pub fn require_governance<'info>(collection: &Account<'info, Collection>) -> Result<()> {
+    require!(
+        collection.multisig != Pubkey::default(),
+        RotarySavingsError::GovernanceNotConfigured
+    );
 
-    assign!(user.borrow_mut().total_paid, 0);
+    Ok(())
+}
 
-    assign!(user.borrow_mut().can_withdraw, false);
+/// Quorum and approval are both measured against active members, so members
+/// who stay away count against a proposal.
+pub fn require_member_majority<'info>(
+    collection: &Account<'info, Collection>,
+    proposal: &Account<'info, Proposal>,
+) -> Result<()> {
+    let member_rules = rules::MemberRules {
+        quorum_pct: collection.quorum_pct,
+        approval_pct: collection.approval_pct,
+        active_members: collection.active_members,
+    };
+
+    require_verdict(member_rules.verdict(proposal.member_approvals, proposal.member_disapprovals))
+}
 
-    assign!(
-        collection.borrow_mut().active_members,
-        collection.borrow().active_members + 1
-    );
+/// Applies the multisig's quorum and pass rule to the signer votes on
+/// `proposal`.
+pub fn require_signer_majority<'info>(
+    multisig: &Account<'info, Multisig>,
+    proposal: &Account<'info, Proposal>,
+) -> Result<()> {
+    let signer_rules = rules::SignerRules {
+        quorum: multisig.quorum,
+        pass_rule: multisig.pass_rule,
+        threshold: multisig.threshold,
+        weighted: !multisig.weights.is_empty(),
+        weight_threshold: multisig.weight_threshold,
+    };
+
+    require_verdict(signer_rules.verdict(&vote_tally(multisig, proposal)))
 }
 
-pub fn adjust_settings_handler<'info>(
-    mut admin: SeahorseSigner<'info, '_>,
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut multisig: Mutable<LoadedMultisig<'info, '_>>,
-    mut proposal: Empty<Mutable<LoadedProposal<'info, '_>>>,
-    mut clock: Sysvar<'info, Clock>,
-    mut new_duration: i64,
-    mut new_period: i64,
-    mut new_amount_per_period: u64,
-) -> () {
-    if !(admin.key() == collection.borrow().admin) {
-        panic!("Only admin can propose setting adjustments");
+/// Turns a vote verdict into the instruction's result.
+pub fn require_verdict(verdict: rules::Verdict) -> Result<()> {
+    match verdict {
+        rules::Verdict::Passed => Ok(()),
+        rules::Verdict::QuorumNotReached => err!(RotarySavingsError::QuorumNotReached),
+        rules::Verdict::Rejected => err!(RotarySavingsError::ProposalRejected),
+        rules::Verdict::NotEnoughApprovals => err!(RotarySavingsError::NotEnoughApprovals),
+        rules::Verdict::NotEnoughApprovalWeight => {
+            err!(RotarySavingsError::NotEnoughApprovalWeight)
+        }
     }
+}
 
-    if !collection.borrow().is_active {
-        panic!("Collection is not active");
-    }
+/// A pause can only be lifted by the multisig, never by the pause key.
+pub fn resume_collection_handler<'info>(
+    proposer: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    proposal_id: u64,
+) -> Result<()> {
+    init_proposal(
+        proposer.key(),
+        collection,
+        multisig,
+        proposal,
+        None,
+        clock,
+        proposal_id,
+        ProposalArgs {
+            proposal_type: PROPOSAL_RESUME,
+            ..Default::default()
+        },
+    )
+}
 
-    let mut proposal = proposal.account.clone();
+pub fn revoke_delegation_handler<'info>(
+    signer: &Signer<'info>,
+    multisig: &mut Account<'info, Multisig>,
+) -> Result<()> {
+    set_signer_delegate(multisig, signer.key(), Pubkey::default())
+}
 
-    assign!(
-        proposal.borrow_mut().collection,
-        collection.borrow().__account__.key()
+pub fn revoke_member_delegation_handler<'info>(
+    member: &Signer<'info>,
+    user_account: &mut Account<'info, User>,
+) -> Result<()> {
+    require!(
+        member.key() == user_account.user,
+        RotarySavingsError::InvalidUser
     );
 
-    assign!(proposal.borrow_mut().proposal_type, 2);
+    user_account.delegate = Pubkey::default();
 
-    assign!(proposal.borrow_mut().proposer, admin.key());
+    Ok(())
+}
 
-    assign!(proposal.borrow_mut().created_at, clock.unix_timestamp);
+/// Grows an account written by the original program to `space` bytes, tops its
+/// rent up from `payer` and writes `account` over it.
+pub fn rewrite_legacy<'info, T: AccountSerialize>(
+    payer: &Signer<'info>,
+    info: &AccountInfo<'info>,
+    system_program: &Program<'info, System>,
+    space: usize,
+    account: &T,
+) -> Result<()> {
+    let shortfall = Rent::get()?
+        .minimum_balance(space)
+        .saturating_sub(info.lamports());
+
+    if shortfall > 0 {
+        solana_program::program::invoke(
+            &solana_program::system_instruction::transfer(&payer.key(), info.key, shortfall),
+            &[
+                payer.to_account_info(),
+                info.clone(),
+                system_program.to_account_info(),
+            ],
+        )?;
+    }
 
-    assign!(
-        proposal.borrow_mut().expires_at,
-        proposal.borrow().created_at + (((7 * 24) * 60) * 60)
-    );
+    info.realloc(space, true)?;
 
-    assign!(proposal.borrow_mut().executed, false);
+    account.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])
+}
 
-    let mut index = 0;
+pub fn round_pot<'info>(collection: &Account<'info, Collection>) -> u64 {
+    rules::round_pot(collection.amount_per_period, collection.total_members)
+}
 
-    for mut index in 0..(multisig.borrow().signers.borrow().len() as u64) {
-        proposal.borrow().approvals.borrow_mut().push(false);
+/// Only the admin manages co-admins; co-admins cannot appoint each other.
+pub fn set_co_admins_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    co_admins: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        admin.key() == collection.admin,
+        RotarySavingsError::NotAdmin
+    );
 
-        proposal.borrow().disapprovals.borrow_mut().push(false);
-    }
+    require!(
+        (co_admins.len() <= MAX_CO_ADMINS)
+            && co_admins.iter().enumerate().all(|(i, co_admin)| {
+                (*co_admin != Pubkey::default())
+                    && (*co_admin != collection.admin)
+                    && !co_admins[..i].contains(co_admin)
+            }),
+        RotarySavingsError::InvalidCoAdmins
+    );
 
-    assign!(proposal.borrow_mut().new_duration, new_duration);
+    collection.co_admins = co_admins;
 
-    assign!(proposal.borrow_mut().new_period, new_period);
+    Ok(())
+}
 
-    assign!(
-        proposal.borrow_mut().new_amount_per_period,
-        new_amount_per_period
+pub fn set_enrollment_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    allow_list_root: [u8; 32],
+    invite_signer: Pubkey,
+) -> Result<()> {
+    require!(
+        is_admin(collection, admin.key()),
+        RotarySavingsError::NotAdmin
     );
 
-    assign!(multisig.borrow_mut().nonce, multisig.borrow().nonce + 1);
+    collection.allow_list_root = allow_list_root;
+    collection.invite_signer = invite_signer;
+
+    Ok(())
 }
 
-pub fn close_collection_handler<'info>(
-    mut admin: SeahorseSigner<'info, '_>,
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut multisig: Mutable<LoadedMultisig<'info, '_>>,
-    mut proposal: Empty<Mutable<LoadedProposal<'info, '_>>>,
-    mut clock: Sysvar<'info, Clock>,
-) -> () {
-    if !(admin.key() == collection.borrow().admin) {
-        panic!("Only admin can propose to close collection");
-    }
+/// Only the admin appoints the guardian; a co-admin could otherwise name
+/// themselves and freeze the vault.
+pub fn set_guardian_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    guardian: Pubkey,
+) -> Result<()> {
+    require!(
+        admin.key() == collection.admin,
+        RotarySavingsError::NotAdmin
+    );
 
-    if !collection.borrow().is_active {
-        panic!("Collection is already closed");
-    }
+    collection.guardian = guardian;
 
-    let mut proposal = proposal.account.clone();
+    Ok(())
+}
 
-    assign!(
-        proposal.borrow_mut().collection,
-        collection.borrow().__account__.key()
+/// Only the admin appoints the pause key; a co-admin could otherwise name
+/// themselves and halt every member's deposits.
+pub fn set_pause_key_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    emergency_pause_key: Pubkey,
+) -> Result<()> {
+    require!(
+        admin.key() == collection.admin,
+        RotarySavingsError::NotAdmin
     );
 
-    assign!(proposal.borrow_mut().proposal_type, 1);
+    collection.emergency_pause_key = emergency_pause_key;
+
+    Ok(())
+}
 
-    assign!(proposal.borrow_mut().proposer, admin.key());
+pub fn set_payout_order_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    order: Vec<Pubkey>,
+) -> Result<()> {
+    require!(
+        is_admin(collection, admin.key()),
+        RotarySavingsError::NotAdmin
+    );
 
-    assign!(proposal.borrow_mut().created_at, clock.unix_timestamp);
+    require!(
+        collection.payout_order_mode == PAYOUT_FIXED,
+        RotarySavingsError::WrongPayoutOrderMode
+    );
 
-    assign!(
-        proposal.borrow_mut().expires_at,
-        proposal.borrow().created_at + (((7 * 24) * 60) * 60)
+    require!(
+        collection.active_members == collection.total_members,
+        RotarySavingsError::CollectionNotFull
     );
 
-    assign!(proposal.borrow_mut().executed, false);
+    require!(
+        collection.current_round == 0,
+        RotarySavingsError::PayoutOrderLocked
+    );
 
-    let mut index = 0;
+    let members = collection.payout_order.clone();
 
-    for mut index in 0..(multisig.borrow().signers.borrow().len() as u64) {
-        proposal.borrow().approvals.borrow_mut().push(false);
+    require!(
+        order.len() == members.len(),
+        RotarySavingsError::InvalidPayoutOrder
+    );
 
-        proposal.borrow().disapprovals.borrow_mut().push(false);
+    for (index, member) in order.iter().enumerate() {
+        require!(
+            members.contains(member) && !order[..index].contains(member),
+            RotarySavingsError::InvalidPayoutOrder
+        );
     }
 
-    assign!(multisig.borrow_mut().nonce, multisig.borrow().nonce + 1);
+    collection.payout_order = order;
+    collection.payout_order_finalized = true;
+
+    Ok(())
 }
 
-pub fn create_collection_handler<'info>(
-    mut admin: SeahorseSigner<'info, '_>,
-    mut collection: Empty<Mutable<LoadedCollection<'info, '_>>>,
-    mut name: String,
-    mut duration: i64,
-    mut period: i64,
-    mut amount_per_period: u64,
-    mut total_members: u8,
-    mut early_withdrawal_penalty_rate: u8,
-) -> () {
-    let mut collection = collection.account.clone();
+/// Records `delegate` as the vote holder for `signer`'s slot; the default key
+/// hands the slot back.
+pub fn set_signer_delegate<'info>(
+    multisig: &mut Account<'info, Multisig>,
+    signer: Pubkey,
+    delegate: Pubkey,
+) -> Result<()> {
+    let index = multisig
+        .signers
+        .iter()
+        .position(|signer_key| *signer_key == signer)
+        .ok_or(RotarySavingsError::NotASigner)?;
+
+    multisig.delegates[index] = delegate;
+
+    Ok(())
+}
 
-    assign!(collection.borrow_mut().admin, admin.key());
+/// Awards the pot to the lowest bid once the window closes or every member
+/// still waiting has bid. The discount is split evenly among the other
+/// members through `dividend_per_member`; the winner's share is booked as
+/// debt so it cannot be claimed. With no bids the pot goes in full to the
+/// next member in join order.
+pub fn settle_round_handler<'info>(
+    collection: &mut Account<'info, Collection>,
+    recipient_account: &mut Account<'info, User>,
+    recipient: AccountInfo<'info>,
+    clock: &Clock,
+) -> Result<()> {
+    require!(
+        collection.is_active,
+        RotarySavingsError::CollectionNotActive
+    );
 
-    assign!(collection.borrow_mut().name, name);
+    require!(!collection.is_paused, RotarySavingsError::CollectionPaused);
 
-    assign!(collection.borrow_mut().duration, duration);
+    require!(
+        collection.payout_order_mode == PAYOUT_AUCTION,
+        RotarySavingsError::NotAuction
+    );
 
-    assign!(collection.borrow_mut().period, period);
+    require!(
+        collection.active_members == collection.total_members,
+        RotarySavingsError::CollectionNotFull
+    );
 
-    assign!(collection.borrow_mut().amount_per_period, amount_per_period);
+    let round = collection.current_round;
+    let total_members = collection.total_members;
 
-    assign!(collection.borrow_mut().total_members, total_members);
+    require!(round < total_members, RotarySavingsError::RotationComplete);
 
-    assign!(collection.borrow_mut().active_members, 0);
+    let (_, closes_at) =
+        payment_schedule(collection).round_window(collection.rotation_started_at, round);
 
-    assign!(collection.borrow_mut().total_balance, 0);
+    require!(
+        (clock.unix_timestamp >= closes_at) || (collection.bid_count == (total_members - round)),
+        RotarySavingsError::BiddingOpen
+    );
 
-    assign!(collection.borrow_mut().is_active, true);
+    let pot = round_pot(collection);
 
-    assign!(
-        collection.borrow_mut().early_withdrawal_penalty_rate,
-        early_withdrawal_penalty_rate
+    require!(
+        collection.total_balance >= pot,
+        RotarySavingsError::PotNotFunded
     );
-}
 
-pub fn create_multisig_handler<'info>(
-    mut admin: SeahorseSigner<'info, '_>,
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut multisig: Empty<Mutable<LoadedMultisig<'info, '_>>>,
-    mut signers: Mutable<Vec<Pubkey>>,
-    mut threshold: u8,
-) -> () {
-    if !(admin.key() == collection.borrow().admin) {
-        panic!("Only admin can create multisig");
-    }
+    let mut order = collection.payout_order.clone();
+    let mut winner = order[round as usize];
+    let mut payout = pot;
 
-    if !((signers.borrow().len() as u64) >= (threshold as u64)) {
-        panic!("Invalid threshold");
+    if collection.bid_count > 0 {
+        winner = collection.best_bidder;
+        payout = collection.best_bid;
     }
 
-    let mut multisig = multisig.account.clone();
-
-    assign!(
-        multisig.borrow_mut().collection,
-        collection.borrow().__account__.key()
+    require!(
+        (recipient_account.user == winner)
+            && (recipient_account.collection == collection.key())
+            && (recipient.key() == winner),
+        RotarySavingsError::WrongRecipient
     );
 
-    assign!(multisig.borrow_mut().signers, signers);
+    // Move the winner into this round's slot so payout_order records who was paid when
+    let slot = order.iter().position(|member| *member == winner).unwrap();
 
-    assign!(multisig.borrow_mut().threshold, threshold);
+    order.swap(round as usize, slot);
 
-    assign!(multisig.borrow_mut().nonce, 0);
-}
+    collection.payout_order = order;
 
-pub fn create_proposal_handler<'info>(
-    mut proposer: SeahorseSigner<'info, '_>,
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut multisig: Mutable<LoadedMultisig<'info, '_>>,
-    mut proposal: Empty<Mutable<LoadedProposal<'info, '_>>>,
-    mut user_account: Mutable<LoadedUser<'info, '_>>,
-    mut clock: Sysvar<'info, Clock>,
-    mut proposal_type: u8,
-    mut withdraw_user: Pubkey,
-    mut withdraw_amount: u64,
-    mut new_duration: i64,
-    mut new_period: i64,
-    mut new_amount_per_period: u64,
-    mut new_early_withdrawal_penalty_rate: u8,
-) -> () {
-    if !collection.borrow().is_active {
-        panic!("Collection is not active");
-    }
+    let (share, shared) = rules::dividend_split(pot, payout, total_members);
 
-    if proposal_type == 0 {
-        if !user_account.borrow().can_withdraw {
-            panic!("User cannot withdraw yet");
-        }
-    } else {
-        if proposal_type == 1 {
-            if !(proposer.key() == collection.borrow().admin) {
-                panic!("Only admin can propose to close collection");
-            }
-        } else {
-            if proposal_type == 2 {
-                if !(proposer.key() == collection.borrow().admin) {
-                    panic!("Only admin can propose setting adjustments");
-                }
-            } else {
-                if proposal_type == 3 {
-                    if !(!user_account.borrow().can_withdraw) {
-                        panic!("User is eligible for regular withdrawal");
-                    }
-
-                    if !(!user_account.borrow().early_withdrawal_requested) {
-                        panic!("Early withdrawal already requested");
-                    }
-                } else {
-                    if !false {
-                        panic!("Invalid proposal type");
-                    }
-                }
-            }
-        }
-    }
+    collection.dividend_per_member += share;
+    collection.dividends_outstanding += shared;
+    recipient_account.dividend_debt += share;
 
-    let mut proposal = proposal.account.clone();
+    // Dividends stay in the vault until claimed; any rounding remainder stays in the pot
+    collection.total_balance -= shared;
+    collection.bid_count = 0;
+    collection.best_bid = 0;
+    collection.best_bidder = Pubkey::default();
 
-    assign!(
-        proposal.borrow_mut().collection,
-        collection.borrow().__account__.key()
-    );
+    pay_out_round(collection, recipient_account, &recipient, payout);
 
-    assign!(proposal.borrow_mut().proposal_type, proposal_type);
+    Ok(())
+}
+
+/// Fisher-Yates shuffle of the members seeded from the most recent slot hash.
+/// The seed is stored so anyone can replay the shuffle off-chain.
+pub fn shuffle_payout_order_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    slot_hashes_sysvar: AccountInfo<'info>,
+) -> Result<()> {
+    require!(
+        is_admin(collection, admin.key()),
+        RotarySavingsError::NotAdmin
+    );
 
-    assign!(proposal.borrow_mut().proposer, proposer.key());
+    require!(
+        collection.payout_order_mode == PAYOUT_RANDOM,
+        RotarySavingsError::WrongPayoutOrderMode
+    );
 
-    assign!(proposal.borrow_mut().created_at, clock.unix_timestamp);
+    require!(
+        collection.active_members == collection.total_members,
+        RotarySavingsError::CollectionNotFull
+    );
 
-    assign!(
-        proposal.borrow_mut().expires_at,
-        proposal.borrow().created_at + (((7 * 24) * 60) * 60)
+    require!(
+        !collection.payout_order_finalized,
+        RotarySavingsError::PayoutOrderLocked
     );
 
-    assign!(proposal.borrow_mut().executed, false);
+    // SlotHashes is too large to deserialize; the newest hash follows the
+    // u64 entry count and the first entry's u64 slot
+    let mut recent_hash = [0u8; 32];
 
-    let mut index = 0;
+    recent_hash.copy_from_slice(&slot_hashes_sysvar.try_borrow_data()?[16..48]);
 
-    for mut index in 0..(multisig.borrow().signers.borrow().len() as u64) {
-        proposal.borrow().approvals.borrow_mut().push(false);
+    let seed = hashv(&[&recent_hash, collection.key().as_ref()]).to_bytes();
 
-        proposal.borrow().disapprovals.borrow_mut().push(false);
-    }
+    collection.payout_seed = seed;
 
-    assign!(proposal.borrow_mut().withdraw_user, withdraw_user);
+    let mut order = collection.payout_order.clone();
 
-    assign!(proposal.borrow_mut().withdraw_amount, withdraw_amount);
+    rules::shuffle(&mut order, seed);
 
-    assign!(proposal.borrow_mut().new_duration, new_duration);
+    collection.payout_order = order;
+    collection.payout_order_finalized = true;
 
-    assign!(proposal.borrow_mut().new_period, new_period);
+    Ok(())
+}
 
-    assign!(
-        proposal.borrow_mut().new_amount_per_period,
-        new_amount_per_period
+/// Takes a member's collateral once they have missed a full period, measured
+/// from their last payment (or from joining if they never paid). It is shared
+/// between the other members as dividends, which they claim out of the vault.
+pub fn slash_collateral_handler<'info>(
+    admin: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+    clock: &Clock,
+) -> Result<()> {
+    require!(
+        is_admin(collection, admin.key()),
+        RotarySavingsError::NotAdmin
     );
 
-    assign!(
-        proposal.borrow_mut().new_early_withdrawal_penalty_rate,
-        new_early_withdrawal_penalty_rate
+    require!(
+        user_account.collection == collection.key(),
+        RotarySavingsError::InvalidUser
     );
 
-    if proposal_type == 3 {
-        assign!(user_account.borrow_mut().early_withdrawal_requested, true);
-    }
+    let collateral = user_account.collateral;
 
-    assign!(multisig.borrow_mut().nonce, multisig.borrow().nonce + 1);
-}
+    require!(collateral > 0, RotarySavingsError::NoCollateral);
 
-pub fn execute_proposal_handler<'info>(
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut multisig: Mutable<LoadedMultisig<'info, '_>>,
-    mut proposal: Mutable<LoadedProposal<'info, '_>>,
-    mut user_account: Mutable<LoadedUser<'info, '_>>,
-    mut clock: Sysvar<'info, Clock>,
-) -> () {
-    if !(!proposal.borrow().executed) {
-        panic!("Proposal already executed");
-    }
+    require!(
+        payment_schedule(collection).in_default(
+            user_account.paid_periods,
+            user_account.last_paid,
+            user_account.joined_at,
+            clock.unix_timestamp
+        ),
+        RotarySavingsError::MemberNotInDefault
+    );
 
-    if !(clock.unix_timestamp < proposal.borrow().expires_at) {
-        panic!("Proposal expired");
-    }
+    // The lamports already sit in the vault; only the bookkeeping moves. The
+    // defaulter's own share is booked as debt and any rounding remainder
+    // stays in the pot
+    let (share, shared) = rules::dividend_split(collateral, 0, collection.active_members);
 
-    let mut approval_count = 0;
+    user_account.collateral = 0;
+    user_account.dividend_debt += share;
+    collection.total_collateral -= collateral;
+    collection.dividend_per_member += share;
+    collection.dividends_outstanding += shared;
+    collection.total_balance += collateral - shared;
 
-    for mut approved in proposal
-        .borrow()
-        .approvals
-        .borrow()
-        .iter()
-        .map(|elem| elem.clone())
-    {
-        if approved {
-            assign!(approval_count, approval_count + 1);
-        }
-    }
+    Ok(())
+}
 
-    if !(approval_count >= multisig.borrow().threshold) {
-        panic!("Not enough approvals");
-    }
+/// First step of an admin transfer: once the proposal passes, `new_admin`
+/// becomes the pending admin and must call `accept_admin`.
+pub fn transfer_admin_handler<'info>(
+    proposer: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    proposal_id: u64,
+    new_admin: Pubkey,
+) -> Result<()> {
+    init_proposal(
+        proposer.key(),
+        collection,
+        multisig,
+        proposal,
+        None,
+        clock,
+        proposal_id,
+        ProposalArgs {
+            proposal_type: PROPOSAL_TRANSFER_ADMIN,
+            new_admin,
+            ..Default::default()
+        },
+    )
+}
 
-    let mut amount_to_withdraw = 0;
+pub fn transfer_collateral_in<'info>(
+    member: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    user_account: &mut Account<'info, User>,
+    system_program: &Program<'info, System>,
+) -> Result<()> {
+    let collateral_amount = collection.collateral_amount;
+
+    solana_program::program::invoke(
+        &solana_program::system_instruction::transfer(
+            &member.key(),
+            &collection.key(),
+            collateral_amount,
+        ),
+        &[
+            member.to_account_info(),
+            collection.to_account_info(),
+            system_program.to_account_info(),
+        ],
+    )?;
+
+    user_account.collateral = collateral_amount;
+    collection.total_collateral += collateral_amount;
+
+    Ok(())
+}
 
-    if (proposal.borrow().proposal_type == 0) || (proposal.borrow().proposal_type == 3) {
-        if proposal.borrow().proposal_type == 0 {
-            if !user_account.borrow().can_withdraw {
-                panic!("User cannot withdraw");
-            }
+pub fn unfreeze_vault_handler<'info>(
+    proposer: &Signer<'info>,
+    collection: &mut Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    proposal_id: u64,
+) -> Result<()> {
+    init_proposal(
+        proposer.key(),
+        collection,
+        multisig,
+        proposal,
+        None,
+        clock,
+        proposal_id,
+        ProposalArgs {
+            proposal_type: PROPOSAL_UNFREEZE,
+            ..Default::default()
+        },
+    )
+}
 
-            amount_to_withdraw = proposal.borrow().withdraw_amount;
-        } else {
-            if !user_account.borrow().early_withdrawal_requested {
-                panic!("Early withdrawal not requested");
-            }
+pub fn validate_collection_settings(
+    duration: i64,
+    period: i64,
+    amount_per_period: u64,
+    early_withdrawal_penalty_rate: u16,
+    penalty_schedule: u8,
+    min_early_withdrawal_penalty_rate: u16,
+) -> Result<()> {
+    require!(duration > 0, RotarySavingsError::InvalidDuration);
+
+    require!(period > 0, RotarySavingsError::InvalidPeriod);
+
+    require!(
+        (duration % period) == 0,
+        RotarySavingsError::DurationNotMultipleOfPeriod
+    );
 
-            let mut penalty = (proposal.borrow().withdraw_amount
-                * (collection.borrow().early_withdrawal_penalty_rate as u64))
-                / 100;
+    require!(
+        amount_per_period > 0,
+        RotarySavingsError::InvalidAmountPerPeriod
+    );
 
-            amount_to_withdraw = proposal.borrow().withdraw_amount - penalty;
+    require!(
+        early_withdrawal_penalty_rate <= BPS_DENOMINATOR,
+        RotarySavingsError::PenaltyRateTooHigh
+    );
 
-            assign!(
-                collection.borrow_mut().total_balance,
-                collection.borrow().total_balance - penalty
-            );
-        }
+    require!(
+        penalty_schedule <= PENALTY_SCHEDULE_LINEAR,
+        RotarySavingsError::InvalidPenaltySchedule
+    );
 
-        assign!(user_account.borrow_mut().total_paid, 0);
+    require!(
+        min_early_withdrawal_penalty_rate <= early_withdrawal_penalty_rate,
+        RotarySavingsError::MinPenaltyRateTooHigh
+    );
 
-        assign!(user_account.borrow_mut().can_withdraw, false);
+    Ok(())
+}
 
-        assign!(user_account.borrow_mut().early_withdrawal_requested, false);
+pub fn validate_governance(governance_mode: u8, quorum_pct: u8, approval_pct: u8) -> Result<()> {
+    require!(
+        governance_mode <= GOVERNANCE_MEMBERS,
+        RotarySavingsError::InvalidGovernance
+    );
 
-        assign!(
-            collection.borrow_mut().total_balance,
-            collection.borrow().total_balance - amount_to_withdraw
+    if governance_mode == GOVERNANCE_MEMBERS {
+        require!(
+            (quorum_pct <= 100) && (approval_pct > 0) && (approval_pct <= 100),
+            RotarySavingsError::InvalidGovernance
         );
+    }
 
-        {
-            let amount = amount_to_withdraw.clone();
+    Ok(())
+}
 
-            **collection
-                .borrow()
-                .__account__
-                .to_account_info()
-                .try_borrow_mut_lamports()
-                .unwrap() -= amount;
+pub fn validate_proposal<'info>(
+    proposer: Pubkey,
+    collection: &Account<'info, Collection>,
+    multisig: &Account<'info, Multisig>,
+    user_account: Option<&Account<'info, User>>,
+    args: &ProposalArgs,
+) -> Result<()> {
+    match args.proposal_type {
+        PROPOSAL_WITHDRAW | PROPOSAL_EARLY_WITHDRAW => {
+            let user_account = user_account.ok_or(RotarySavingsError::MissingUserAccount)?;
+
+            // Only the member can ask to withdraw their own savings
+            require!(
+                (proposer == user_account.user)
+                    && (args.withdraw_user == user_account.user)
+                    && (user_account.collection == collection.key()),
+                RotarySavingsError::InvalidUser
+            );
 
-            **user_account
-                .borrow()
-                .__account__
-                .to_account_info()
-                .try_borrow_mut_lamports()
-                .unwrap() += amount;
-        };
-    } else {
-        if proposal.borrow().proposal_type == 1 {
-            assign!(collection.borrow_mut().is_active, false);
-        } else {
-            if proposal.borrow().proposal_type == 2 {
-                assign!(
-                    collection.borrow_mut().duration,
-                    proposal.borrow().new_duration
-                );
+            require!(
+                args.withdraw_amount == user_account.total_paid,
+                RotarySavingsError::InvalidWithdrawAmount
+            );
+
+            require!(
+                collection.payout_order_mode == PAYOUT_NONE,
+                RotarySavingsError::RotatingCollection
+            );
 
-                assign!(collection.borrow_mut().period, proposal.borrow().new_period);
+            if args.proposal_type == PROPOSAL_WITHDRAW {
+                require!(
+                    user_account.can_withdraw,
+                    RotarySavingsError::CannotWithdrawYet
+                );
+            } else {
+                require!(
+                    !user_account.can_withdraw,
+                    RotarySavingsError::EligibleForRegularWithdrawal
+                );
 
-                assign!(
-                    collection.borrow_mut().amount_per_period,
-                    proposal.borrow().new_amount_per_period
+                require!(
+                    !user_account.early_withdrawal_requested,
+                    RotarySavingsError::EarlyWithdrawalAlreadyRequested
                 );
+            }
+        }
+        PROPOSAL_CLOSE | PROPOSAL_ADJUST_SETTINGS => {
+            require!(is_admin(collection, proposer), RotarySavingsError::NotAdmin);
+
+            if args.proposal_type == PROPOSAL_ADJUST_SETTINGS {
+                validate_settings_changes(
+                    collection,
+                    args.settings_changes,
+                    args.new_duration,
+                    args.new_period,
+                    args.new_amount_per_period,
+                    args.new_early_withdrawal_penalty_rate,
+                    args.new_penalty_schedule,
+                    args.new_min_early_withdrawal_penalty_rate,
+                )?;
+            }
+        }
+        PROPOSAL_TRANSFER_ADMIN | PROPOSAL_PAUSE | PROPOSAL_RESUME | PROPOSAL_UNFREEZE => {
+            // Signers can propose these too, so a lost or compromised admin
+            // key can be worked around
+            require!(
+                is_admin(collection, proposer) || multisig.signers.contains(&proposer),
+                RotarySavingsError::NotAdmin
+            );
 
-                assign!(
-                    collection.borrow_mut().early_withdrawal_penalty_rate,
-                    proposal.borrow().new_early_withdrawal_penalty_rate
+            if args.proposal_type == PROPOSAL_TRANSFER_ADMIN {
+                require!(
+                    (args.new_admin != Pubkey::default()) && (args.new_admin != collection.admin),
+                    RotarySavingsError::InvalidNewAdmin
                 );
+            } else if args.proposal_type == PROPOSAL_PAUSE {
+                require!(!collection.is_paused, RotarySavingsError::AlreadyPaused);
+            } else if args.proposal_type == PROPOSAL_RESUME {
+                require!(collection.is_paused, RotarySavingsError::NotPaused);
             }
         }
+        _ => return err!(RotarySavingsError::InvalidProposalType),
     }
 
-    assign!(proposal.borrow_mut().executed, true);
+    Ok(())
 }
 
-pub fn pay_handler<'info>(
-    mut user: SeahorseSigner<'info, '_>,
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut user_account: Mutable<LoadedUser<'info, '_>>,
-    mut clock: Sysvar<'info, Clock>,
-    mut amount: u64,
-) -> () {
-    if !(user.key() == user_account.borrow().user) {
-        panic!("Invalid user");
+pub fn validate_rotation(
+    payout_order_mode: u8,
+    duration: i64,
+    period: i64,
+    total_members: u8,
+) -> Result<()> {
+    if payout_order_mode != PAYOUT_NONE {
+        require!(
+            (duration / period) == (total_members as i64),
+            RotarySavingsError::RoundsMustMatchMembers
+        );
     }
 
-    if !collection.borrow().is_active {
-        panic!("Collection is not active");
-    }
+    Ok(())
+}
 
-    if !(amount == collection.borrow().amount_per_period) {
-        panic!("Invalid payment amount");
-    }
+pub fn validate_settings_changes<'info>(
+    collection: &Account<'info, Collection>,
+    settings_changes: u8,
+    new_duration: i64,
+    new_period: i64,
+    new_amount_per_period: u64,
+    new_early_withdrawal_penalty_rate: u16,
+    new_penalty_schedule: u8,
+    new_min_early_withdrawal_penalty_rate: u16,
+) -> Result<()> {
+    require!(settings_changes != 0, RotarySavingsError::NoSettingsChanges);
+
+    require!(
+        (settings_changes & !ALL_SETTINGS) == 0,
+        RotarySavingsError::InvalidSettingsChanges
+    );
 
-    let mut current_time = clock.unix_timestamp;
+    let mut duration = collection.duration;
 
-    if !((current_time - user_account.borrow().last_paid) >= collection.borrow().period) {
-        panic!("Too early for next payment");
+    if (settings_changes & SETTING_DURATION) != 0 {
+        duration = new_duration;
     }
 
-    assign!(
-        user_account.borrow_mut().paid_periods,
-        user_account.borrow().paid_periods + 1
-    );
-
-    assign!(user_account.borrow_mut().last_paid, current_time);
+    let mut period = collection.period;
 
-    assign!(
-        user_account.borrow_mut().total_paid,
-        user_account.borrow().total_paid + amount
-    );
+    if (settings_changes & SETTING_PERIOD) != 0 {
+        period = new_period;
+    }
 
-    assign!(
-        collection.borrow_mut().total_balance,
-        collection.borrow().total_balance + amount
-    );
+    let mut amount_per_period = collection.amount_per_period;
 
-    if ((user_account.borrow().paid_periods as i64) * collection.borrow().period)
-        >= collection.borrow().duration
-    {
-        assign!(user_account.borrow_mut().can_withdraw, true);
+    if (settings_changes & SETTING_AMOUNT_PER_PERIOD) != 0 {
+        amount_per_period = new_amount_per_period;
     }
-}
 
-pub fn propose_withdraw_handler<'info>(
-    mut user: SeahorseSigner<'info, '_>,
-    mut collection: Mutable<LoadedCollection<'info, '_>>,
-    mut user_account: Mutable<LoadedUser<'info, '_>>,
-    mut multisig: Mutable<LoadedMultisig<'info, '_>>,
-    mut proposal: Empty<Mutable<LoadedProposal<'info, '_>>>,
-    mut clock: Sysvar<'info, Clock>,
-) -> () {
-    if !(user.key() == user_account.borrow().user) {
-        panic!("Invalid user");
-    }
+    let mut early_withdrawal_penalty_rate = collection.early_withdrawal_penalty_rate;
 
-    if !collection.borrow().is_active {
-        panic!("Collection is not active");
+    if (settings_changes & SETTING_PENALTY_RATE) != 0 {
+        early_withdrawal_penalty_rate = new_early_withdrawal_penalty_rate;
     }
 
-    if !user_account.borrow().can_withdraw {
-        panic!("User cannot withdraw yet");
+    let mut penalty_schedule = collection.penalty_schedule;
+
+    if (settings_changes & SETTING_PENALTY_SCHEDULE) != 0 {
+        penalty_schedule = new_penalty_schedule;
     }
 
-    let mut proposal = proposal.account.clone();
+    let mut min_early_withdrawal_penalty_rate = collection.min_early_withdrawal_penalty_rate;
 
-    assign!(
-        proposal.borrow_mut().collection,
-        collection.borrow().__account__.key()
-    );
+    if (settings_changes & SETTING_MIN_PENALTY_RATE) != 0 {
+        min_early_withdrawal_penalty_rate = new_min_early_withdrawal_penalty_rate;
+    }
+
+    validate_collection_settings(
+        duration,
+        period,
+        amount_per_period,
+        early_withdrawal_penalty_rate,
+        penalty_schedule,
+        min_early_withdrawal_penalty_rate,
+    )?;
+
+    validate_rotation(
+        collection.payout_order_mode,
+        duration,
+        period,
+        collection.total_members,
+    )
+}
 
-    assign!(proposal.borrow_mut().proposal_type, 0);
+/// Walks a sorted-pair sha256 Merkle proof from the member's leaf up to `root`.
+pub fn verify_allow_list_proof(root: [u8; 32], member: Pubkey, proof: &[[u8; 32]]) -> bool {
+    let mut node = hashv(&[member.as_ref()]).to_bytes();
 
-    assign!(proposal.borrow_mut().proposer, user.key());
+    for sibling in proof.iter() {
+        if node <= *sibling {
+            node = hashv(&[&node, sibling]).to_bytes();
+        } else {
+            node = hashv(&[sibling, &node]).to_bytes();
+        }
+    }
 
-    assign!(proposal.borrow_mut().created_at, clock.unix_timestamp);
+    node == root
+}
 
-    assign!(
-        proposal.borrow_mut().expires_at,
-        proposal.borrow().created_at + (((7 * 24) * 60) * 60)
+/// Checks that the instruction preceding this one is an ed25519 signature
+/// check by `invite_signer` over `collection || member`. The signature itself
+/// is verified by the ed25519 program; this only inspects what was signed.
+pub fn verify_invite(
+    instructions_sysvar: &AccountInfo,
+    invite_signer: Pubkey,
+    collection: Pubkey,
+    member: Pubkey,
+) -> Result<()> {
+    let ix = instructions::get_instruction_relative(-1, instructions_sysvar)
+        .map_err(|_| error!(RotarySavingsError::MissingInvite))?;
+
+    require!(
+        ix.program_id == ed25519_program::ID,
+        RotarySavingsError::MissingInvite
     );
 
-    assign!(proposal.borrow_mut().executed, false);
+    // Layout: [count: u8, padding: u8, offsets: 7 x u16, ...payload]
+    let data = &ix.data;
 
-    let mut index = 0;
+    require!(
+        (data.len() >= 16) && (data[0] == 1),
+        RotarySavingsError::InvalidInvite
+    );
 
-    for mut index in 0..(multisig.borrow().signers.borrow().len() as u64) {
-        proposal.borrow().approvals.borrow_mut().push(false);
+    let read_u16 = |at: usize| u16::from_le_bytes([data[at], data[at + 1]]) as usize;
+    let public_key_offset = read_u16(6);
+    let message_offset = read_u16(10);
+    let message_size = read_u16(12);
+
+    // Every offset must point into this instruction's own data
+    require!(
+        (read_u16(4) == (u16::MAX as usize))
+            && (read_u16(8) == (u16::MAX as usize))
+            && (read_u16(14) == (u16::MAX as usize)),
+        RotarySavingsError::InvalidInvite
+    );
 
-        proposal.borrow().disapprovals.borrow_mut().push(false);
-    }
+    require!(
+        (data.len() >= (public_key_offset + 32)) && (data.len() >= (message_offset + message_size)),
+        RotarySavingsError::InvalidInvite
+    );
+
+    let mut expected_message = collection.to_bytes().to_vec();
 
-    assign!(proposal.borrow_mut().withdraw_user, user.key());
+    expected_message.extend_from_slice(member.as_ref());
 
-    assign!(
-        proposal.borrow_mut().withdraw_amount,
-        user_account.borrow().total_paid
+    require!(
+        (&data[public_key_offset..(public_key_offset + 32)] == invite_signer.as_ref())
+            && (data[message_offset..(message_offset + message_size)] == expected_message[..]),
+        RotarySavingsError::InvalidInvite
     );
 
-    assign!(multisig.borrow_mut().nonce, multisig.borrow().nonce + 1);
+    Ok(())
 }
 
 pub fn vote_on_proposal_handler<'info>(
-    mut signer: SeahorseSigner<'info, '_>,
-    mut multisig: Mutable<LoadedMultisig<'info, '_>>,
-    mut proposal: Mutable<LoadedProposal<'info, '_>>,
-    mut clock: Sysvar<'info, Clock>,
-    mut approve: bool,
-) -> () {
-    let mut is_valid_signer = false;
-
-    for mut valid_signer in multisig
-        .borrow()
-        .signers
-        .borrow()
-        .iter()
-        .map(|elem| elem.clone())
-    {
-        if valid_signer == signer.key() {
-            is_valid_signer = true;
+    signer: &Signer<'info>,
+    collection: &Account<'info, Collection>,
+    multisig: &mut Account<'info, Multisig>,
+    proposal: &mut Account<'info, Proposal>,
+    clock: &Clock,
+    approve: bool,
+) -> Result<()> {
+    require!(
+        multisig.key() == collection.multisig,
+        RotarySavingsError::WrongMultisig
+    );
 
-            break;
-        }
-    }
+    require!(
+        (proposal.collection == collection.key()) && (multisig.collection == collection.key()),
+        RotarySavingsError::ProposalMismatch
+    );
 
-    if !is_valid_signer {
-        panic!("Not a valid multisig signer");
-    }
+    // A signer votes in their own slot and in the slot of every signer who
+    // delegated to them
+    let voting_slots = multisig
+        .signers
+        .iter()
+        .zip(multisig.delegates.iter())
+        .enumerate()
+        .filter(|(_, (signer_key, delegate))| {
+            (**signer_key == signer.key()) || (**delegate == signer.key())
+        })
+        .map(|(index, _)| index)
+        .collect::<Vec<usize>>();
 
-    if !(!proposal.borrow().executed) {
-        panic!("Proposal already executed");
-    }
+    require!(!voting_slots.is_empty(), RotarySavingsError::NotASigner);
 
-    if !(clock.unix_timestamp < proposal.borrow().expires_at) {
-        panic!("Proposal expired");
-    }
+    require!(!proposal.executed, RotarySavingsError::ProposalExecuted);
 
-    let mut signer_index = 0;
-    let mut index = 0;
+    require!(
+        clock.unix_timestamp < proposal.expires_at,
+        RotarySavingsError::ProposalExpired
+    );
 
-    for mut index in 0..(multisig.borrow().signers.borrow().len() as u64) {
-        if (*multisig
-            .borrow()
-            .signers
-            .borrow()
-            .index_wrapped(signer_index.into()))
-            == signer.key()
-        {
-            break;
-        }
+    for index in voting_slots {
+        require!(
+            (index < proposal.approvals.len()) && (index < proposal.disapprovals.len()),
+            RotarySavingsError::VoteSlotOutOfRange
+        );
 
-        assign!(signer_index, signer_index + 1);
+        proposal.approvals[index] = approve;
+        proposal.disapprovals[index] = !approve;
     }
 
-    if approve {
-        assign!(
-            (*proposal
-                .borrow_mut()
-                .approvals
-                .borrow_mut()
-                .index_wrapped_mut(signer_index.into())),
-            true
-        );
-
-        assign!(
-            (*proposal
-                .borrow_mut()
-                .disapprovals
-                .borrow_mut()
-                .index_wrapped_mut(signer_index.into())),
-            false
-        );
-    } else {
-        assign!(
-            (*proposal
-                .borrow_mut()
-                .approvals
-                .borrow_mut()
-                .index_wrapped_mut(signer_index.into())),
-            false
-        );
+    Ok(())
+}
 
-        assign!(
-            (*proposal
-                .borrow_mut()
-                .disapprovals
-                .borrow_mut()
-                .index_wrapped_mut(signer_index.into())),
-            true
-        );
-    }
+/// Tallies the signer votes on `proposal`.
+pub fn vote_tally<'info>(
+    multisig: &Account<'info, Multisig>,
+    proposal: &Account<'info, Proposal>,
+) -> rules::Tally {
+    rules::Tally::signers(
+        &multisig.weights,
+        &proposal.approvals,
+        &proposal.disapprovals,
+    )
 }
@@ -1,228 +1,57 @@
-#![allow(unused_imports)]
-#![allow(unused_variables)]
-#![allow(unused_mut)]
-
+#[cfg(feature = "client")]
+pub mod client;
 pub mod dot;
+pub mod rules;
+pub mod seeds;
 
 use anchor_lang::prelude::*;
-use anchor_spl::{
-    associated_token::{self, AssociatedToken},
-    token::{self, Mint, Token, TokenAccount},
-};
 
 use dot::program::*;
-use std::{cell::RefCell, rc::Rc};
 
 declare_id!("Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS");
 
-pub mod seahorse_util {
+#[program]
+mod rotary_savings {
     use super::*;
-    use std::{
-        collections::HashMap,
-        fmt::Debug,
-        ops::{Deref, Index, IndexMut},
-    };
-
-    pub struct Mutable<T>(Rc<RefCell<T>>);
-
-    impl<T> Mutable<T> {
-        pub fn new(obj: T) -> Self {
-            Self(Rc::new(RefCell::new(obj)))
-        }
-    }
-
-    impl<T> Clone for Mutable<T> {
-        fn clone(&self) -> Self {
-            Self(self.0.clone())
-        }
-    }
-
-    impl<T> Deref for Mutable<T> {
-        type Target = Rc<RefCell<T>>;
-
-        fn deref(&self) -> &Self::Target {
-            &self.0
-        }
-    }
-
-    impl<T: Debug> Debug for Mutable<T> {
-        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-            write!(f, "{:?}", self.0)
-        }
-    }
-
-    impl<T: Default> Default for Mutable<T> {
-        fn default() -> Self {
-            Self::new(T::default())
-        }
-    }
-
-    pub trait IndexWrapped {
-        type Output;
-
-        fn index_wrapped(&self, index: i128) -> &Self::Output;
-    }
-
-    pub trait IndexWrappedMut: IndexWrapped {
-        fn index_wrapped_mut(&mut self, index: i128) -> &mut <Self as IndexWrapped>::Output;
-    }
-
-    impl<T> IndexWrapped for Vec<T> {
-        type Output = T;
-
-        fn index_wrapped(&self, mut index: i128) -> &Self::Output {
-            if index < 0 {
-                index += self.len() as i128;
-            }
-
-            let index: usize = index.try_into().unwrap();
-
-            self.index(index)
-        }
-    }
-
-    impl<T> IndexWrappedMut for Vec<T> {
-        fn index_wrapped_mut(&mut self, mut index: i128) -> &mut <Self as IndexWrapped>::Output {
-            if index < 0 {
-                index += self.len() as i128;
-            }
-
-            let index: usize = index.try_into().unwrap();
-
-            self.index_mut(index)
-        }
-    }
-
-    impl<T, const N: usize> IndexWrapped for [T; N] {
-        type Output = T;
-
-        fn index_wrapped(&self, mut index: i128) -> &Self::Output {
-            if index < 0 {
-                index += N as i128;
-            }
-
-            let index: usize = index.try_into().unwrap();
-
-            self.index(index)
-        }
-    }
-
-    impl<T, const N: usize> IndexWrappedMut for [T; N] {
-        fn index_wrapped_mut(&mut self, mut index: i128) -> &mut <Self as IndexWrapped>::Output {
-            if index < 0 {
-                index += N as i128;
-            }
-
-            let index: usize = index.try_into().unwrap();
-
-            self.index_mut(index)
-        }
-    }
-
-    #[derive(Clone)]
-    pub struct Empty<T: Clone> {
-        pub account: T,
-        pub bump: Option<u8>,
-    }
-
-    #[derive(Clone, Debug)]
-    pub struct ProgramsMap<'info>(pub HashMap<&'static str, AccountInfo<'info>>);
-
-    impl<'info> ProgramsMap<'info> {
-        pub fn get(&self, name: &'static str) -> AccountInfo<'info> {
-            self.0.get(name).unwrap().clone()
-        }
-    }
-
-    #[derive(Clone, Debug)]
-    pub struct WithPrograms<'info, 'entrypoint, A> {
-        pub account: &'entrypoint A,
-        pub programs: &'entrypoint ProgramsMap<'info>,
-    }
-
-    impl<'info, 'entrypoint, A> Deref for WithPrograms<'info, 'entrypoint, A> {
-        type Target = A;
-
-        fn deref(&self) -> &Self::Target {
-            &self.account
-        }
-    }
-
-    pub type SeahorseAccount<'info, 'entrypoint, A> =
-        WithPrograms<'info, 'entrypoint, Box<Account<'info, A>>>;
-
-    pub type SeahorseSigner<'info, 'entrypoint> = WithPrograms<'info, 'entrypoint, Signer<'info>>;
-
-    #[derive(Clone, Debug)]
-    pub struct CpiAccount<'info> {
-        #[doc = "CHECK: CpiAccounts temporarily store AccountInfos."]
-        pub account_info: AccountInfo<'info>,
-        pub is_writable: bool,
-        pub is_signer: bool,
-        pub seeds: Option<Vec<Vec<u8>>>,
-    }
-
-    #[macro_export]
-    macro_rules! seahorse_const {
-        ($ name : ident , $ value : expr) => {
-            macro_rules! $name {
-                () => {
-                    $value
-                };
-            }
-
-            pub(crate) use $name;
-        };
-    }
-
-    pub trait Loadable {
-        type Loaded;
 
-        fn load(stored: Self) -> Self::Loaded;
-
-        fn store(loaded: Self::Loaded) -> Self;
+    #[derive(Accounts)]
+    pub struct AcceptAdmin<'info> {
+        #[account(mut)]
+        pub new_admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
     }
-
-    macro_rules! Loaded {
-        ($ name : ty) => {
-            <$name as Loadable>::Loaded
-        };
+    pub fn accept_admin(ctx: Context<AcceptAdmin>) -> Result<()> {
+        accept_admin_handler(&ctx.accounts.new_admin, &mut ctx.accounts.collection)
     }
 
-    pub(crate) use Loaded;
-
-    #[macro_export]
-    macro_rules! assign {
-        ($ lval : expr , $ rval : expr) => {{
-            let temp = $rval;
-
-            $lval = temp;
-        }};
+    #[derive(Accounts)]
+    pub struct AcceptSwap<'info> {
+        #[account(mut)]
+        pub counterparty: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        # [account (mut , close = proposer)]
+        pub swap: Box<Account<'info, dot::program::Swap>>,
+        #[doc = "CHECK: Receives the swap account's rent back."]
+        # [account (mut , address = swap . proposer)]
+        pub proposer: UncheckedAccount<'info>,
+        #[account(mut)]
+        pub proposer_account: Box<Account<'info, dot::program::User>>,
+        #[account(mut)]
+        pub counterparty_account: Box<Account<'info, dot::program::User>>,
     }
 
-    #[macro_export]
-    macro_rules! index_assign {
-        ($ lval : expr , $ idx : expr , $ rval : expr) => {
-            let temp_rval = $rval;
-            let temp_idx = $idx;
-
-            $lval[temp_idx] = temp_rval;
-        };
+    pub fn accept_swap(ctx: Context<AcceptSwap>) -> Result<()> {
+        accept_swap_handler(
+            &ctx.accounts.counterparty,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.swap,
+            &mut ctx.accounts.proposer_account,
+            &mut ctx.accounts.counterparty_account,
+        )
     }
 
-    pub(crate) use assign;
-
-    pub(crate) use index_assign;
-
-    pub(crate) use seahorse_const;
-}
-
-#[program]
-mod rotary_savings {
-    use super::*;
-    use seahorse_util::*;
-    use std::collections::HashMap;
-
     #[derive(Accounts)]
     # [instruction (new_user : Pubkey)]
     pub struct AddUser<'info> {
@@ -230,45 +59,23 @@ mod rotary_savings {
         pub admin: Signer<'info>,
         #[account(mut)]
         pub collection: Box<Account<'info, dot::program::Collection>>,
-        # [account (init , space = std :: mem :: size_of :: < dot :: program :: User > () + 8 , payer = admin , seeds = ["user" . as_bytes () . as_ref () , collection . key () . as_ref () , new_user . as_ref ()] , bump)]
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: User > () + 8 , payer = admin , seeds = [seeds :: USER , collection . key () . as_ref () , new_user . as_ref ()] , bump)]
         pub user: Box<Account<'info, dot::program::User>>,
         pub rent: Sysvar<'info, Rent>,
         pub system_program: Program<'info, System>,
     }
 
     pub fn add_user(ctx: Context<AddUser>, new_user: Pubkey) -> Result<()> {
-        let mut programs = HashMap::new();
-
-        programs.insert(
-            "system_program",
-            ctx.accounts.system_program.to_account_info(),
-        );
-
-        let programs_map = ProgramsMap(programs);
-        let admin = SeahorseSigner {
-            account: &ctx.accounts.admin,
-            programs: &programs_map,
-        };
-
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
-
-        let user = Empty {
-            account: dot::program::User::load(&mut ctx.accounts.user, &programs_map),
-            bump: Some(ctx.bumps.user),
-        };
-
-        add_user_handler(admin.clone(), collection.clone(), user.clone(), new_user);
-
-        dot::program::Collection::store(collection);
-
-        dot::program::User::store(user.account);
-
-        return Ok(());
+        add_user_handler(
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user,
+            new_user,
+        )
     }
 
     #[derive(Accounts)]
-    # [instruction (new_duration : i64 , new_period : i64 , new_amount_per_period : u64)]
+    # [instruction (proposal_id : u64 , settings_changes : u8 , new_duration : i64 , new_period : i64 , new_amount_per_period : u64 , new_early_withdrawal_penalty_rate : u16 , new_penalty_schedule : u8 , new_min_early_withdrawal_penalty_rate : u16)]
     pub struct AdjustSettings<'info> {
         #[account(mut)]
         pub admin: Signer<'info>,
@@ -276,7 +83,7 @@ mod rotary_savings {
         pub collection: Box<Account<'info, dot::program::Collection>>,
         #[account(mut)]
         pub multisig: Box<Account<'info, dot::program::Multisig>>,
-        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 , payer = admin , seeds = ["proposal" . as_bytes () . as_ref () , collection . key () . as_ref () , format ! ("{:?}" , multisig . borrow () . nonce) . as_bytes () . as_ref ()] , bump)]
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = admin , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
         pub proposal: Box<Account<'info, dot::program::Proposal>>,
         #[account()]
         pub clock: Sysvar<'info, Clock>,
@@ -286,118 +93,148 @@ mod rotary_savings {
 
     pub fn adjust_settings(
         ctx: Context<AdjustSettings>,
+        proposal_id: u64,
+        settings_changes: u8,
         new_duration: i64,
         new_period: i64,
         new_amount_per_period: u64,
+        new_early_withdrawal_penalty_rate: u16,
+        new_penalty_schedule: u8,
+        new_min_early_withdrawal_penalty_rate: u16,
     ) -> Result<()> {
-        let mut programs = HashMap::new();
-
-        programs.insert(
-            "system_program",
-            ctx.accounts.system_program.to_account_info(),
-        );
-
-        let programs_map = ProgramsMap(programs);
-        let admin = SeahorseSigner {
-            account: &ctx.accounts.admin,
-            programs: &programs_map,
-        };
-
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
-
-        let multisig = dot::program::Multisig::load(&mut ctx.accounts.multisig, &programs_map);
-        let proposal = Empty {
-            account: dot::program::Proposal::load(&mut ctx.accounts.proposal, &programs_map),
-            bump: Some(ctx.bumps.proposal),
-        };
-
-        let clock = &ctx.accounts.clock.clone();
-
         adjust_settings_handler(
-            admin.clone(),
-            collection.clone(),
-            multisig.clone(),
-            proposal.clone(),
-            clock.clone(),
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+            proposal_id,
+            settings_changes,
             new_duration,
             new_period,
             new_amount_per_period,
-        );
+            new_early_withdrawal_penalty_rate,
+            new_penalty_schedule,
+            new_min_early_withdrawal_penalty_rate,
+        )
+    }
 
-        dot::program::Collection::store(collection);
+    #[derive(Accounts)]
+    pub struct CancelProposal<'info> {
+        #[account(mut)]
+        pub authority: Signer<'info>,
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        # [account (mut , close = proposer)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        #[doc = "CHECK: Receives the proposal's rent."]
+        # [account (mut , address = proposal . proposer)]
+        pub proposer: UncheckedAccount<'info>,
+    }
 
-        dot::program::Multisig::store(multisig);
+    pub fn cancel_proposal(ctx: Context<CancelProposal>) -> Result<()> {
+        cancel_proposal_handler(
+            &ctx.accounts.authority,
+            &ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+        )
+    }
 
-        dot::program::Proposal::store(proposal.account);
+    #[derive(Accounts)]
+    pub struct CancelSwap<'info> {
+        #[account(mut)]
+        pub proposer: Signer<'info>,
+        # [account (mut , close = proposer)]
+        pub swap: Box<Account<'info, dot::program::Swap>>,
+    }
 
-        return Ok(());
+    pub fn cancel_swap(ctx: Context<CancelSwap>) -> Result<()> {
+        cancel_swap_handler(&ctx.accounts.proposer, &mut ctx.accounts.swap)
     }
 
     #[derive(Accounts)]
-    pub struct CloseCollection<'info> {
-        #[account(mut)]
-        pub admin: Signer<'info>,
+    # [instruction (approve : bool)]
+    pub struct CastVote<'info> {
         #[account(mut)]
+        pub member: Signer<'info>,
         pub collection: Box<Account<'info, dot::program::Collection>>,
+        pub user_account: Box<Account<'info, dot::program::User>>,
         #[account(mut)]
-        pub multisig: Box<Account<'info, dot::program::Multisig>>,
-        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 , payer = admin , seeds = ["proposal" . as_bytes () . as_ref () , collection . key () . as_ref () , format ! ("{:?}" , multisig . borrow () . nonce) . as_bytes () . as_ref ()] , bump)]
         pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Vote > () + 8 , payer = member , seeds = [seeds :: VOTE , proposal . key () . as_ref () , user_account . user . as_ref ()] , bump)]
+        pub vote: Box<Account<'info, dot::program::Vote>>,
         #[account()]
         pub clock: Sysvar<'info, Clock>,
         pub rent: Sysvar<'info, Rent>,
         pub system_program: Program<'info, System>,
     }
 
-    pub fn close_collection(ctx: Context<CloseCollection>) -> Result<()> {
-        let mut programs = HashMap::new();
-
-        programs.insert(
-            "system_program",
-            ctx.accounts.system_program.to_account_info(),
-        );
-
-        let programs_map = ProgramsMap(programs);
-        let admin = SeahorseSigner {
-            account: &ctx.accounts.admin,
-            programs: &programs_map,
-        };
+    pub fn cast_vote(ctx: Context<CastVote>, approve: bool) -> Result<()> {
+        cast_vote_handler(
+            &ctx.accounts.member,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.vote,
+            &ctx.accounts.clock,
+            approve,
+        )
+    }
 
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
+    #[derive(Accounts)]
+    pub struct ClaimDividends<'info> {
+        #[account(mut)]
+        pub member: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub user_account: Box<Account<'info, dot::program::User>>,
+    }
 
-        let multisig = dot::program::Multisig::load(&mut ctx.accounts.multisig, &programs_map);
-        let proposal = Empty {
-            account: dot::program::Proposal::load(&mut ctx.accounts.proposal, &programs_map),
-            bump: Some(ctx.bumps.proposal),
-        };
+    pub fn claim_dividends(ctx: Context<ClaimDividends>) -> Result<()> {
+        claim_dividends_handler(
+            &ctx.accounts.member,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+        )
+    }
 
-        let clock = &ctx.accounts.clock.clone();
+    #[derive(Accounts)]
+    # [instruction (proposal_id : u64)]
+    pub struct CloseCollection<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = admin , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
 
+    pub fn close_collection(ctx: Context<CloseCollection>, proposal_id: u64) -> Result<()> {
         close_collection_handler(
-            admin.clone(),
-            collection.clone(),
-            multisig.clone(),
-            proposal.clone(),
-            clock.clone(),
-        );
-
-        dot::program::Collection::store(collection);
-
-        dot::program::Multisig::store(multisig);
-
-        dot::program::Proposal::store(proposal.account);
-
-        return Ok(());
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+            proposal_id,
+        )
     }
 
     #[derive(Accounts)]
-    # [instruction (name : String , duration : i64 , period : i64 , amount_per_period : u64 , total_members : u8 , early_withdrawal_penalty_rate : u8)]
+    # [instruction (name : String , duration : i64 , period : i64 , amount_per_period : u64 , total_members : u8 , early_withdrawal_penalty_rate : u16 , penalty_schedule : u8 , min_early_withdrawal_penalty_rate : u16 , collateral_amount : u64 , payout_order_mode : u8 , governance_mode : u8 , quorum_pct : u8 , approval_pct : u8)]
     pub struct CreateCollection<'info> {
         #[account(mut)]
         pub admin: Signer<'info>,
-        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Collection > () + 8 , payer = admin , seeds = ["collection" . as_bytes () . as_ref () , admin . key () . as_ref () , name . as_bytes () . as_ref ()] , bump)]
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Collection > () + 8 + dot :: program :: MAX_NAME_LEN + (total_members as usize) * 32 + dot :: program :: MAX_CO_ADMINS * 32 , payer = admin , seeds = [seeds :: COLLECTION , admin . key () . as_ref () , seeds :: collection_name (& name) ?] , bump)]
         pub collection: Box<Account<'info, dot::program::Collection>>,
         pub rent: Sysvar<'info, Rent>,
         pub system_program: Program<'info, System>,
@@ -410,50 +247,42 @@ mod rotary_savings {
         period: i64,
         amount_per_period: u64,
         total_members: u8,
-        early_withdrawal_penalty_rate: u8,
+        early_withdrawal_penalty_rate: u16,
+        penalty_schedule: u8,
+        min_early_withdrawal_penalty_rate: u16,
+        collateral_amount: u64,
+        payout_order_mode: u8,
+        governance_mode: u8,
+        quorum_pct: u8,
+        approval_pct: u8,
     ) -> Result<()> {
-        let mut programs = HashMap::new();
-
-        programs.insert(
-            "system_program",
-            ctx.accounts.system_program.to_account_info(),
-        );
-
-        let programs_map = ProgramsMap(programs);
-        let admin = SeahorseSigner {
-            account: &ctx.accounts.admin,
-            programs: &programs_map,
-        };
-
-        let collection = Empty {
-            account: dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map),
-            bump: Some(ctx.bumps.collection),
-        };
-
         create_collection_handler(
-            admin.clone(),
-            collection.clone(),
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
             name,
             duration,
             period,
             amount_per_period,
             total_members,
             early_withdrawal_penalty_rate,
-        );
-
-        dot::program::Collection::store(collection.account);
-
-        return Ok(());
+            penalty_schedule,
+            min_early_withdrawal_penalty_rate,
+            collateral_amount,
+            payout_order_mode,
+            governance_mode,
+            quorum_pct,
+            approval_pct,
+        )
     }
 
     #[derive(Accounts)]
-    # [instruction (signers : Vec < Pubkey > , threshold : u8)]
+    # [instruction (signers : Vec < Pubkey > , threshold : u8 , weights : Vec < u16 > , weight_threshold : u32 , quorum : u8 , pass_rule : u8)]
     pub struct CreateMultisig<'info> {
         #[account(mut)]
         pub admin: Signer<'info>,
         #[account(mut)]
         pub collection: Box<Account<'info, dot::program::Collection>>,
-        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Multisig > () + 8 , payer = admin , seeds = ["multisig" . as_bytes () . as_ref () , collection . key () . as_ref ()] , bump)]
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Multisig > () + 8 + dot :: program :: MAX_OPEN_PROPOSALS * 8 + weights . len () * 2 + signers . len () * 2 * 32 , payer = admin , seeds = [seeds :: MULTISIG , collection . key () . as_ref ()] , bump)]
         pub multisig: Box<Account<'info, dot::program::Multisig>>,
         pub rent: Sysvar<'info, Rent>,
         pub system_program: Program<'info, System>,
@@ -463,45 +292,26 @@ mod rotary_savings {
         ctx: Context<CreateMultisig>,
         signers: Vec<Pubkey>,
         threshold: u8,
+        weights: Vec<u16>,
+        weight_threshold: u32,
+        quorum: u8,
+        pass_rule: u8,
     ) -> Result<()> {
-        let mut programs = HashMap::new();
-
-        programs.insert(
-            "system_program",
-            ctx.accounts.system_program.to_account_info(),
-        );
-
-        let programs_map = ProgramsMap(programs);
-        let admin = SeahorseSigner {
-            account: &ctx.accounts.admin,
-            programs: &programs_map,
-        };
-
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
-
-        let multisig = Empty {
-            account: dot::program::Multisig::load(&mut ctx.accounts.multisig, &programs_map),
-            bump: Some(ctx.bumps.multisig),
-        };
-
         create_multisig_handler(
-            admin.clone(),
-            collection.clone(),
-            multisig.clone(),
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
             signers,
             threshold,
-        );
-
-        dot::program::Collection::store(collection);
-
-        dot::program::Multisig::store(multisig.account);
-
-        return Ok(());
+            weights,
+            weight_threshold,
+            quorum,
+            pass_rule,
+        )
     }
 
     #[derive(Accounts)]
-    # [instruction (proposal_type : u8 , withdraw_user : Pubkey , withdraw_amount : u64 , new_duration : i64 , new_period : i64 , new_amount_per_period : u64 , new_early_withdrawal_penalty_rate : u8)]
+    # [instruction (proposal_id : u64 , proposal_type : u8 , withdraw_user : Pubkey , withdraw_amount : u64 , new_duration : i64 , new_period : i64 , new_amount_per_period : u64 , new_early_withdrawal_penalty_rate : u16 , settings_changes : u8 , new_penalty_schedule : u8 , new_min_early_withdrawal_penalty_rate : u16)]
     pub struct CreateProposal<'info> {
         #[account(mut)]
         pub proposer: Signer<'info>,
@@ -509,7 +319,7 @@ mod rotary_savings {
         pub collection: Box<Account<'info, dot::program::Collection>>,
         #[account(mut)]
         pub multisig: Box<Account<'info, dot::program::Multisig>>,
-        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 , payer = proposer , seeds = ["proposal" . as_bytes () . as_ref () , collection . key () . as_ref () , format ! ("{:?}" , multisig . borrow () . nonce) . as_bytes () . as_ref ()] , bump)]
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = proposer , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
         pub proposal: Box<Account<'info, dot::program::Proposal>>,
         #[account(mut)]
         pub user_account: Box<Account<'info, dot::program::User>>,
@@ -521,46 +331,26 @@ mod rotary_savings {
 
     pub fn create_proposal(
         ctx: Context<CreateProposal>,
+        proposal_id: u64,
         proposal_type: u8,
         withdraw_user: Pubkey,
         withdraw_amount: u64,
         new_duration: i64,
         new_period: i64,
         new_amount_per_period: u64,
-        new_early_withdrawal_penalty_rate: u8,
+        new_early_withdrawal_penalty_rate: u16,
+        settings_changes: u8,
+        new_penalty_schedule: u8,
+        new_min_early_withdrawal_penalty_rate: u16,
     ) -> Result<()> {
-        let mut programs = HashMap::new();
-
-        programs.insert(
-            "system_program",
-            ctx.accounts.system_program.to_account_info(),
-        );
-
-        let programs_map = ProgramsMap(programs);
-        let proposer = SeahorseSigner {
-            account: &ctx.accounts.proposer,
-            programs: &programs_map,
-        };
-
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
-
-        let multisig = dot::program::Multisig::load(&mut ctx.accounts.multisig, &programs_map);
-        let proposal = Empty {
-            account: dot::program::Proposal::load(&mut ctx.accounts.proposal, &programs_map),
-            bump: Some(ctx.bumps.proposal),
-        };
-
-        let user_account = dot::program::User::load(&mut ctx.accounts.user_account, &programs_map);
-        let clock = &ctx.accounts.clock.clone();
-
         create_proposal_handler(
-            proposer.clone(),
-            collection.clone(),
-            multisig.clone(),
-            proposal.clone(),
-            user_account.clone(),
-            clock.clone(),
+            &ctx.accounts.proposer,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.user_account,
+            &ctx.accounts.clock,
+            proposal_id,
             proposal_type,
             withdraw_user,
             withdraw_amount,
@@ -568,17 +358,90 @@ mod rotary_savings {
             new_period,
             new_amount_per_period,
             new_early_withdrawal_penalty_rate,
-        );
+            settings_changes,
+            new_penalty_schedule,
+            new_min_early_withdrawal_penalty_rate,
+        )
+    }
+
+    #[derive(Accounts)]
+    # [instruction (delegate : Pubkey)]
+    pub struct DelegateMemberVote<'info> {
+        #[account(mut)]
+        pub member: Signer<'info>,
+        #[account(mut)]
+        pub user_account: Box<Account<'info, dot::program::User>>,
+    }
+
+    pub fn delegate_member_vote(ctx: Context<DelegateMemberVote>, delegate: Pubkey) -> Result<()> {
+        delegate_member_vote_handler(
+            &ctx.accounts.member,
+            &mut ctx.accounts.user_account,
+            delegate,
+        )
+    }
+
+    #[derive(Accounts)]
+    # [instruction (delegate : Pubkey)]
+    pub struct DelegateVote<'info> {
+        #[account(mut)]
+        pub signer: Signer<'info>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+    }
 
-        dot::program::Collection::store(collection);
+    pub fn delegate_vote(ctx: Context<DelegateVote>, delegate: Pubkey) -> Result<()> {
+        delegate_vote_handler(&ctx.accounts.signer, &mut ctx.accounts.multisig, delegate)
+    }
+
+    #[derive(Accounts)]
+    pub struct DepositCollateral<'info> {
+        #[account(mut)]
+        pub member: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub user_account: Box<Account<'info, dot::program::User>>,
+        pub system_program: Program<'info, System>,
+    }
 
-        dot::program::Multisig::store(multisig);
+    pub fn deposit_collateral(ctx: Context<DepositCollateral>) -> Result<()> {
+        deposit_collateral_handler(
+            &ctx.accounts.member,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+            &ctx.accounts.system_program,
+        )
+    }
 
-        dot::program::Proposal::store(proposal.account);
+    #[derive(Accounts)]
+    pub struct DisburseRound<'info> {
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub recipient_account: Box<Account<'info, dot::program::User>>,
+        #[doc = "CHECK: Must match the member scheduled for the current round."]
+        #[account(mut)]
+        pub recipient: UncheckedAccount<'info>,
+    }
 
-        dot::program::User::store(user_account);
+    pub fn disburse_round(ctx: Context<DisburseRound>) -> Result<()> {
+        disburse_round_handler(
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.recipient_account,
+            ctx.accounts.recipient.to_account_info(),
+        )
+    }
 
-        return Ok(());
+    #[derive(Accounts)]
+    pub struct EmergencyPause<'info> {
+        #[account(mut)]
+        pub pause_key: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+    }
+    pub fn emergency_pause(ctx: Context<EmergencyPause>) -> Result<()> {
+        emergency_pause_handler(&ctx.accounts.pause_key, &mut ctx.accounts.collection)
     }
 
     #[derive(Accounts)]
@@ -596,33 +459,159 @@ mod rotary_savings {
     }
 
     pub fn execute_proposal(ctx: Context<ExecuteProposal>) -> Result<()> {
-        let mut programs = HashMap::new();
-        let programs_map = ProgramsMap(programs);
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
+        execute_proposal_handler(
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &mut ctx.accounts.user_account,
+            &ctx.accounts.clock,
+        )
+    }
+
+    #[derive(Accounts)]
+    # [instruction (duration : i64)]
+    pub struct GuardianFreeze<'info> {
+        #[account(mut)]
+        pub guardian: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+    }
+    pub fn guardian_freeze(ctx: Context<GuardianFreeze>, duration: i64) -> Result<()> {
+        guardian_freeze_handler(
+            &ctx.accounts.guardian,
+            &mut ctx.accounts.collection,
+            &ctx.accounts.clock,
+            duration,
+        )
+    }
 
-        let multisig = dot::program::Multisig::load(&mut ctx.accounts.multisig, &programs_map);
-        let proposal = dot::program::Proposal::load(&mut ctx.accounts.proposal, &programs_map);
-        let user_account = dot::program::User::load(&mut ctx.accounts.user_account, &programs_map);
-        let clock = &ctx.accounts.clock.clone();
+    #[derive(Accounts)]
+    # [instruction (proof : Vec < [u8 ; 32] >)]
+    pub struct JoinCollection<'info> {
+        #[account(mut)]
+        pub member: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: User > () + 8 , payer = member , seeds = [seeds :: USER , collection . key () . as_ref () , member . key () . as_ref ()] , bump)]
+        pub user: Box<Account<'info, dot::program::User>>,
+        #[doc = "CHECK: Verified against the instructions sysvar address."]
+        # [account (address = anchor_lang :: solana_program :: sysvar :: instructions :: ID)]
+        pub instructions: UncheckedAccount<'info>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
 
-        execute_proposal_handler(
-            collection.clone(),
-            multisig.clone(),
-            proposal.clone(),
-            user_account.clone(),
-            clock.clone(),
-        );
+    pub fn join_collection(ctx: Context<JoinCollection>, proof: Vec<[u8; 32]>) -> Result<()> {
+        join_collection_handler(
+            &ctx.accounts.member,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user,
+            ctx.accounts.instructions.to_account_info(),
+            proof,
+            &ctx.accounts.system_program,
+        )
+    }
 
-        dot::program::Collection::store(collection);
+    #[derive(Accounts)]
+    pub struct MigrateCollection<'info> {
+        #[account(mut)]
+        pub payer: Signer<'info>,
+        #[doc = "CHECK: Read in the original layout and rewritten by the handler."]
+        # [account (mut , owner = crate :: ID)]
+        pub collection: UncheckedAccount<'info>,
+        #[doc = "CHECK: Rewritten alongside the collection if the original program created it."]
+        # [account (mut , seeds = [seeds :: MULTISIG , collection . key () . as_ref ()] , bump)]
+        pub multisig: UncheckedAccount<'info>,
+        pub system_program: Program<'info, System>,
+    }
+
+    pub fn migrate_collection(ctx: Context<MigrateCollection>) -> Result<()> {
+        migrate_collection_handler(
+            &ctx.accounts.payer,
+            &ctx.accounts.collection,
+            &ctx.accounts.multisig,
+            &ctx.accounts.system_program,
+        )
+    }
+
+    #[derive(Accounts)]
+    # [instruction (legacy_nonce : u64)]
+    pub struct MigrateProposal<'info> {
+        #[account(mut)]
+        pub payer: Signer<'info>,
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        #[doc = "CHECK: Read in the pre-id layout and closed by the handler."]
+        # [account (mut , owner = crate :: ID , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: legacy_proposal_id (legacy_nonce) . as_bytes ()] , bump)]
+        pub legacy_proposal: UncheckedAccount<'info>,
+        #[doc = "CHECK: Must match the legacy proposal's proposer, who gets its rent back."]
+        #[account(mut)]
+        pub proposer: UncheckedAccount<'info>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + legacy_proposal . data_len () , payer = payer , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (legacy_nonce) . as_ref ()] , bump)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
 
-        dot::program::Multisig::store(multisig);
+    pub fn migrate_proposal(ctx: Context<MigrateProposal>, legacy_nonce: u64) -> Result<()> {
+        migrate_proposal_handler(
+            &ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &ctx.accounts.legacy_proposal,
+            &ctx.accounts.proposer,
+            &mut ctx.accounts.proposal,
+            legacy_nonce,
+        )
+    }
 
-        dot::program::Proposal::store(proposal);
+    #[derive(Accounts)]
+    pub struct MigrateUser<'info> {
+        #[account(mut)]
+        pub payer: Signer<'info>,
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[doc = "CHECK: Read in the original layout and rewritten by the handler."]
+        # [account (mut , owner = crate :: ID)]
+        pub user_account: UncheckedAccount<'info>,
+        pub system_program: Program<'info, System>,
+    }
 
-        dot::program::User::store(user_account);
+    pub fn migrate_user(ctx: Context<MigrateUser>) -> Result<()> {
+        migrate_user_handler(
+            &ctx.accounts.payer,
+            &ctx.accounts.collection,
+            &ctx.accounts.user_account,
+            &ctx.accounts.system_program,
+        )
+    }
 
-        return Ok(());
+    #[derive(Accounts)]
+    # [instruction (proposal_id : u64)]
+    pub struct PauseCollection<'info> {
+        #[account(mut)]
+        pub proposer: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = proposer , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
+    pub fn pause_collection(ctx: Context<PauseCollection>, proposal_id: u64) -> Result<()> {
+        pause_collection_handler(
+            &ctx.accounts.proposer,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+            proposal_id,
+        )
     }
 
     #[derive(Accounts)]
@@ -636,38 +625,77 @@ mod rotary_savings {
         pub user_account: Box<Account<'info, dot::program::User>>,
         #[account()]
         pub clock: Sysvar<'info, Clock>,
+        pub system_program: Program<'info, System>,
     }
 
     pub fn pay(ctx: Context<Pay>, amount: u64) -> Result<()> {
-        let mut programs = HashMap::new();
-        let programs_map = ProgramsMap(programs);
-        let user = SeahorseSigner {
-            account: &ctx.accounts.user,
-            programs: &programs_map,
-        };
-
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
-
-        let user_account = dot::program::User::load(&mut ctx.accounts.user_account, &programs_map);
-        let clock = &ctx.accounts.clock.clone();
-
         pay_handler(
-            user.clone(),
-            collection.clone(),
-            user_account.clone(),
-            clock.clone(),
+            &ctx.accounts.user,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+            &ctx.accounts.clock,
             amount,
-        );
+            &ctx.accounts.system_program,
+        )
+    }
 
-        dot::program::Collection::store(collection);
+    #[derive(Accounts)]
+    # [instruction (amount : u64)]
+    pub struct PlaceBid<'info> {
+        #[account(mut)]
+        pub member: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub user_account: Box<Account<'info, dot::program::User>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+    }
 
-        dot::program::User::store(user_account);
+    pub fn place_bid(ctx: Context<PlaceBid>, amount: u64) -> Result<()> {
+        place_bid_handler(
+            &ctx.accounts.member,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+            &ctx.accounts.clock,
+            amount,
+        )
+    }
+
+    #[derive(Accounts)]
+    # [instruction (side_payment : u64)]
+    pub struct ProposeSwap<'info> {
+        #[account(mut)]
+        pub proposer: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub proposer_account: Box<Account<'info, dot::program::User>>,
+        #[account(mut)]
+        pub counterparty_account: Box<Account<'info, dot::program::User>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Swap > () + 8 , payer = proposer , seeds = [seeds :: SWAP , collection . key () . as_ref () , proposer . key () . as_ref () , seeds :: round (collection . current_round) . as_ref ()] , bump)]
+        pub swap: Box<Account<'info, dot::program::Swap>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
 
-        return Ok(());
+    pub fn propose_swap(ctx: Context<ProposeSwap>, side_payment: u64) -> Result<()> {
+        propose_swap_handler(
+            &ctx.accounts.proposer,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.proposer_account,
+            &mut ctx.accounts.counterparty_account,
+            &mut ctx.accounts.swap,
+            &ctx.accounts.clock,
+            side_payment,
+            &ctx.accounts.system_program,
+        )
     }
 
     #[derive(Accounts)]
+    # [instruction (proposal_id : u64)]
     pub struct ProposeWithdraw<'info> {
         #[account(mut)]
         pub user: Signer<'info>,
@@ -677,7 +705,7 @@ mod rotary_savings {
         pub user_account: Box<Account<'info, dot::program::User>>,
         #[account(mut)]
         pub multisig: Box<Account<'info, dot::program::Multisig>>,
-        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 , payer = user , seeds = ["proposal" . as_bytes () . as_ref () , collection . key () . as_ref () , format ! ("{:?}" , multisig . borrow () . nonce) . as_bytes () . as_ref ()] , bump)]
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = user , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
         pub proposal: Box<Account<'info, dot::program::Proposal>>,
         #[account()]
         pub clock: Sysvar<'info, Clock>,
@@ -685,50 +713,299 @@ mod rotary_savings {
         pub system_program: Program<'info, System>,
     }
 
-    pub fn propose_withdraw(ctx: Context<ProposeWithdraw>) -> Result<()> {
-        let mut programs = HashMap::new();
+    pub fn propose_withdraw(ctx: Context<ProposeWithdraw>, proposal_id: u64) -> Result<()> {
+        propose_withdraw_handler(
+            &ctx.accounts.user,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+            proposal_id,
+        )
+    }
+
+    #[derive(Accounts)]
+    pub struct PruneProposal<'info> {
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        #[account(mut)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+    }
+
+    pub fn prune_proposal(ctx: Context<PruneProposal>) -> Result<()> {
+        prune_proposal_handler(
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+        )
+    }
 
-        programs.insert(
-            "system_program",
-            ctx.accounts.system_program.to_account_info(),
-        );
+    #[derive(Accounts)]
+    pub struct ReclaimCollateral<'info> {
+        #[account(mut)]
+        pub member: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub user_account: Box<Account<'info, dot::program::User>>,
+    }
 
-        let programs_map = ProgramsMap(programs);
-        let user = SeahorseSigner {
-            account: &ctx.accounts.user,
-            programs: &programs_map,
-        };
+    pub fn reclaim_collateral(ctx: Context<ReclaimCollateral>) -> Result<()> {
+        reclaim_collateral_handler(
+            &ctx.accounts.member,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+        )
+    }
 
-        let collection =
-            dot::program::Collection::load(&mut ctx.accounts.collection, &programs_map);
+    #[derive(Accounts)]
+    # [instruction (proposal_id : u64)]
+    pub struct ResumeCollection<'info> {
+        #[account(mut)]
+        pub proposer: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = proposer , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
+    pub fn resume_collection(ctx: Context<ResumeCollection>, proposal_id: u64) -> Result<()> {
+        resume_collection_handler(
+            &ctx.accounts.proposer,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+            proposal_id,
+        )
+    }
 
-        let user_account = dot::program::User::load(&mut ctx.accounts.user_account, &programs_map);
-        let multisig = dot::program::Multisig::load(&mut ctx.accounts.multisig, &programs_map);
-        let proposal = Empty {
-            account: dot::program::Proposal::load(&mut ctx.accounts.proposal, &programs_map),
-            bump: Some(ctx.bumps.proposal),
-        };
+    #[derive(Accounts)]
+    pub struct RevokeDelegation<'info> {
+        #[account(mut)]
+        pub signer: Signer<'info>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+    }
 
-        let clock = &ctx.accounts.clock.clone();
+    pub fn revoke_delegation(ctx: Context<RevokeDelegation>) -> Result<()> {
+        revoke_delegation_handler(&ctx.accounts.signer, &mut ctx.accounts.multisig)
+    }
 
-        propose_withdraw_handler(
-            user.clone(),
-            collection.clone(),
-            user_account.clone(),
-            multisig.clone(),
-            proposal.clone(),
-            clock.clone(),
-        );
+    #[derive(Accounts)]
+    pub struct RevokeMemberDelegation<'info> {
+        #[account(mut)]
+        pub member: Signer<'info>,
+        #[account(mut)]
+        pub user_account: Box<Account<'info, dot::program::User>>,
+    }
+
+    pub fn revoke_member_delegation(ctx: Context<RevokeMemberDelegation>) -> Result<()> {
+        revoke_member_delegation_handler(&ctx.accounts.member, &mut ctx.accounts.user_account)
+    }
+
+    #[derive(Accounts)]
+    # [instruction (co_admins : Vec < Pubkey >)]
+    pub struct SetCoAdmins<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+    }
+    pub fn set_co_admins(ctx: Context<SetCoAdmins>, co_admins: Vec<Pubkey>) -> Result<()> {
+        set_co_admins_handler(&ctx.accounts.admin, &mut ctx.accounts.collection, co_admins)
+    }
+
+    #[derive(Accounts)]
+    # [instruction (allow_list_root : [u8 ; 32] , invite_signer : Pubkey)]
+    pub struct SetEnrollment<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+    }
+
+    pub fn set_enrollment(
+        ctx: Context<SetEnrollment>,
+        allow_list_root: [u8; 32],
+        invite_signer: Pubkey,
+    ) -> Result<()> {
+        set_enrollment_handler(
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            allow_list_root,
+            invite_signer,
+        )
+    }
+
+    #[derive(Accounts)]
+    # [instruction (guardian : Pubkey)]
+    pub struct SetGuardian<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+    }
+    pub fn set_guardian(ctx: Context<SetGuardian>, guardian: Pubkey) -> Result<()> {
+        set_guardian_handler(&ctx.accounts.admin, &mut ctx.accounts.collection, guardian)
+    }
+
+    #[derive(Accounts)]
+    # [instruction (emergency_pause_key : Pubkey)]
+    pub struct SetPauseKey<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+    }
+    pub fn set_pause_key(ctx: Context<SetPauseKey>, emergency_pause_key: Pubkey) -> Result<()> {
+        set_pause_key_handler(
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            emergency_pause_key,
+        )
+    }
+
+    #[derive(Accounts)]
+    # [instruction (order : Vec < Pubkey >)]
+    pub struct SetPayoutOrder<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+    }
+
+    pub fn set_payout_order(ctx: Context<SetPayoutOrder>, order: Vec<Pubkey>) -> Result<()> {
+        set_payout_order_handler(&ctx.accounts.admin, &mut ctx.accounts.collection, order)
+    }
+
+    #[derive(Accounts)]
+    pub struct SettleRound<'info> {
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub recipient_account: Box<Account<'info, dot::program::User>>,
+        #[doc = "CHECK: Must match the winner of the current round."]
+        #[account(mut)]
+        pub recipient: UncheckedAccount<'info>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+    }
+
+    pub fn settle_round(ctx: Context<SettleRound>) -> Result<()> {
+        settle_round_handler(
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.recipient_account,
+            ctx.accounts.recipient.to_account_info(),
+            &ctx.accounts.clock,
+        )
+    }
+
+    #[derive(Accounts)]
+    pub struct ShufflePayoutOrder<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[doc = "CHECK: Verified against the slot hashes sysvar address."]
+        # [account (address = anchor_lang :: solana_program :: sysvar :: slot_hashes :: ID)]
+        pub slot_hashes: UncheckedAccount<'info>,
+    }
 
-        dot::program::Collection::store(collection);
+    pub fn shuffle_payout_order(ctx: Context<ShufflePayoutOrder>) -> Result<()> {
+        shuffle_payout_order_handler(
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            ctx.accounts.slot_hashes.to_account_info(),
+        )
+    }
 
-        dot::program::User::store(user_account);
+    #[derive(Accounts)]
+    pub struct SlashCollateral<'info> {
+        #[account(mut)]
+        pub admin: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub user_account: Box<Account<'info, dot::program::User>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+    }
 
-        dot::program::Multisig::store(multisig);
+    pub fn slash_collateral(ctx: Context<SlashCollateral>) -> Result<()> {
+        slash_collateral_handler(
+            &ctx.accounts.admin,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.user_account,
+            &ctx.accounts.clock,
+        )
+    }
 
-        dot::program::Proposal::store(proposal.account);
+    #[derive(Accounts)]
+    # [instruction (proposal_id : u64 , new_admin : Pubkey)]
+    pub struct TransferAdmin<'info> {
+        #[account(mut)]
+        pub proposer: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = proposer , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
+    pub fn transfer_admin(
+        ctx: Context<TransferAdmin>,
+        proposal_id: u64,
+        new_admin: Pubkey,
+    ) -> Result<()> {
+        transfer_admin_handler(
+            &ctx.accounts.proposer,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+            proposal_id,
+            new_admin,
+        )
+    }
 
-        return Ok(());
+    #[derive(Accounts)]
+    # [instruction (proposal_id : u64)]
+    pub struct UnfreezeVault<'info> {
+        #[account(mut)]
+        pub proposer: Signer<'info>,
+        #[account(mut)]
+        pub collection: Box<Account<'info, dot::program::Collection>>,
+        #[account(mut)]
+        pub multisig: Box<Account<'info, dot::program::Multisig>>,
+        # [account (init , space = std :: mem :: size_of :: < dot :: program :: Proposal > () + 8 + multisig . signers . len () * 2 , payer = proposer , seeds = [seeds :: PROPOSAL , collection . key () . as_ref () , seeds :: proposal_id (proposal_id) . as_ref ()] , bump)]
+        pub proposal: Box<Account<'info, dot::program::Proposal>>,
+        #[account()]
+        pub clock: Sysvar<'info, Clock>,
+        pub rent: Sysvar<'info, Rent>,
+        pub system_program: Program<'info, System>,
+    }
+    pub fn unfreeze_vault(ctx: Context<UnfreezeVault>, proposal_id: u64) -> Result<()> {
+        unfreeze_vault_handler(
+            &ctx.accounts.proposer,
+            &mut ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
+            proposal_id,
+        )
     }
 
     #[derive(Accounts)]
@@ -736,6 +1013,7 @@ mod rotary_savings {
     pub struct VoteOnProposal<'info> {
         #[account(mut)]
         pub signer: Signer<'info>,
+        pub collection: Box<Account<'info, dot::program::Collection>>,
         #[account(mut)]
         pub multisig: Box<Account<'info, dot::program::Multisig>>,
         #[account(mut)]
@@ -745,29 +1023,13 @@ mod rotary_savings {
     }
 
     pub fn vote_on_proposal(ctx: Context<VoteOnProposal>, approve: bool) -> Result<()> {
-        let mut programs = HashMap::new();
-        let programs_map = ProgramsMap(programs);
-        let signer = SeahorseSigner {
-            account: &ctx.accounts.signer,
-            programs: &programs_map,
-        };
-
-        let multisig = dot::program::Multisig::load(&mut ctx.accounts.multisig, &programs_map);
-        let proposal = dot::program::Proposal::load(&mut ctx.accounts.proposal, &programs_map);
-        let clock = &ctx.accounts.clock.clone();
-
         vote_on_proposal_handler(
-            signer.clone(),
-            multisig.clone(),
-            proposal.clone(),
-            clock.clone(),
+            &ctx.accounts.signer,
+            &ctx.accounts.collection,
+            &mut ctx.accounts.multisig,
+            &mut ctx.accounts.proposal,
+            &ctx.accounts.clock,
             approve,
-        );
-
-        dot::program::Multisig::store(multisig);
-
-        dot::program::Proposal::store(proposal);
-
-        return Ok(());
+        )
     }
 }
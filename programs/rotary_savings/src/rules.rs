@@ -0,0 +1,504 @@
+//! The savings rules as plain functions over plain values. Handlers copy the
+//! fields a rule needs out of their accounts, call it and apply the result,
+//! so the rules can be tested without building any accounts.
+
+use crate::dot::program::{
+    BPS_DENOMINATOR, PASS_MAJORITY, PASS_SUPERMAJORITY, PENALTY_SCHEDULE_LINEAR,
+};
+use anchor_lang::solana_program::hash::hashv;
+
+/// A collection's payment schedule.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Schedule {
+    pub period: i64,
+    pub duration: i64,
+}
+
+impl Schedule {
+    /// Whether `paid_periods` payments cover the whole duration, which makes
+    /// the member eligible for a regular withdrawal.
+    pub fn cycle_complete(&self, paid_periods: u8) -> bool {
+        (paid_periods as i64) * self.period >= self.duration
+    }
+
+    /// Whether a member who last paid at `last_paid` may pay again at `now`.
+    pub fn payment_due(&self, last_paid: i64, now: i64) -> bool {
+        now - last_paid >= self.period
+    }
+
+    /// A member is in default once they have missed a full period, counted
+    /// from their last payment or from joining if they never paid.
+    pub fn in_default(&self, paid_periods: u8, last_paid: i64, joined_at: i64, now: i64) -> bool {
+        let last_activity = if paid_periods == 0 {
+            joined_at
+        } else {
+            last_paid
+        };
+
+        !self.cycle_complete(paid_periods) && (now - last_activity >= 2 * self.period)
+    }
+
+    /// The bidding window for `round` of a rotation that started at
+    /// `started_at`, as `(opens_at, closes_at)`. Each round lasts one period.
+    pub fn round_window(&self, started_at: i64, round: u8) -> (i64, i64) {
+        let opens_at = started_at + (round as i64) * self.period;
+
+        (opens_at, opens_at + self.period)
+    }
+}
+
+/// How much of an early withdrawal is kept in the vault.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PenaltyTerms {
+    /// In basis points.
+    pub rate: u16,
+    /// `PENALTY_SCHEDULE_FLAT` charges `rate` throughout;
+    /// `PENALTY_SCHEDULE_LINEAR` falls linearly from `rate` to `min_rate` as
+    /// the member's payments approach the duration.
+    pub schedule: u8,
+    pub min_rate: u16,
+}
+
+impl PenaltyTerms {
+    /// The rate in basis points for a member who has made `paid_periods`
+    /// payments.
+    pub fn rate(&self, schedule: &Schedule, paid_periods: u8) -> u16 {
+        if self.schedule != PENALTY_SCHEDULE_LINEAR || schedule.duration <= 0 {
+            return self.rate;
+        }
+
+        let progress = ((paid_periods as i64) * schedule.period).min(schedule.duration);
+        let spread = self.rate - self.min_rate;
+
+        self.rate - (((spread as i64) * progress) / schedule.duration) as u16
+    }
+
+    /// The penalty on withdrawing `amount` early, rounded down.
+    pub fn penalty(&self, schedule: &Schedule, paid_periods: u8, amount: u64) -> u64 {
+        let rate = self.rate(schedule, paid_periods);
+        (((amount as u128) * (rate as u128)) / (BPS_DENOMINATOR as u128)) as u64
+    }
+}
+
+/// Votes on a proposal, weighted for signers and one per member otherwise.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Tally {
+    pub approved: u32,
+    pub rejected: u32,
+    pub voters: u32,
+}
+
+impl Tally {
+    /// Adds up the signer votes on a proposal. Signers without a weight
+    /// count once.
+    pub fn signers(weights: &[u16], approvals: &[bool], disapprovals: &[bool]) -> Self {
+        let mut tally = Self::default();
+
+        for (i, (approved, rejected)) in approvals.iter().zip(disapprovals).enumerate() {
+            let weight = weights.get(i).map(|weight| *weight as u32).unwrap_or(1);
+
+            if *approved {
+                tally.approved += weight;
+                tally.voters += 1;
+            } else if *rejected {
+                tally.rejected += weight;
+                tally.voters += 1;
+            }
+        }
+
+        tally
+    }
+}
+
+/// The outcome of applying a governance rule to a tally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Verdict {
+    Passed,
+    QuorumNotReached,
+    Rejected,
+    /// Too few signers approved under an unweighted threshold.
+    NotEnoughApprovals,
+    /// Too little weight approved under a weighted threshold.
+    NotEnoughApprovalWeight,
+}
+
+/// A multisig's voting rules.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignerRules {
+    /// Minimum number of signers who voted either way.
+    pub quorum: u8,
+    pub pass_rule: u8,
+    pub threshold: u8,
+    pub weighted: bool,
+    pub weight_threshold: u32,
+}
+
+impl SignerRules {
+    /// The threshold (or weight threshold) is a floor under every pass rule;
+    /// the majority rules additionally compare approvals with rejections.
+    pub fn verdict(&self, tally: &Tally) -> Verdict {
+        if tally.voters < self.quorum as u32 {
+            return Verdict::QuorumNotReached;
+        }
+
+        if self.weighted {
+            if tally.approved < self.weight_threshold {
+                return Verdict::NotEnoughApprovalWeight;
+            }
+        } else if tally.approved < self.threshold as u32 {
+            return Verdict::NotEnoughApprovals;
+        }
+
+        let passed = match self.pass_rule {
+            PASS_MAJORITY => tally.approved > tally.rejected,
+            PASS_SUPERMAJORITY => {
+                (tally.approved > 0)
+                    && (tally.approved * 3 >= (tally.approved + tally.rejected) * 2)
+            }
+            _ => true,
+        };
+
+        if passed {
+            Verdict::Passed
+        } else {
+            Verdict::Rejected
+        }
+    }
+}
+
+/// A member-governed collection's voting rules, as percentages.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemberRules {
+    pub quorum_pct: u8,
+    pub approval_pct: u8,
+    pub active_members: u8,
+}
+
+impl MemberRules {
+    pub fn verdict(&self, approvals: u32, disapprovals: u32) -> Verdict {
+        let approvals = approvals as u64;
+        let votes = approvals + (disapprovals as u64);
+
+        if votes * 100 < (self.quorum_pct as u64) * (self.active_members as u64) {
+            return Verdict::QuorumNotReached;
+        }
+
+        if (votes == 0)
+            || (approvals * 100 < (self.approval_pct as u64) * (self.active_members as u64))
+        {
+            return Verdict::Rejected;
+        }
+
+        Verdict::Passed
+    }
+}
+
+/// What one round of a rotation pays out when every member has paid in.
+pub fn round_pot(amount_per_period: u64, total_members: u8) -> u64 {
+    amount_per_period * (total_members as u64)
+}
+
+/// Splits what an auction winner gave up, an early withdrawal was charged or a
+/// defaulter's collateral between the other members. Returns each member's share and the total
+/// booked, which can be less than `pot - payout` by the rounding remainder.
+pub fn dividend_split(pot: u64, payout: u64, total_members: u8) -> (u64, u64) {
+    if total_members <= 1 {
+        return (0, 0);
+    }
+
+    let others = (total_members - 1) as u64;
+    let share = (pot - payout) / others;
+
+    (share, share * others)
+}
+
+/// Fisher-Yates shuffle driven by repeatedly hashing `seed`, so anyone with
+/// the seed can replay it.
+pub fn shuffle<T>(order: &mut [T], mut seed: [u8; 32]) {
+    let mut index = order.len();
+
+    while index > 1 {
+        seed = hashv(&[&seed]).to_bytes();
+
+        let draw = u64::from_le_bytes(seed[..8].try_into().unwrap());
+
+        order.swap(index - 1, (draw % (index as u64)) as usize);
+
+        index -= 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dot::program::PENALTY_SCHEDULE_FLAT;
+
+    const DAY: i64 = 24 * 60 * 60;
+    const WEEKLY: Schedule = Schedule {
+        period: 7 * DAY,
+        duration: 4 * 7 * DAY,
+    };
+
+    fn unweighted(threshold: u8, pass_rule: u8, quorum: u8) -> SignerRules {
+        SignerRules {
+            quorum,
+            pass_rule,
+            threshold,
+            weighted: false,
+            weight_threshold: 0,
+        }
+    }
+
+    fn tally(approved: u32, rejected: u32) -> Tally {
+        Tally {
+            approved,
+            rejected,
+            voters: approved + rejected,
+        }
+    }
+
+    #[test]
+    fn cycle_completes_once_payments_cover_the_duration() {
+        assert!(!WEEKLY.cycle_complete(0));
+        assert!(!WEEKLY.cycle_complete(3));
+        assert!(WEEKLY.cycle_complete(4));
+        assert!(WEEKLY.cycle_complete(5));
+    }
+
+    #[test]
+    fn payments_are_due_a_period_apart() {
+        assert!(WEEKLY.payment_due(0, 7 * DAY));
+        assert!(!WEEKLY.payment_due(DAY, 7 * DAY));
+        assert!(WEEKLY.payment_due(DAY, 8 * DAY));
+    }
+
+    #[test]
+    fn default_counts_from_joining_until_the_first_payment() {
+        let joined_at = 100 * DAY;
+
+        assert!(!WEEKLY.in_default(0, 0, joined_at, joined_at + 13 * DAY));
+        assert!(WEEKLY.in_default(0, 0, joined_at, joined_at + 14 * DAY));
+
+        let last_paid = joined_at + 7 * DAY;
+
+        assert!(!WEEKLY.in_default(1, last_paid, joined_at, last_paid + 13 * DAY));
+        assert!(WEEKLY.in_default(1, last_paid, joined_at, last_paid + 14 * DAY));
+    }
+
+    #[test]
+    fn finished_members_are_never_in_default() {
+        assert!(!WEEKLY.in_default(4, 0, 0, 365 * DAY));
+    }
+
+    #[test]
+    fn rounds_follow_each_other_a_period_apart() {
+        assert_eq!(WEEKLY.round_window(1_000, 0), (1_000, 1_000 + 7 * DAY));
+        assert_eq!(
+            WEEKLY.round_window(1_000, 2),
+            (1_000 + 14 * DAY, 1_000 + 21 * DAY)
+        );
+    }
+
+    #[test]
+    fn flat_penalty_ignores_progress() {
+        let terms = PenaltyTerms {
+            rate: 500,
+            schedule: PENALTY_SCHEDULE_FLAT,
+            min_rate: 100,
+        };
+
+        assert_eq!(terms.penalty(&WEEKLY, 0, 1_000_000), 50_000);
+        assert_eq!(terms.penalty(&WEEKLY, 3, 1_000_000), 50_000);
+    }
+
+    #[test]
+    fn linear_penalty_falls_to_the_minimum() {
+        let terms = PenaltyTerms {
+            rate: 500,
+            schedule: PENALTY_SCHEDULE_LINEAR,
+            min_rate: 100,
+        };
+
+        assert_eq!(terms.rate(&WEEKLY, 0), 500);
+        assert_eq!(terms.rate(&WEEKLY, 1), 400);
+        assert_eq!(terms.rate(&WEEKLY, 2), 300);
+        assert_eq!(terms.rate(&WEEKLY, 4), 100);
+        // Payments past the duration don't push the rate below the minimum
+        assert_eq!(terms.rate(&WEEKLY, 9), 100);
+        assert_eq!(terms.penalty(&WEEKLY, 2, 1_000_000), 30_000);
+    }
+
+    #[test]
+    fn linear_penalty_rounds_down() {
+        let terms = PenaltyTerms {
+            rate: 1_000,
+            schedule: PENALTY_SCHEDULE_LINEAR,
+            min_rate: 0,
+        };
+        let schedule = Schedule {
+            period: 1,
+            duration: 3,
+        };
+
+        // 1000 - 1000 / 3 leaves 667 basis points
+        assert_eq!(terms.rate(&schedule, 1), 667);
+        assert_eq!(terms.penalty(&schedule, 1, 999), 66);
+    }
+
+    #[test]
+    fn penalty_does_not_overflow_on_large_amounts() {
+        let terms = PenaltyTerms {
+            rate: 10_000,
+            schedule: PENALTY_SCHEDULE_FLAT,
+            min_rate: 0,
+        };
+
+        assert_eq!(terms.penalty(&WEEKLY, 0, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn signer_tally_applies_weights() {
+        let approvals = [true, false, true, false];
+        let disapprovals = [false, true, false, false];
+
+        assert_eq!(
+            Tally::signers(&[], &approvals, &disapprovals),
+            Tally {
+                approved: 2,
+                rejected: 1,
+                voters: 3,
+            }
+        );
+        assert_eq!(
+            Tally::signers(&[5, 3, 2, 7], &approvals, &disapprovals),
+            Tally {
+                approved: 7,
+                rejected: 3,
+                voters: 3,
+            }
+        );
+    }
+
+    #[test]
+    fn threshold_counts_approvals_only() {
+        let rules = unweighted(2, 0, 0);
+
+        assert_eq!(rules.verdict(&tally(1, 0)), Verdict::NotEnoughApprovals);
+        assert_eq!(rules.verdict(&tally(2, 3)), Verdict::Passed);
+
+        let weighted = SignerRules {
+            weighted: true,
+            weight_threshold: 10,
+            ..rules
+        };
+
+        assert_eq!(
+            weighted.verdict(&tally(9, 0)),
+            Verdict::NotEnoughApprovalWeight
+        );
+        assert_eq!(weighted.verdict(&tally(10, 0)), Verdict::Passed);
+    }
+
+    #[test]
+    fn majority_and_supermajority() {
+        let majority = unweighted(0, PASS_MAJORITY, 0);
+
+        assert_eq!(majority.verdict(&tally(2, 2)), Verdict::Rejected);
+        assert_eq!(majority.verdict(&tally(3, 2)), Verdict::Passed);
+
+        let supermajority = unweighted(0, PASS_SUPERMAJORITY, 0);
+
+        assert_eq!(supermajority.verdict(&tally(0, 0)), Verdict::Rejected);
+        assert_eq!(supermajority.verdict(&tally(3, 2)), Verdict::Rejected);
+        assert_eq!(supermajority.verdict(&tally(2, 1)), Verdict::Passed);
+
+        // The threshold still has to be met
+        let majority_of_three = unweighted(3, PASS_MAJORITY, 0);
+
+        assert_eq!(
+            majority_of_three.verdict(&tally(2, 0)),
+            Verdict::NotEnoughApprovals
+        );
+        assert_eq!(majority_of_three.verdict(&tally(3, 2)), Verdict::Passed);
+
+        let weighted = SignerRules {
+            weighted: true,
+            weight_threshold: 10,
+            ..supermajority
+        };
+
+        assert_eq!(
+            weighted.verdict(&tally(9, 0)),
+            Verdict::NotEnoughApprovalWeight
+        );
+        assert_eq!(weighted.verdict(&tally(10, 5)), Verdict::Passed);
+    }
+
+    #[test]
+    fn signer_quorum_counts_voters_not_weight() {
+        let rules = unweighted(0, PASS_MAJORITY, 3);
+        let short = Tally {
+            approved: 10,
+            rejected: 0,
+            voters: 2,
+        };
+
+        assert_eq!(rules.verdict(&short), Verdict::QuorumNotReached);
+        assert_eq!(
+            rules.verdict(&Tally { voters: 3, ..short }),
+            Verdict::Passed
+        );
+    }
+
+    #[test]
+    fn member_votes_need_quorum_then_approval() {
+        let rules = MemberRules {
+            quorum_pct: 50,
+            approval_pct: 60,
+            active_members: 5,
+        };
+
+        assert_eq!(rules.verdict(0, 0), Verdict::QuorumNotReached);
+        assert_eq!(rules.verdict(2, 0), Verdict::QuorumNotReached);
+        assert_eq!(rules.verdict(1, 2), Verdict::Rejected);
+        assert_eq!(rules.verdict(3, 2), Verdict::Passed);
+        assert_eq!(rules.verdict(3, 0), Verdict::Passed);
+
+        // Approval counts against every active member, not just the voters
+        assert_eq!(rules.verdict(2, 1), Verdict::Rejected);
+        assert_eq!(rules.verdict(3, 1), Verdict::Passed);
+
+        let no_quorum = MemberRules {
+            quorum_pct: 0,
+            ..rules
+        };
+
+        // Nobody voting never passes, even without a quorum
+        assert_eq!(no_quorum.verdict(0, 0), Verdict::Rejected);
+    }
+
+    #[test]
+    fn dividends_leave_the_remainder_in_the_pot() {
+        assert_eq!(round_pot(100, 4), 400);
+        assert_eq!(dividend_split(400, 300, 4), (33, 99));
+        assert_eq!(dividend_split(400, 400, 4), (0, 0));
+        assert_eq!(dividend_split(100, 40, 1), (0, 0));
+    }
+
+    #[test]
+    fn shuffle_is_a_replayable_permutation() {
+        let seed = [7u8; 32];
+        let mut first: Vec<u8> = (0..10).collect();
+        let mut second = first.clone();
+
+        shuffle(&mut first, seed);
+        shuffle(&mut second, seed);
+
+        assert_eq!(first, second);
+        assert_ne!(first, (0..10).collect::<Vec<u8>>());
+
+        first.sort();
+
+        assert_eq!(first, (0..10).collect::<Vec<u8>>());
+    }
+}
@@ -0,0 +1,41 @@
+//! PDA seed prefixes and encoders shared by every `seeds = [...]` attribute.
+//! Integers are encoded as little-endian bytes to match standard client derivation.
+
+use crate::dot::program::{RotarySavingsError, MAX_NAME_LEN};
+use anchor_lang::prelude::*;
+
+pub const COLLECTION: &[u8] = b"collection";
+
+pub const MULTISIG: &[u8] = b"multisig";
+
+pub const PROPOSAL: &[u8] = b"proposal";
+
+pub const SWAP: &[u8] = b"swap";
+
+pub const USER: &[u8] = b"user";
+
+pub const VOTE: &[u8] = b"vote";
+
+/// The collection name as a seed. `init` derives the address before any
+/// constraint runs, and a seed over 32 bytes aborts the derivation, so the
+/// length is checked here.
+pub fn collection_name(name: &str) -> Result<&[u8]> {
+    require!(name.len() <= MAX_NAME_LEN, RotarySavingsError::NameTooLong);
+
+    Ok(name.as_bytes())
+}
+
+pub fn proposal_id(id: u64) -> [u8; 8] {
+    id.to_le_bytes()
+}
+
+pub fn round(round: u8) -> [u8; 1] {
+    round.to_le_bytes()
+}
+
+/// Seed used for proposals created before ids were binary-encoded: the
+/// multisig nonce at creation, Debug-formatted. Only `migrate_proposal`
+/// derives it.
+pub fn legacy_proposal_id(nonce: u64) -> String {
+    format!("{:?}", nonce)
+}
@@ -0,0 +1,167 @@
+mod common;
+
+use anchor_lang::prelude::*;
+use common::*;
+use rotary_savings::{client, dot::program::*, instruction};
+use solana_sdk::signature::{Keypair, Signer};
+
+async fn data(env: &mut TestEnv, address: &Pubkey) -> Vec<u8> {
+    env.context
+        .banks_client
+        .get_account(*address)
+        .await
+        .unwrap()
+        .unwrap()
+        .data
+}
+
+#[tokio::test]
+async fn builders_drive_a_withdrawal() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let member = env.funded_keypair().await;
+    let signer = env.funded_keypair().await;
+    let collection = client::collection_pda(&admin.pubkey(), "Savings");
+
+    env.send(
+        &[
+            client::create_collection(
+                &admin.pubkey(),
+                instruction::CreateCollection {
+                    name: "Savings".to_string(),
+                    duration: 2 * PERIOD,
+                    period: PERIOD,
+                    amount_per_period: AMOUNT,
+                    total_members: 1,
+                    early_withdrawal_penalty_rate: 0,
+                    penalty_schedule: PENALTY_SCHEDULE_FLAT,
+                    min_early_withdrawal_penalty_rate: 0,
+                    collateral_amount: 0,
+                    payout_order_mode: PAYOUT_NONE,
+                    governance_mode: GOVERNANCE_MULTISIG,
+                    quorum_pct: 0,
+                    approval_pct: 0,
+                },
+            ),
+            client::add_user(&admin.pubkey(), &collection, &member.pubkey()),
+            client::create_multisig(
+                &admin.pubkey(),
+                &collection,
+                instruction::CreateMultisig {
+                    signers: vec![signer.pubkey()],
+                    threshold: 1,
+                    weights: vec![],
+                    weight_threshold: 0,
+                    quorum: 0,
+                    pass_rule: PASS_THRESHOLD,
+                },
+            ),
+        ],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    for _ in 0..2 {
+        env.send(
+            &[client::pay(&member.pubkey(), &collection, AMOUNT)],
+            &[&member],
+        )
+        .await
+        .unwrap();
+        env.warp(PERIOD).await;
+    }
+
+    let user_account = client::user_pda(&collection, &member.pubkey());
+    let user = client::deserialize_user(&data(&mut env, &user_account).await).unwrap();
+
+    assert_eq!(user.user, member.pubkey());
+    assert_eq!(user.total_paid, 2 * AMOUNT);
+    assert!(user.can_withdraw);
+
+    env.send(
+        &[client::propose_withdraw(&member.pubkey(), &collection, 1)],
+        &[&member],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[client::vote_on_proposal(
+            &signer.pubkey(),
+            &collection,
+            1,
+            true,
+        )],
+        &[&signer],
+    )
+    .await
+    .unwrap();
+
+    let proposal_address = client::proposal_pda(&collection, 1);
+    let proposal = client::deserialize_proposal(&data(&mut env, &proposal_address).await).unwrap();
+
+    assert_eq!(proposal.withdraw_user, member.pubkey());
+    assert_eq!(proposal.approvals, vec![true]);
+
+    env.send(
+        &[client::execute_proposal(&collection, 1, &user_account)],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let account = client::deserialize_collection(&data(&mut env, &collection).await).unwrap();
+    let multisig =
+        client::deserialize_multisig(&data(&mut env, &client::multisig_pda(&collection)).await)
+            .unwrap();
+
+    assert_eq!(account.admin, admin.pubkey());
+    assert_eq!(account.total_balance, 0);
+    assert_eq!(multisig.signers, vec![signer.pubkey()]);
+    assert!(multisig.open_proposals.is_empty());
+}
+
+#[tokio::test]
+async fn deserializers_check_the_account_type() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let collection_data = data(&mut env, &collection).await;
+
+    assert!(client::deserialize_collection(&collection_data).is_ok());
+    assert!(client::deserialize_user(&collection_data).is_err());
+    assert!(client::deserialize_proposal(&collection_data).is_err());
+    assert!(client::deserialize::<Swap>(&collection_data[..8]).is_err());
+}
+
+#[test]
+fn builders_derive_the_program_addresses() {
+    let admin = Keypair::new().pubkey();
+    let member = Keypair::new().pubkey();
+    let collection = client::collection_pda(&admin, "Savings");
+    let proposal = client::proposal_pda(&collection, 3);
+
+    let ix = client::cast_vote(&admin, &collection, &member, 3, true);
+
+    assert_eq!(ix.program_id, rotary_savings::ID);
+    assert_eq!(ix.accounts[0].pubkey, admin);
+    assert!(ix.accounts[0].is_signer);
+    assert_eq!(
+        ix.accounts[2].pubkey,
+        client::user_pda(&collection, &member)
+    );
+    assert_eq!(ix.accounts[3].pubkey, proposal);
+    assert_eq!(ix.accounts[4].pubkey, client::vote_pda(&proposal, &member));
+
+    let ix = client::migrate_proposal(&admin, &collection, &member, 3);
+
+    assert_eq!(ix.accounts[2].pubkey, client::multisig_pda(&collection));
+    assert_eq!(
+        ix.accounts[3].pubkey,
+        client::legacy_proposal_pda(&collection, 3)
+    );
+    assert_eq!(ix.accounts[4].pubkey, member);
+    assert!(ix.accounts[4].is_writable);
+    assert_eq!(ix.accounts[5].pubkey, proposal);
+}
@@ -0,0 +1,884 @@
+mod common;
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{ed25519_program, hash::hashv, instruction::Instruction},
+    system_program,
+};
+use common::*;
+use rotary_savings::{accounts, client, dot::program::*, instruction};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    sysvar,
+};
+
+/// A change to the default parameters and the error it should be rejected with.
+type InvalidParams = (fn(&mut CollectionParams), RotarySavingsError);
+
+/// Builds the ed25519 check an invite rides on: `signer` over
+/// `collection || member`, with every offset pointing into this instruction.
+fn invite_ix(signer: &Keypair, collection: &Pubkey, member: &Pubkey) -> Instruction {
+    const PUBLIC_KEY_OFFSET: u16 = 16;
+    const SIGNATURE_OFFSET: u16 = PUBLIC_KEY_OFFSET + 32;
+    const MESSAGE_OFFSET: u16 = SIGNATURE_OFFSET + 64;
+
+    let message = [collection.as_ref(), member.as_ref()].concat();
+    let signature = signer.sign_message(&message);
+    let mut data = vec![1, 0];
+
+    for offset in [
+        SIGNATURE_OFFSET,
+        u16::MAX,
+        PUBLIC_KEY_OFFSET,
+        u16::MAX,
+        MESSAGE_OFFSET,
+        message.len() as u16,
+        u16::MAX,
+    ] {
+        data.extend_from_slice(&offset.to_le_bytes());
+    }
+
+    data.extend_from_slice(signer.pubkey().as_ref());
+    data.extend_from_slice(signature.as_ref());
+    data.extend_from_slice(&message);
+
+    Instruction {
+        program_id: ed25519_program::ID,
+        accounts: vec![],
+        data,
+    }
+}
+
+fn leaf(member: &Pubkey) -> [u8; 32] {
+    hashv(&[member.as_ref()]).to_bytes()
+}
+
+fn parent(left: [u8; 32], right: [u8; 32]) -> [u8; 32] {
+    if left <= right {
+        hashv(&[&left, &right]).to_bytes()
+    } else {
+        hashv(&[&right, &left]).to_bytes()
+    }
+}
+
+#[tokio::test]
+async fn create_collection_validates_settings() {
+    let mut env = TestEnv::new().await;
+    let cases: Vec<InvalidParams> = vec![
+        (
+            |params| params.total_members = 0,
+            RotarySavingsError::InvalidTotalMembers,
+        ),
+        (
+            |params| params.duration = 0,
+            RotarySavingsError::InvalidDuration,
+        ),
+        (
+            |params| params.period = 0,
+            RotarySavingsError::InvalidPeriod,
+        ),
+        (
+            |params| params.duration = PERIOD + DAY,
+            RotarySavingsError::DurationNotMultipleOfPeriod,
+        ),
+        (
+            |params| params.amount_per_period = 0,
+            RotarySavingsError::InvalidAmountPerPeriod,
+        ),
+        (
+            |params| params.early_withdrawal_penalty_rate = 10_001,
+            RotarySavingsError::PenaltyRateTooHigh,
+        ),
+        (
+            |params| params.penalty_schedule = 2,
+            RotarySavingsError::InvalidPenaltySchedule,
+        ),
+        (
+            |params| params.min_early_withdrawal_penalty_rate = 501,
+            RotarySavingsError::MinPenaltyRateTooHigh,
+        ),
+        (
+            |params| params.payout_order_mode = PAYOUT_AUCTION + 1,
+            RotarySavingsError::InvalidPayoutOrderMode,
+        ),
+        (
+            |params| {
+                params.payout_order_mode = PAYOUT_FIXED;
+                params.duration = 2 * PERIOD;
+            },
+            RotarySavingsError::RoundsMustMatchMembers,
+        ),
+        (
+            |params| params.governance_mode = GOVERNANCE_MEMBERS + 1,
+            RotarySavingsError::InvalidGovernance,
+        ),
+        (
+            |params| {
+                params.governance_mode = GOVERNANCE_MEMBERS;
+                params.approval_pct = 0;
+            },
+            RotarySavingsError::InvalidGovernance,
+        ),
+    ];
+
+    for (change, expected) in cases {
+        let mut params = CollectionParams::default();
+
+        change(&mut params);
+
+        let result = env.try_create_collection("Savings", params).await;
+
+        assert_error(result, expected);
+    }
+
+    let result = env
+        .try_create_collection("", CollectionParams::default())
+        .await;
+
+    assert_error(result, RotarySavingsError::EmptyName);
+
+    // Checked before the name is used as a seed, which would abort the
+    // instruction
+    let admin = env.admin.insecure_clone();
+    let long_name = "n".repeat(33);
+    let result = env
+        .send(
+            &[ix(
+                accounts::CreateCollection {
+                    admin: admin.pubkey(),
+                    collection: Pubkey::new_unique(),
+                    rent: sysvar::rent::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::CreateCollection {
+                    name: long_name,
+                    duration: 3 * PERIOD,
+                    period: PERIOD,
+                    amount_per_period: AMOUNT,
+                    total_members: 3,
+                    early_withdrawal_penalty_rate: 500,
+                    penalty_schedule: PENALTY_SCHEDULE_FLAT,
+                    min_early_withdrawal_penalty_rate: 0,
+                    collateral_amount: 0,
+                    payout_order_mode: 0,
+                    governance_mode: 0,
+                    quorum_pct: 0,
+                    approval_pct: 0,
+                },
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NameTooLong);
+
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.admin, admin.pubkey());
+    assert_eq!(account.creator, admin.pubkey());
+    assert_eq!(account.name, "Savings");
+    assert_eq!(account.total_members, 3);
+    assert_eq!(account.active_members, 0);
+    assert!(account.is_active);
+    assert!(!account.is_paused);
+    assert_eq!(account.multisig, Pubkey::default());
+}
+
+#[tokio::test]
+async fn add_user_fills_the_collection() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let outsider = env.funded_keypair().await;
+    let result = env
+        .send(
+            &[client::add_user(
+                &outsider.pubkey(),
+                &collection,
+                &outsider.pubkey(),
+            )],
+            &[&outsider],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    let members = env.add_members(&collection, 3).await;
+    let now = env.now().await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.active_members, 3);
+    assert_eq!(account.rotation_started_at, now);
+    assert_eq!(
+        account.payout_order,
+        members.iter().map(|m| m.pubkey()).collect::<Vec<_>>()
+    );
+
+    let user: User = env
+        .fetch(&user_pda(&collection, &members[0].pubkey()))
+        .await;
+
+    assert_eq!(user.collection, collection);
+    assert_eq!(user.user, members[0].pubkey());
+    assert_eq!(user.paid_periods, 0);
+    assert_eq!(user.joined_at, now);
+
+    let result = env.try_add_user(&collection, &Pubkey::new_unique()).await;
+
+    assert_error(result, RotarySavingsError::CollectionFull);
+}
+
+#[tokio::test]
+async fn join_through_the_allow_list() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection(
+            "Savings",
+            CollectionParams {
+                collateral_amount: AMOUNT / 2,
+                ..Default::default()
+            },
+        )
+        .await;
+    let member = env.funded_keypair().await;
+    let other = env.funded_keypair().await;
+    let outsider = env.funded_keypair().await;
+
+    let result = env
+        .send(
+            &[client::join_collection(
+                &member.pubkey(),
+                &collection,
+                vec![],
+            )],
+            &[&member],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::GovernanceNotConfigured);
+
+    env.create_multisig(&collection, 1, 1).await;
+
+    let result = env
+        .send(
+            &[client::join_collection(
+                &member.pubkey(),
+                &collection,
+                vec![],
+            )],
+            &[&member],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::EnrollmentClosed);
+
+    let root = parent(leaf(&member.pubkey()), leaf(&other.pubkey()));
+    let result = env
+        .send(
+            &[client::set_enrollment(
+                &outsider.pubkey(),
+                &collection,
+                root,
+                &Pubkey::default(),
+            )],
+            &[&outsider],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.send(
+        &[client::set_enrollment(
+            &admin.pubkey(),
+            &collection,
+            root,
+            &Pubkey::default(),
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let proof = vec![leaf(&other.pubkey())];
+    let result = env
+        .send(
+            &[client::join_collection(
+                &outsider.pubkey(),
+                &collection,
+                proof.clone(),
+            )],
+            &[&outsider],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotOnAllowList);
+
+    let vault_before = env.balance(&collection).await;
+
+    env.send(
+        &[client::join_collection(
+            &member.pubkey(),
+            &collection,
+            proof,
+        )],
+        &[&member],
+    )
+    .await
+    .unwrap();
+
+    let user: User = env.fetch(&user_pda(&collection, &member.pubkey())).await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(user.user, member.pubkey());
+    assert_eq!(user.collateral, AMOUNT / 2);
+    assert_eq!(account.total_collateral, AMOUNT / 2);
+    assert_eq!(account.active_members, 1);
+    assert_eq!(env.balance(&collection).await, vault_before + AMOUNT / 2);
+}
+
+#[tokio::test]
+async fn join_with_a_signed_invite() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection(
+            "Savings",
+            CollectionParams {
+                total_members: 1,
+                duration: PERIOD,
+                ..Default::default()
+            },
+        )
+        .await;
+    let invite_signer = Keypair::new();
+    let forger = Keypair::new();
+    let member = env.funded_keypair().await;
+
+    env.create_multisig(&collection, 1, 1).await;
+    env.send(
+        &[client::set_enrollment(
+            &admin.pubkey(),
+            &collection,
+            [0; 32],
+            &invite_signer.pubkey(),
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .send(
+            &[client::join_collection(
+                &member.pubkey(),
+                &collection,
+                vec![],
+            )],
+            &[&member],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::MissingInvite);
+
+    let result = env
+        .send(
+            &[
+                invite_ix(&forger, &collection, &member.pubkey()),
+                client::join_collection(&member.pubkey(), &collection, vec![]),
+            ],
+            &[&member],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidInvite);
+
+    env.send(
+        &[
+            invite_ix(&invite_signer, &collection, &member.pubkey()),
+            client::join_collection(&member.pubkey(), &collection, vec![]),
+        ],
+        &[&member],
+    )
+    .await
+    .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.active_members, 1);
+
+    let late = env.funded_keypair().await;
+    let result = env
+        .send(
+            &[
+                invite_ix(&invite_signer, &collection, &late.pubkey()),
+                client::join_collection(&late.pubkey(), &collection, vec![]),
+            ],
+            &[&late],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::CollectionFull);
+}
+
+#[tokio::test]
+async fn pay_waits_a_period_and_unlocks_withdrawal_after_the_duration() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let member = &members[0];
+
+    let result = env.try_pay(&collection, member).await;
+
+    assert_error(result, RotarySavingsError::GovernanceNotConfigured);
+
+    env.create_multisig(&collection, 1, 1).await;
+
+    let vault_before = env.balance(&collection).await;
+
+    env.try_pay(&collection, member).await.unwrap();
+
+    let result = env.try_pay(&collection, member).await;
+
+    assert_error(result, RotarySavingsError::PaymentNotDue);
+
+    // Someone else's membership cannot be paid into
+    let stranger = env.funded_keypair().await;
+    let result = env
+        .send(
+            &[ix(
+                accounts::Pay {
+                    user: stranger.pubkey(),
+                    collection,
+                    user_account: user_pda(&collection, &member.pubkey()),
+                    clock: sysvar::clock::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::Pay { amount: AMOUNT },
+            )],
+            &[&stranger],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidUser);
+
+    // Nor can a membership be paid into another collection's vault
+    let other = env
+        .create_collection("Other", CollectionParams::default())
+        .await;
+
+    env.create_multisig(&other, 1, 1).await;
+
+    let result = env
+        .send(
+            &[ix(
+                accounts::Pay {
+                    user: member.pubkey(),
+                    collection: other,
+                    user_account: user_pda(&collection, &member.pubkey()),
+                    clock: sysvar::clock::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::Pay { amount: AMOUNT },
+            )],
+            &[member],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidUser);
+
+    env.warp(PERIOD).await;
+    env.try_pay(&collection, member).await.unwrap();
+
+    let user: User = env.fetch(&user_pda(&collection, &member.pubkey())).await;
+
+    assert_eq!(user.paid_periods, 2);
+    assert!(!user.can_withdraw);
+
+    env.warp(PERIOD).await;
+    env.try_pay(&collection, member).await.unwrap();
+
+    let user: User = env.fetch(&user_pda(&collection, &member.pubkey())).await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(user.paid_periods, 3);
+    assert_eq!(user.total_paid, 3 * AMOUNT);
+    assert!(user.can_withdraw);
+    assert_eq!(account.total_balance, 3 * AMOUNT);
+    assert_eq!(env.balance(&collection).await, vault_before + 3 * AMOUNT);
+}
+
+#[tokio::test]
+async fn collateral_is_slashed_on_default_and_returned_after_the_cycle() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collateral = AMOUNT / 2;
+    let collection = env
+        .create_collection(
+            "Savings",
+            CollectionParams {
+                collateral_amount: collateral,
+                ..Default::default()
+            },
+        )
+        .await;
+    let members = env.add_members(&collection, 2).await;
+    let deposit = |member: &Keypair| client::deposit_collateral(&member.pubkey(), &collection);
+    let slash = |admin: &Keypair, member: &Keypair| {
+        client::slash_collateral(&admin.pubkey(), &collection, &member.pubkey())
+    };
+    let reclaim = |member: &Keypair| client::reclaim_collateral(&member.pubkey(), &collection);
+    let claim = |member: &Keypair| client::claim_dividends(&member.pubkey(), &collection);
+
+    let result = env.send(&[deposit(&members[0])], &[&members[0]]).await;
+
+    assert_error(result, RotarySavingsError::GovernanceNotConfigured);
+
+    env.create_multisig(&collection, 1, 1).await;
+
+    let result = env.try_pay(&collection, &members[0]).await;
+
+    assert_error(result, RotarySavingsError::CollateralRequired);
+
+    for member in &members {
+        env.send(&[deposit(member)], &[member]).await.unwrap();
+    }
+
+    let result = env.send(&[deposit(&members[0])], &[&members[0]]).await;
+
+    assert_error(result, RotarySavingsError::CollateralAlreadyDeposited);
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.total_collateral, 2 * collateral);
+
+    // A membership only moves collateral in and out of its own collection
+    let other = env
+        .create_collection(
+            "Other",
+            CollectionParams {
+                collateral_amount: collateral,
+                ..Default::default()
+            },
+        )
+        .await;
+
+    for (mut ix, signer) in [
+        (deposit(&members[0]), &members[0]),
+        (reclaim(&members[0]), &members[0]),
+        (slash(&admin, &members[0]), &admin),
+    ] {
+        ix.accounts[1].pubkey = other;
+
+        let result = env.send(&[ix], &[signer]).await;
+
+        assert_error(result, RotarySavingsError::InvalidUser);
+    }
+
+    let result = env.send(&[reclaim(&members[0])], &[&members[0]]).await;
+
+    assert_error(result, RotarySavingsError::CollateralLocked);
+
+    let result = env.send(&[slash(&admin, &members[1])], &[&admin]).await;
+
+    assert_error(result, RotarySavingsError::MemberNotInDefault);
+
+    // Only the first member keeps paying; the second misses two periods
+    env.pay_periods(&collection, &members[..1], 2).await;
+
+    let result = env
+        .send(&[slash(&members[0], &members[1])], &[&members[0]])
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.send(&[slash(&admin, &members[1])], &[&admin])
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+    let user: User = env
+        .fetch(&user_pda(&collection, &members[1].pubkey()))
+        .await;
+
+    // The slashed collateral is owed to the one member who kept paying
+    assert_eq!(user.collateral, 0);
+    assert_eq!(account.total_collateral, collateral);
+    assert_eq!(account.total_balance, 2 * AMOUNT);
+    assert_eq!(account.dividends_outstanding, collateral);
+
+    let result = env.send(&[reclaim(&members[1])], &[&members[1]]).await;
+
+    assert_error(result, RotarySavingsError::NoCollateral);
+
+    let result = env.send(&[claim(&members[1])], &[&members[1]]).await;
+
+    assert_error(result, RotarySavingsError::NoDividends);
+
+    let vault_before = env.balance(&collection).await;
+    let balance_before = env.balance(&members[0].pubkey()).await;
+
+    env.send(&[claim(&members[0])], &[&members[0]])
+        .await
+        .unwrap();
+
+    assert_eq!(env.balance(&collection).await, vault_before - collateral);
+    assert_eq!(
+        env.balance(&members[0].pubkey()).await,
+        balance_before + collateral
+    );
+
+    env.try_pay(&collection, &members[0]).await.unwrap();
+
+    let balance_before = env.balance(&members[0].pubkey()).await;
+
+    env.send(&[reclaim(&members[0])], &[&members[0]])
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.total_collateral, 0);
+    assert_eq!(
+        env.balance(&members[0].pubkey()).await,
+        balance_before + collateral
+    );
+}
+
+#[tokio::test]
+async fn co_admins_share_the_day_to_day_admin_role() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let co_admin = env.funded_keypair().await;
+    let set_co_admins = |admin: &Keypair, co_admins: Vec<Pubkey>| {
+        client::set_co_admins(&admin.pubkey(), &collection, co_admins)
+    };
+
+    let result = env
+        .send(
+            &[set_co_admins(&co_admin, vec![co_admin.pubkey()])],
+            &[&co_admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    for co_admins in [
+        vec![admin.pubkey()],
+        vec![Pubkey::default()],
+        vec![co_admin.pubkey(), co_admin.pubkey()],
+        (0..4).map(|_| Pubkey::new_unique()).collect(),
+    ] {
+        let result = env
+            .send(&[set_co_admins(&admin, co_admins)], &[&admin])
+            .await;
+
+        assert_error(result, RotarySavingsError::InvalidCoAdmins);
+    }
+
+    env.send(&[set_co_admins(&admin, vec![co_admin.pubkey()])], &[&admin])
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.co_admins, vec![co_admin.pubkey()]);
+
+    let member = Pubkey::new_unique();
+
+    env.send(
+        &[client::add_user(&co_admin.pubkey(), &collection, &member)],
+        &[&co_admin],
+    )
+    .await
+    .unwrap();
+
+    // Co-admins cannot appoint each other, a guardian or a pause key
+    let result = env
+        .send(&[set_co_admins(&co_admin, vec![])], &[&co_admin])
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    let result = env
+        .send(
+            &[client::set_guardian(
+                &co_admin.pubkey(),
+                &collection,
+                &co_admin.pubkey(),
+            )],
+            &[&co_admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    let result = env
+        .send(
+            &[client::set_pause_key(
+                &co_admin.pubkey(),
+                &collection,
+                &co_admin.pubkey(),
+            )],
+            &[&co_admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    // Nor pick the signers that govern the vault
+    let result = env
+        .send(
+            &[client::create_multisig(
+                &co_admin.pubkey(),
+                &collection,
+                instruction::CreateMultisig {
+                    signers: vec![co_admin.pubkey()],
+                    threshold: 1,
+                    weights: vec![],
+                    weight_threshold: 0,
+                    quorum: 0,
+                    pass_rule: PASS_THRESHOLD,
+                },
+            )],
+            &[&co_admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+    assert!(!env.exists(&multisig_pda(&collection)).await);
+}
+
+#[tokio::test]
+async fn emergency_pause_stops_deposits_and_enrollment() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let pause_key = env.funded_keypair().await;
+    let set_pause_key =
+        |admin: &Keypair| client::set_pause_key(&admin.pubkey(), &collection, &pause_key.pubkey());
+    let emergency_pause =
+        |pause_key: &Keypair| client::emergency_pause(&pause_key.pubkey(), &collection);
+
+    env.create_multisig(&collection, 1, 1).await;
+
+    let result = env
+        .send(&[emergency_pause(&pause_key)], &[&pause_key])
+        .await;
+
+    assert_error(result, RotarySavingsError::NotPauseKey);
+
+    let result = env.send(&[set_pause_key(&pause_key)], &[&pause_key]).await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.send(&[set_pause_key(&admin)], &[&admin]).await.unwrap();
+
+    let result = env.send(&[emergency_pause(&admin)], &[&admin]).await;
+
+    assert_error(result, RotarySavingsError::NotPauseKey);
+
+    env.send(&[emergency_pause(&pause_key)], &[&pause_key])
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert!(account.is_paused);
+
+    let result = env
+        .send(&[emergency_pause(&pause_key)], &[&pause_key])
+        .await;
+
+    assert_error(result, RotarySavingsError::AlreadyPaused);
+
+    let result = env.try_pay(&collection, &members[0]).await;
+
+    assert_error(result, RotarySavingsError::CollectionPaused);
+
+    let result = env.try_add_user(&collection, &Pubkey::new_unique()).await;
+
+    assert_error(result, RotarySavingsError::CollectionPaused);
+
+    let result = env
+        .send(
+            &[client::deposit_collateral(
+                &members[0].pubkey(),
+                &collection,
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::CollectionPaused);
+}
+
+#[tokio::test]
+async fn guardian_freeze_is_bounded_and_cannot_be_extended() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let guardian = env.funded_keypair().await;
+    let set_guardian =
+        |admin: &Keypair| client::set_guardian(&admin.pubkey(), &collection, &guardian.pubkey());
+    let freeze = |guardian: &Keypair, duration: i64| {
+        client::guardian_freeze(&guardian.pubkey(), &collection, duration)
+    };
+
+    let result = env.send(&[freeze(&guardian, DAY)], &[&guardian]).await;
+
+    assert_error(result, RotarySavingsError::NotGuardian);
+
+    let result = env.send(&[set_guardian(&guardian)], &[&guardian]).await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.send(&[set_guardian(&admin)], &[&admin]).await.unwrap();
+
+    for duration in [0, MAX_FREEZE_DURATION + 1] {
+        let result = env.send(&[freeze(&guardian, duration)], &[&guardian]).await;
+
+        assert_error(result, RotarySavingsError::InvalidFreezeDuration);
+    }
+
+    env.send(&[freeze(&guardian, DAY)], &[&guardian])
+        .await
+        .unwrap();
+
+    let now = env.now().await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.frozen_until, now + DAY);
+
+    let result = env
+        .send(&[freeze(&guardian, MAX_FREEZE_DURATION)], &[&guardian])
+        .await;
+
+    assert_error(result, RotarySavingsError::AlreadyFrozen);
+
+    // Once it runs out the guardian still waits out the cooldown
+    env.warp(DAY).await;
+
+    let result = env.send(&[freeze(&guardian, DAY)], &[&guardian]).await;
+
+    assert_error(result, RotarySavingsError::FreezeCooldown);
+
+    env.warp(FREEZE_COOLDOWN - DAY).await;
+    env.send(&[freeze(&guardian, DAY)], &[&guardian])
+        .await
+        .unwrap();
+}
@@ -0,0 +1,636 @@
+//! Shared harness for the program-test suites. The program runs natively
+//! inside a local bank, so tests can move the clock instead of waiting on a
+//! validator.
+
+#![allow(dead_code, unused_imports)]
+
+use anchor_lang::{
+    prelude::*,
+    solana_program::{entrypoint::ProgramResult, instruction::Instruction},
+    system_program, AccountDeserialize, InstructionData, ToAccountMetas,
+};
+pub use rotary_savings::client::{
+    collection_pda, multisig_pda, proposal_pda, swap_pda, user_pda, vote_pda,
+};
+use rotary_savings::{accounts, client, dot::program::*, instruction};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    account::{Account, AccountSharedData},
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    system_instruction, sysvar,
+    transaction::{Transaction, TransactionError},
+};
+
+pub const DAY: i64 = 24 * 60 * 60;
+pub const PERIOD: i64 = 7 * DAY;
+pub const AMOUNT: u64 = 100_000_000;
+
+pub type TxResult = std::result::Result<(), BanksClientError>;
+
+/// An account as the original program wrote it: `legacy` under the current
+/// type's discriminator, padded to the `size_of::<T>() + 8` bytes it was
+/// allocated.
+pub fn legacy_data<T: AnchorSerialize>(discriminator: [u8; 8], legacy: &T) -> Vec<u8> {
+    let mut data = discriminator.to_vec();
+
+    legacy.serialize(&mut data).unwrap();
+    data.resize(std::mem::size_of::<T>() + 8, 0);
+    data
+}
+
+/// An adjust-settings proposal as the original program wrote it: no id,
+/// settings mask or schedule, and a 7% rate stored in percent. The original
+/// multisig only had room for no signers, so nobody has voted.
+pub fn legacy_proposal(collection: &Pubkey, proposer: &Pubkey, created_at: i64) -> LegacyProposal {
+    LegacyProposal {
+        collection: *collection,
+        proposal_type: PROPOSAL_ADJUST_SETTINGS,
+        proposer: *proposer,
+        created_at,
+        expires_at: created_at + PROPOSAL_LIFETIME,
+        executed: false,
+        approvals: vec![],
+        disapprovals: vec![],
+        withdraw_user: Pubkey::default(),
+        withdraw_amount: 0,
+        new_duration: 12 * PERIOD,
+        new_period: PERIOD,
+        new_amount_per_period: 5_000,
+        new_early_withdrawal_penalty_rate: 7,
+    }
+}
+
+fn process_instruction<'a>(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo<'a>],
+    data: &[u8],
+) -> ProgramResult {
+    // Anchor's entrypoint ties the slice and account lifetimes together,
+    // which the builtin processor signature cannot express
+    let accounts: &'a [AccountInfo<'a>] = Box::leak(accounts.to_vec().into_boxed_slice());
+
+    rotary_savings::entry(program_id, accounts, data)
+}
+
+pub fn program_test() -> ProgramTest {
+    let mut program_test = ProgramTest::new(
+        "rotary_savings",
+        rotary_savings::ID,
+        processor!(process_instruction),
+    );
+
+    program_test.prefer_bpf(false);
+
+    program_test
+}
+
+pub fn ix(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: rotary_savings::ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Asserts that a transaction failed with the given program error.
+pub fn assert_error(result: TxResult, expected: RotarySavingsError) {
+    match result.map_err(|error| error.unwrap()) {
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(actual))) => {
+            assert_eq!(actual, u32::from(expected), "expected {:?}", expected)
+        }
+        other => panic!("expected {:?}, got {:?}", expected, other),
+    }
+}
+
+/// Creation parameters, defaulting to a three-member personal savings
+/// collection with weekly payments over three weeks.
+#[derive(Clone)]
+pub struct CollectionParams {
+    pub duration: i64,
+    pub period: i64,
+    pub amount_per_period: u64,
+    pub total_members: u8,
+    pub early_withdrawal_penalty_rate: u16,
+    pub penalty_schedule: u8,
+    pub min_early_withdrawal_penalty_rate: u16,
+    pub collateral_amount: u64,
+    pub payout_order_mode: u8,
+    pub governance_mode: u8,
+    pub quorum_pct: u8,
+    pub approval_pct: u8,
+}
+
+impl Default for CollectionParams {
+    fn default() -> Self {
+        Self {
+            duration: 3 * PERIOD,
+            period: PERIOD,
+            amount_per_period: AMOUNT,
+            total_members: 3,
+            early_withdrawal_penalty_rate: 500,
+            penalty_schedule: PENALTY_SCHEDULE_FLAT,
+            min_early_withdrawal_penalty_rate: 0,
+            collateral_amount: 0,
+            payout_order_mode: 0,
+            governance_mode: 0,
+            quorum_pct: 0,
+            approval_pct: 0,
+        }
+    }
+}
+
+pub struct TestEnv {
+    pub context: ProgramTestContext,
+    pub admin: Keypair,
+    /// Compute units the last transaction sent through `send` consumed. Only
+    /// meaningful when the program runs as SBF; native builtins are not
+    /// metered.
+    pub compute_units: u64,
+}
+
+impl TestEnv {
+    pub async fn new() -> Self {
+        Self::start(program_test()).await
+    }
+
+    pub async fn start(program_test: ProgramTest) -> Self {
+        let context = program_test.start_with_context().await;
+        let mut env = Self {
+            context,
+            admin: Keypair::new(),
+            compute_units: 0,
+        };
+        let admin = env.admin.pubkey();
+
+        env.airdrop(&admin, 100 * AMOUNT).await;
+
+        env
+    }
+
+    pub async fn send(&mut self, instructions: &[Instruction], signers: &[&Keypair]) -> TxResult {
+        // A fresh blockhash keeps otherwise identical transactions distinct
+        let blockhash = self.context.get_new_latest_blockhash().await?;
+
+        let mut all_signers = vec![&self.context.payer];
+
+        all_signers.extend_from_slice(signers);
+
+        let transaction = Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            blockhash,
+        );
+
+        let outcome = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?;
+
+        self.compute_units = outcome
+            .metadata
+            .map_or(0, |metadata| metadata.compute_units_consumed);
+
+        Ok(outcome.result?)
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let payer = self.context.payer.pubkey();
+
+        self.send(&[system_instruction::transfer(&payer, to, lamports)], &[])
+            .await
+            .unwrap();
+    }
+
+    pub async fn funded_keypair(&mut self) -> Keypair {
+        let keypair = Keypair::new();
+
+        self.airdrop(&keypair.pubkey(), 10 * AMOUNT).await;
+
+        keypair
+    }
+
+    pub async fn now(&mut self) -> i64 {
+        self.clock().await.unix_timestamp
+    }
+
+    pub async fn clock(&mut self) -> Clock {
+        self.context.banks_client.get_sysvar().await.unwrap()
+    }
+
+    /// Moves the bank clock forward without producing blocks.
+    pub async fn warp(&mut self, seconds: i64) {
+        let mut clock = self.clock().await;
+
+        clock.unix_timestamp += seconds;
+
+        self.context.set_sysvar(&clock);
+    }
+
+    pub async fn fetch<T: AccountDeserialize>(&mut self, address: &Pubkey) -> T {
+        let account = self
+            .context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .expect("account not found");
+
+        T::try_deserialize(&mut account.data.as_slice()).unwrap()
+    }
+
+    pub async fn balance(&mut self, address: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*address)
+            .await
+            .unwrap()
+    }
+
+    /// Writes a rent-exempt, program-owned account holding `data`.
+    pub async fn plant(&mut self, address: &Pubkey, data: Vec<u8>) {
+        let rent: Rent = self.context.banks_client.get_sysvar().await.unwrap();
+        let account = Account {
+            lamports: rent.minimum_balance(data.len()),
+            data,
+            owner: rotary_savings::ID,
+            executable: false,
+            rent_epoch: 0,
+        };
+
+        self.context
+            .set_account(address, &AccountSharedData::from(account));
+    }
+
+    pub async fn exists(&mut self, address: &Pubkey) -> bool {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .is_some()
+    }
+
+    pub async fn try_create_collection(
+        &mut self,
+        name: &str,
+        params: CollectionParams,
+    ) -> TxResult {
+        let admin = self.admin.insecure_clone();
+
+        self.send(
+            &[client::create_collection(
+                &admin.pubkey(),
+                instruction::CreateCollection {
+                    name: name.to_string(),
+                    duration: params.duration,
+                    period: params.period,
+                    amount_per_period: params.amount_per_period,
+                    total_members: params.total_members,
+                    early_withdrawal_penalty_rate: params.early_withdrawal_penalty_rate,
+                    penalty_schedule: params.penalty_schedule,
+                    min_early_withdrawal_penalty_rate: params.min_early_withdrawal_penalty_rate,
+                    collateral_amount: params.collateral_amount,
+                    payout_order_mode: params.payout_order_mode,
+                    governance_mode: params.governance_mode,
+                    quorum_pct: params.quorum_pct,
+                    approval_pct: params.approval_pct,
+                },
+            )],
+            &[&admin],
+        )
+        .await
+    }
+
+    pub async fn create_collection(&mut self, name: &str, params: CollectionParams) -> Pubkey {
+        self.try_create_collection(name, params).await.unwrap();
+
+        collection_pda(&self.admin.pubkey(), name)
+    }
+
+    pub async fn try_add_user(&mut self, collection: &Pubkey, member: &Pubkey) -> TxResult {
+        let admin = self.admin.insecure_clone();
+
+        self.send(
+            &[client::add_user(&admin.pubkey(), collection, member)],
+            &[&admin],
+        )
+        .await
+    }
+
+    /// Adds `count` funded members and returns their keypairs.
+    pub async fn add_members(&mut self, collection: &Pubkey, count: usize) -> Vec<Keypair> {
+        let mut members = vec![];
+
+        for _ in 0..count {
+            let member = self.funded_keypair().await;
+
+            self.try_add_user(collection, &member.pubkey())
+                .await
+                .unwrap();
+
+            members.push(member);
+        }
+
+        members
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub async fn try_create_multisig(
+        &mut self,
+        collection: &Pubkey,
+        signers: Vec<Pubkey>,
+        threshold: u8,
+        weights: Vec<u16>,
+        weight_threshold: u32,
+        quorum: u8,
+        pass_rule: u8,
+    ) -> TxResult {
+        let admin = self.admin.insecure_clone();
+
+        self.send(
+            &[client::create_multisig(
+                &admin.pubkey(),
+                collection,
+                instruction::CreateMultisig {
+                    signers,
+                    threshold,
+                    weights,
+                    weight_threshold,
+                    quorum,
+                    pass_rule,
+                },
+            )],
+            &[&admin],
+        )
+        .await
+    }
+
+    /// Sets up an unweighted multisig and returns its signers.
+    pub async fn create_multisig(
+        &mut self,
+        collection: &Pubkey,
+        signer_count: usize,
+        threshold: u8,
+    ) -> Vec<Keypair> {
+        let mut signers = vec![];
+
+        for _ in 0..signer_count {
+            signers.push(self.funded_keypair().await);
+        }
+
+        self.try_create_multisig(
+            collection,
+            signers.iter().map(|signer| signer.pubkey()).collect(),
+            threshold,
+            vec![],
+            0,
+            0,
+            PASS_THRESHOLD,
+        )
+        .await
+        .unwrap();
+
+        signers
+    }
+
+    pub async fn try_pay(&mut self, collection: &Pubkey, member: &Keypair) -> TxResult {
+        let amount = self.fetch::<Collection>(collection).await.amount_per_period;
+
+        self.send(
+            &[client::pay(&member.pubkey(), collection, amount)],
+            &[member],
+        )
+        .await
+    }
+
+    pub async fn try_vote(
+        &mut self,
+        collection: &Pubkey,
+        proposal_id: u64,
+        signer: &Keypair,
+        approve: bool,
+    ) -> TxResult {
+        self.send(
+            &[client::vote_on_proposal(
+                &signer.pubkey(),
+                collection,
+                proposal_id,
+                approve,
+            )],
+            &[signer],
+        )
+        .await
+    }
+
+    /// `user_account` is only read for withdrawals, but the instruction
+    /// always takes one.
+    pub async fn try_execute(
+        &mut self,
+        collection: &Pubkey,
+        proposal_id: u64,
+        user_account: &Pubkey,
+    ) -> TxResult {
+        self.send(
+            &[client::execute_proposal(
+                collection,
+                proposal_id,
+                user_account,
+            )],
+            &[],
+        )
+        .await
+    }
+
+    /// Approves `proposal_id` with each of `signers` in turn.
+    pub async fn approve(&mut self, collection: &Pubkey, proposal_id: u64, signers: &[Keypair]) {
+        for signer in signers {
+            self.try_vote(collection, proposal_id, signer, true)
+                .await
+                .unwrap();
+        }
+    }
+
+    /// Has every member pay once per period for `periods` periods, moving the
+    /// clock forward after each round of payments.
+    pub async fn pay_periods(&mut self, collection: &Pubkey, members: &[Keypair], periods: u8) {
+        let period = self.fetch::<Collection>(collection).await.period;
+
+        for _ in 0..periods {
+            for member in members {
+                self.try_pay(collection, member).await.unwrap();
+            }
+
+            self.warp(period).await;
+        }
+    }
+
+    pub async fn try_propose_withdraw(
+        &mut self,
+        collection: &Pubkey,
+        member: &Keypair,
+        proposal_id: u64,
+    ) -> TxResult {
+        self.send(
+            &[client::propose_withdraw(
+                &member.pubkey(),
+                collection,
+                proposal_id,
+            )],
+            &[member],
+        )
+        .await
+    }
+
+    /// `user_account` is only read for withdrawal proposals, but the
+    /// instruction always takes one.
+    pub async fn try_create_proposal(
+        &mut self,
+        collection: &Pubkey,
+        proposer: &Keypair,
+        proposal_id: u64,
+        user_account: &Pubkey,
+        args: ProposalArgs,
+    ) -> TxResult {
+        self.send(
+            &[client::create_proposal(
+                &proposer.pubkey(),
+                collection,
+                user_account,
+                instruction::CreateProposal {
+                    proposal_id,
+                    proposal_type: args.proposal_type,
+                    withdraw_user: args.withdraw_user,
+                    withdraw_amount: args.withdraw_amount,
+                    new_duration: args.new_duration,
+                    new_period: args.new_period,
+                    new_amount_per_period: args.new_amount_per_period,
+                    new_early_withdrawal_penalty_rate: args.new_early_withdrawal_penalty_rate,
+                    settings_changes: args.settings_changes,
+                    new_penalty_schedule: args.new_penalty_schedule,
+                    new_min_early_withdrawal_penalty_rate: args
+                        .new_min_early_withdrawal_penalty_rate,
+                },
+            )],
+            &[proposer],
+        )
+        .await
+    }
+
+    pub async fn try_adjust_settings(
+        &mut self,
+        collection: &Pubkey,
+        admin: &Keypair,
+        proposal_id: u64,
+        args: ProposalArgs,
+    ) -> TxResult {
+        self.send(
+            &[client::adjust_settings(
+                &admin.pubkey(),
+                collection,
+                instruction::AdjustSettings {
+                    proposal_id,
+                    settings_changes: args.settings_changes,
+                    new_duration: args.new_duration,
+                    new_period: args.new_period,
+                    new_amount_per_period: args.new_amount_per_period,
+                    new_early_withdrawal_penalty_rate: args.new_early_withdrawal_penalty_rate,
+                    new_penalty_schedule: args.new_penalty_schedule,
+                    new_min_early_withdrawal_penalty_rate: args
+                        .new_min_early_withdrawal_penalty_rate,
+                },
+            )],
+            &[admin],
+        )
+        .await
+    }
+
+    pub async fn try_close_collection(
+        &mut self,
+        collection: &Pubkey,
+        admin: &Keypair,
+        proposal_id: u64,
+    ) -> TxResult {
+        self.send(
+            &[client::close_collection(
+                &admin.pubkey(),
+                collection,
+                proposal_id,
+            )],
+            &[admin],
+        )
+        .await
+    }
+
+    pub async fn try_transfer_admin(
+        &mut self,
+        collection: &Pubkey,
+        proposer: &Keypair,
+        proposal_id: u64,
+        new_admin: Pubkey,
+    ) -> TxResult {
+        self.send(
+            &[client::transfer_admin(
+                &proposer.pubkey(),
+                collection,
+                proposal_id,
+                &new_admin,
+            )],
+            &[proposer],
+        )
+        .await
+    }
+
+    pub async fn try_pause_collection(
+        &mut self,
+        collection: &Pubkey,
+        proposer: &Keypair,
+        proposal_id: u64,
+    ) -> TxResult {
+        self.send(
+            &[client::pause_collection(
+                &proposer.pubkey(),
+                collection,
+                proposal_id,
+            )],
+            &[proposer],
+        )
+        .await
+    }
+
+    pub async fn try_resume_collection(
+        &mut self,
+        collection: &Pubkey,
+        proposer: &Keypair,
+        proposal_id: u64,
+    ) -> TxResult {
+        self.send(
+            &[client::resume_collection(
+                &proposer.pubkey(),
+                collection,
+                proposal_id,
+            )],
+            &[proposer],
+        )
+        .await
+    }
+
+    pub async fn try_unfreeze_vault(
+        &mut self,
+        collection: &Pubkey,
+        proposer: &Keypair,
+        proposal_id: u64,
+    ) -> TxResult {
+        self.send(
+            &[client::unfreeze_vault(
+                &proposer.pubkey(),
+                collection,
+                proposal_id,
+            )],
+            &[proposer],
+        )
+        .await
+    }
+}
@@ -0,0 +1,1706 @@
+mod common;
+
+use anchor_lang::{prelude::*, system_program};
+use common::*;
+use rotary_savings::{accounts, client, dot::program::*, instruction};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    sysvar,
+};
+
+/// Sets up a multisig over three fresh signers with the given weights and
+/// rule, returning the signers.
+async fn create_ruled_multisig(
+    env: &mut TestEnv,
+    collection: &Pubkey,
+    threshold: u8,
+    weights: Vec<u16>,
+    weight_threshold: u32,
+    quorum: u8,
+    pass_rule: u8,
+) -> Vec<Keypair> {
+    let mut signers = vec![];
+
+    for _ in 0..3 {
+        signers.push(env.funded_keypair().await);
+    }
+
+    env.try_create_multisig(
+        collection,
+        signers.iter().map(|signer| signer.pubkey()).collect(),
+        threshold,
+        weights,
+        weight_threshold,
+        quorum,
+        pass_rule,
+    )
+    .await
+    .unwrap();
+
+    signers
+}
+
+#[tokio::test]
+async fn create_multisig_validates_signers() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let outsider = env.funded_keypair().await;
+    let (a, b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+    let result = env
+        .send(
+            &[client::create_multisig(
+                &outsider.pubkey(),
+                &collection,
+                instruction::CreateMultisig {
+                    signers: vec![a],
+                    threshold: 1,
+                    weights: vec![],
+                    weight_threshold: 0,
+                    quorum: 0,
+                    pass_rule: PASS_THRESHOLD,
+                },
+            )],
+            &[&outsider],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    let result = env
+        .try_create_multisig(&collection, vec![a, b], 3, vec![], 0, 0, PASS_THRESHOLD)
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidThreshold);
+
+    let cases = vec![
+        (
+            vec![a, a],
+            1,
+            vec![],
+            0,
+            0,
+            PASS_THRESHOLD,
+            RotarySavingsError::DuplicateSigner,
+        ),
+        (
+            vec![],
+            0,
+            vec![],
+            0,
+            0,
+            PASS_THRESHOLD,
+            RotarySavingsError::InvalidThreshold,
+        ),
+        (
+            vec![a, b],
+            0,
+            vec![],
+            0,
+            0,
+            PASS_THRESHOLD,
+            RotarySavingsError::InvalidThreshold,
+        ),
+        (
+            vec![a, b],
+            0,
+            vec![1],
+            1,
+            0,
+            PASS_THRESHOLD,
+            RotarySavingsError::InvalidWeights,
+        ),
+        (
+            vec![a, b],
+            0,
+            vec![1, 0],
+            1,
+            0,
+            PASS_THRESHOLD,
+            RotarySavingsError::InvalidWeights,
+        ),
+        (
+            vec![a, b],
+            0,
+            vec![1, 1],
+            0,
+            0,
+            PASS_THRESHOLD,
+            RotarySavingsError::InvalidWeightThreshold,
+        ),
+        (
+            vec![a, b],
+            0,
+            vec![1, 1],
+            3,
+            0,
+            PASS_THRESHOLD,
+            RotarySavingsError::InvalidWeightThreshold,
+        ),
+        (
+            vec![a, b],
+            1,
+            vec![],
+            0,
+            0,
+            PASS_SUPERMAJORITY + 1,
+            RotarySavingsError::InvalidPassRule,
+        ),
+        (
+            vec![a, b],
+            1,
+            vec![],
+            0,
+            3,
+            PASS_THRESHOLD,
+            RotarySavingsError::InvalidQuorum,
+        ),
+        (
+            vec![a, b],
+            1,
+            vec![],
+            0,
+            0,
+            PASS_MAJORITY,
+            RotarySavingsError::InvalidQuorum,
+        ),
+        (
+            vec![a, b],
+            1,
+            vec![],
+            0,
+            0,
+            PASS_SUPERMAJORITY,
+            RotarySavingsError::InvalidQuorum,
+        ),
+    ];
+
+    for (signers, threshold, weights, weight_threshold, quorum, pass_rule, expected) in cases {
+        let result = env
+            .try_create_multisig(
+                &collection,
+                signers,
+                threshold,
+                weights,
+                weight_threshold,
+                quorum,
+                pass_rule,
+            )
+            .await;
+
+        assert_error(result, expected);
+    }
+
+    env.try_create_multisig(&collection, vec![a, b], 2, vec![], 0, 1, PASS_MAJORITY)
+        .await
+        .unwrap();
+
+    let multisig: Multisig = env.fetch(&multisig_pda(&collection)).await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(multisig.collection, collection);
+    assert_eq!(multisig.signers, vec![a, b]);
+    assert_eq!(multisig.threshold, 2);
+    assert_eq!(multisig.delegates, vec![Pubkey::default(); 2]);
+    assert_eq!(multisig.quorum, 1);
+    assert_eq!(multisig.pass_rule, PASS_MAJORITY);
+    assert_eq!(account.multisig, multisig_pda(&collection));
+
+    // The multisig address is already taken, so a second one cannot be made
+    let result = env
+        .try_create_multisig(&collection, vec![a], 1, vec![], 0, 0, PASS_THRESHOLD)
+        .await;
+
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn withdrawal_pays_out_after_the_full_cycle() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 2).await;
+    let signers = env.create_multisig(&collection, 3, 2).await;
+    let member = &members[0];
+    let user_account = user_pda(&collection, &member.pubkey());
+
+    env.try_pay(&collection, member).await.unwrap();
+
+    let result = env.try_propose_withdraw(&collection, member, 1).await;
+
+    assert_error(result, RotarySavingsError::CannotWithdrawYet);
+
+    env.warp(PERIOD).await;
+    env.pay_periods(&collection, &members[..1], 2).await;
+
+    // Only the member can ask for their own savings, and only all of them
+    let result = env
+        .try_create_proposal(
+            &collection,
+            &members[1],
+            1,
+            &user_account,
+            ProposalArgs {
+                withdraw_user: member.pubkey(),
+                withdraw_amount: 3 * AMOUNT,
+                ..Default::default()
+            },
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidUser);
+
+    let result = env
+        .try_create_proposal(
+            &collection,
+            member,
+            1,
+            &user_account,
+            ProposalArgs {
+                withdraw_user: member.pubkey(),
+                withdraw_amount: AMOUNT,
+                ..Default::default()
+            },
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidWithdrawAmount);
+
+    env.try_propose_withdraw(&collection, member, 1)
+        .await
+        .unwrap();
+
+    let proposal: Proposal = env.fetch(&proposal_pda(&collection, 1)).await;
+    let now = env.now().await;
+
+    assert_eq!(proposal.proposal_id, 1);
+    assert_eq!(proposal.withdraw_user, member.pubkey());
+    assert_eq!(proposal.withdraw_amount, 3 * AMOUNT);
+    assert_eq!(proposal.expires_at, now + PROPOSAL_LIFETIME);
+
+    let result = env.try_vote(&collection, 1, &members[1], true).await;
+
+    assert_error(result, RotarySavingsError::NotASigner);
+
+    env.approve(&collection, 1, &signers[..1]).await;
+
+    let result = env.try_execute(&collection, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::NotEnoughApprovals);
+
+    env.approve(&collection, 1, &signers[1..2]).await;
+
+    // The payout can only go to the proposal's member
+    let other_account = user_pda(&collection, &members[1].pubkey());
+    let result = env.try_execute(&collection, 1, &other_account).await;
+
+    assert_error(result, RotarySavingsError::InvalidUser);
+
+    let balance_before = env.balance(&user_account).await;
+
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+    let multisig: Multisig = env.fetch(&multisig_pda(&collection)).await;
+    let user: User = env.fetch(&user_account).await;
+
+    assert_eq!(
+        env.balance(&user_account).await,
+        balance_before + 3 * AMOUNT
+    );
+    assert_eq!(account.total_balance, 0);
+    assert_eq!(user.total_paid, 0);
+    assert!(!user.can_withdraw);
+    assert!(multisig.open_proposals.is_empty());
+
+    let result = env.try_execute(&collection, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::ProposalExecuted);
+}
+
+#[tokio::test]
+async fn early_withdrawal_is_charged_the_penalty() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 3).await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+    let member = &members[0];
+    let user_account = user_pda(&collection, &member.pubkey());
+    let claim = |member: &Keypair| client::claim_dividends(&member.pubkey(), &collection);
+    let early = ProposalArgs {
+        proposal_type: PROPOSAL_EARLY_WITHDRAW,
+        withdraw_user: member.pubkey(),
+        withdraw_amount: AMOUNT,
+        ..Default::default()
+    };
+
+    env.try_pay(&collection, member).await.unwrap();
+
+    let result = env
+        .try_create_proposal(
+            &collection,
+            member,
+            1,
+            &user_account,
+            ProposalArgs {
+                proposal_type: 8,
+                ..early.clone()
+            },
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidProposalType);
+
+    env.try_create_proposal(&collection, member, 1, &user_account, early.clone())
+        .await
+        .unwrap();
+
+    let user: User = env.fetch(&user_account).await;
+
+    assert!(user.early_withdrawal_requested);
+
+    let result = env
+        .try_create_proposal(&collection, member, 2, &user_account, early.clone())
+        .await;
+
+    assert_error(result, RotarySavingsError::EarlyWithdrawalAlreadyRequested);
+
+    env.approve(&collection, 1, &signers).await;
+
+    let vault_before = env.balance(&collection).await;
+    let penalty = AMOUNT * 500 / 10_000;
+
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let user: User = env.fetch(&user_account).await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(
+        env.balance(&collection).await,
+        vault_before - (AMOUNT - penalty)
+    );
+    assert!(!user.early_withdrawal_requested);
+    assert_eq!(account.total_balance, 0);
+    assert_eq!(account.dividends_outstanding, penalty);
+
+    // The penalty goes to the other members, not back to the one who paid it
+    let result = env.send(&[claim(member)], &[member]).await;
+
+    assert_error(result, RotarySavingsError::NoDividends);
+
+    for other in &members[1..] {
+        let balance_before = env.balance(&other.pubkey()).await;
+
+        env.send(&[claim(other)], &[other]).await.unwrap();
+
+        assert_eq!(
+            env.balance(&other.pubkey()).await,
+            balance_before + penalty / 2
+        );
+    }
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.dividends_outstanding, 0);
+
+    // A member who finished the cycle withdraws normally instead
+    let short = env
+        .create_collection(
+            "Short",
+            CollectionParams {
+                duration: PERIOD,
+                ..Default::default()
+            },
+        )
+        .await;
+    let members = env.add_members(&short, 1).await;
+    let member = &members[0];
+
+    env.create_multisig(&short, 1, 1).await;
+    env.try_pay(&short, member).await.unwrap();
+
+    let result = env
+        .try_create_proposal(
+            &short,
+            member,
+            1,
+            &user_pda(&short, &member.pubkey()),
+            ProposalArgs {
+                withdraw_user: member.pubkey(),
+                ..early
+            },
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::EligibleForRegularWithdrawal);
+}
+
+#[tokio::test]
+async fn sole_member_early_withdrawal_leaves_the_penalty_in_the_vault() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+    let member = &members[0];
+    let user_account = user_pda(&collection, &member.pubkey());
+
+    env.try_pay(&collection, member).await.unwrap();
+    env.try_create_proposal(
+        &collection,
+        member,
+        1,
+        &user_account,
+        ProposalArgs {
+            proposal_type: PROPOSAL_EARLY_WITHDRAW,
+            withdraw_user: member.pubkey(),
+            withdraw_amount: AMOUNT,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+    env.approve(&collection, 1, &signers).await;
+
+    let vault_before = env.balance(&collection).await;
+    let penalty = AMOUNT * 500 / 10_000;
+
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    // Nobody else can take a dividend, so the penalty is not waived either
+    assert_eq!(
+        env.balance(&collection).await,
+        vault_before - (AMOUNT - penalty)
+    );
+    assert_eq!(account.total_balance, penalty);
+    assert_eq!(account.dividends_outstanding, 0);
+    assert_eq!(account.dividend_per_member, 0);
+}
+
+#[tokio::test]
+async fn adjust_settings_applies_after_approval() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+    let user_account = user_pda(&collection, &members[0].pubkey());
+    let change = ProposalArgs {
+        settings_changes: SETTING_AMOUNT_PER_PERIOD | SETTING_PENALTY_RATE,
+        new_amount_per_period: 2 * AMOUNT,
+        new_early_withdrawal_penalty_rate: 1_000,
+        ..Default::default()
+    };
+
+    let result = env
+        .try_adjust_settings(&collection, &members[0], 1, change.clone())
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    let cases = vec![
+        (0, RotarySavingsError::NoSettingsChanges),
+        (64, RotarySavingsError::InvalidSettingsChanges),
+        (
+            SETTING_PERIOD,
+            RotarySavingsError::DurationNotMultipleOfPeriod,
+        ),
+    ];
+
+    for (settings_changes, expected) in cases {
+        let result = env
+            .try_adjust_settings(
+                &collection,
+                &admin,
+                1,
+                ProposalArgs {
+                    settings_changes,
+                    new_period: 2 * PERIOD,
+                    ..Default::default()
+                },
+            )
+            .await;
+
+        assert_error(result, expected);
+    }
+
+    env.try_adjust_settings(&collection, &admin, 1, change)
+        .await
+        .unwrap();
+    env.approve(&collection, 1, &signers).await;
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.amount_per_period, 2 * AMOUNT);
+    assert_eq!(account.early_withdrawal_penalty_rate, 1_000);
+    assert_eq!(account.period, PERIOD);
+}
+
+#[tokio::test]
+async fn close_collection_stops_new_proposals() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+    let user_account = user_pda(&collection, &members[0].pubkey());
+
+    let result = env.try_close_collection(&collection, &members[0], 1).await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.try_close_collection(&collection, &admin, 1)
+        .await
+        .unwrap();
+    env.approve(&collection, 1, &signers).await;
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert!(!account.is_active);
+
+    let result = env.try_close_collection(&collection, &admin, 2).await;
+
+    assert_error(result, RotarySavingsError::CollectionNotActive);
+
+    let result = env.try_pay(&collection, &members[0]).await;
+
+    assert_error(result, RotarySavingsError::CollectionNotActive);
+}
+
+#[tokio::test]
+async fn proposals_have_room_for_every_signer() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let signers = env.create_multisig(&collection, 25, 25).await;
+
+    env.try_close_collection(&collection, &admin, 1)
+        .await
+        .unwrap();
+    env.approve(&collection, 1, &signers).await;
+
+    let proposal: Proposal = env.fetch(&proposal_pda(&collection, 1)).await;
+
+    assert_eq!(proposal.approvals, vec![true; 25]);
+}
+
+#[tokio::test]
+async fn expired_proposals_are_pruned() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+    let user_account = user_pda(&collection, &members[0].pubkey());
+
+    for proposal_id in 0..MAX_OPEN_PROPOSALS as u64 {
+        env.try_close_collection(&collection, &admin, proposal_id)
+            .await
+            .unwrap();
+    }
+
+    let result = env
+        .try_close_collection(&collection, &admin, MAX_OPEN_PROPOSALS as u64)
+        .await;
+
+    assert_error(result, RotarySavingsError::TooManyOpenProposals);
+
+    let result = env
+        .send(&[client::prune_proposal(&collection, 0)], &[])
+        .await;
+
+    assert_error(result, RotarySavingsError::ProposalStillOpen);
+
+    env.warp(PROPOSAL_LIFETIME).await;
+
+    let result = env.try_vote(&collection, 0, &signers[0], true).await;
+
+    assert_error(result, RotarySavingsError::ProposalExpired);
+
+    let result = env.try_execute(&collection, 0, &user_account).await;
+
+    assert_error(result, RotarySavingsError::ProposalExpired);
+
+    // Another collection's multisig cannot prune this proposal
+    let other = env
+        .create_collection("Other", CollectionParams::default())
+        .await;
+
+    env.create_multisig(&other, 1, 1).await;
+
+    let result = env
+        .send(
+            &[ix(
+                accounts::PruneProposal {
+                    multisig: multisig_pda(&other),
+                    proposal: proposal_pda(&collection, 0),
+                    clock: sysvar::clock::ID,
+                },
+                instruction::PruneProposal {},
+            )],
+            &[],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::ProposalMismatch);
+
+    env.send(&[client::prune_proposal(&collection, 0)], &[])
+        .await
+        .unwrap();
+
+    let multisig: Multisig = env.fetch(&multisig_pda(&collection)).await;
+
+    assert_eq!(multisig.open_proposals.len(), MAX_OPEN_PROPOSALS - 1);
+    assert!(!multisig.open_proposals.contains(&0));
+
+    env.try_close_collection(&collection, &admin, MAX_OPEN_PROPOSALS as u64)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn proposals_are_cancelled_by_their_proposer_or_an_admin() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 2, 1).await;
+    let spammer = &signers[0];
+
+    // One signer can fill every slot, locking the admin out of proposing
+    for proposal_id in 0..MAX_OPEN_PROPOSALS as u64 {
+        env.try_pause_collection(&collection, spammer, proposal_id)
+            .await
+            .unwrap();
+    }
+
+    let result = env
+        .try_close_collection(&collection, &admin, MAX_OPEN_PROPOSALS as u64)
+        .await;
+
+    assert_error(result, RotarySavingsError::TooManyOpenProposals);
+
+    for outsider in [&signers[1], &members[0]] {
+        let result = env
+            .send(
+                &[client::cancel_proposal(
+                    &outsider.pubkey(),
+                    &collection,
+                    &spammer.pubkey(),
+                    0,
+                )],
+                &[outsider],
+            )
+            .await;
+
+        assert_error(result, RotarySavingsError::NotProposer);
+    }
+
+    let balance_before = env.balance(&spammer.pubkey()).await;
+
+    env.send(
+        &[client::cancel_proposal(
+            &admin.pubkey(),
+            &collection,
+            &spammer.pubkey(),
+            0,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    assert!(!env.exists(&proposal_pda(&collection, 0)).await);
+    assert!(env.balance(&spammer.pubkey()).await > balance_before);
+
+    env.send(
+        &[client::cancel_proposal(
+            &spammer.pubkey(),
+            &collection,
+            &spammer.pubkey(),
+            1,
+        )],
+        &[spammer],
+    )
+    .await
+    .unwrap();
+
+    let multisig: Multisig = env.fetch(&multisig_pda(&collection)).await;
+
+    assert_eq!(multisig.open_proposals.len(), MAX_OPEN_PROPOSALS - 2);
+    assert!(!multisig.open_proposals.contains(&0));
+    assert!(!multisig.open_proposals.contains(&1));
+
+    let proposal_id = MAX_OPEN_PROPOSALS as u64;
+
+    env.try_close_collection(&collection, &admin, proposal_id)
+        .await
+        .unwrap();
+    env.approve(&collection, proposal_id, &signers[..1]).await;
+    env.try_execute(
+        &collection,
+        proposal_id,
+        &user_pda(&collection, &members[0].pubkey()),
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .send(
+            &[client::cancel_proposal(
+                &admin.pubkey(),
+                &collection,
+                &admin.pubkey(),
+                proposal_id,
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::ProposalExecuted);
+}
+
+#[tokio::test]
+async fn votes_stay_within_their_collection() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let other = env
+        .create_collection("Other", CollectionParams::default())
+        .await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+
+    env.create_multisig(&other, 1, 1).await;
+    env.try_close_collection(&other, &admin, 1).await.unwrap();
+
+    let vote = |collection: Pubkey, multisig: Pubkey| {
+        ix(
+            accounts::VoteOnProposal {
+                signer: signers[0].pubkey(),
+                collection,
+                multisig,
+                proposal: proposal_pda(&other, 1),
+                clock: sysvar::clock::ID,
+            },
+            instruction::VoteOnProposal { approve: true },
+        )
+    };
+
+    // A signer of one collection cannot vote on another's proposal
+    let result = env
+        .send(
+            &[vote(collection, multisig_pda(&collection))],
+            &[&signers[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::ProposalMismatch);
+
+    let result = env
+        .send(&[vote(other, multisig_pda(&collection))], &[&signers[0]])
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongMultisig);
+
+    let proposal: Proposal = env.fetch(&proposal_pda(&other, 1)).await;
+
+    assert_eq!(proposal.approvals, vec![false]);
+}
+
+#[tokio::test]
+async fn migrate_moves_legacy_accounts() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let proposer = env.funded_keypair().await;
+    let member = Pubkey::new_unique();
+    let collection = collection_pda(&admin.pubkey(), "Legacy");
+    let multisig = multisig_pda(&collection);
+    let now = env.now().await;
+    let legacy_pda = |proposal_id: u64| client::legacy_proposal_pda(&collection, proposal_id);
+
+    env.plant(
+        &collection,
+        legacy_data(
+            <Collection as anchor_lang::Discriminator>::DISCRIMINATOR,
+            &LegacyCollection {
+                admin: admin.pubkey(),
+                name: "Legacy".to_string(),
+                duration: 12 * PERIOD,
+                period: PERIOD,
+                amount_per_period: AMOUNT,
+                total_members: 2,
+                active_members: 1,
+                total_balance: AMOUNT,
+                is_active: true,
+                early_withdrawal_penalty_rate: 5,
+            },
+        ),
+    )
+    .await;
+    env.plant(
+        &multisig,
+        legacy_data(
+            <Multisig as anchor_lang::Discriminator>::DISCRIMINATOR,
+            &LegacyMultisig {
+                collection,
+                signers: vec![],
+                threshold: 1,
+                nonce: 8,
+            },
+        ),
+    )
+    .await;
+    env.plant(
+        &user_pda(&collection, &member),
+        legacy_data(
+            <User as anchor_lang::Discriminator>::DISCRIMINATOR,
+            &LegacyUser {
+                collection,
+                user: member,
+                paid_periods: 1,
+                last_paid: now,
+                total_paid: AMOUNT,
+                can_withdraw: false,
+                early_withdrawal_requested: false,
+            },
+        ),
+    )
+    .await;
+    for (proposal_id, owner) in [(7, collection), (8, Pubkey::new_unique())] {
+        env.plant(
+            &legacy_pda(proposal_id),
+            legacy_data(
+                <Proposal as anchor_lang::Discriminator>::DISCRIMINATOR,
+                &legacy_proposal(&owner, &proposer.pubkey(), now),
+            ),
+        )
+        .await;
+    }
+
+    // Nothing reads the proposals or members until their collection is current
+    let result = env
+        .send(
+            &[client::migrate_proposal(
+                &admin.pubkey(),
+                &collection,
+                &proposer.pubkey(),
+                7,
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert!(result.is_err());
+
+    env.send(
+        &[client::migrate_collection(&admin.pubkey(), &collection)],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let migrated: Collection = env.fetch(&collection).await;
+    let migrated_multisig: Multisig = env.fetch(&multisig).await;
+
+    assert_eq!(migrated.admin, admin.pubkey());
+    assert_eq!(migrated.creator, admin.pubkey());
+    assert_eq!(migrated.name, "Legacy");
+    assert_eq!(migrated.total_members, 2);
+    assert_eq!(migrated.active_members, 1);
+    assert_eq!(migrated.total_balance, AMOUNT);
+    assert_eq!(migrated.early_withdrawal_penalty_rate, 500);
+    assert_eq!(migrated.multisig, multisig);
+    assert_eq!(migrated_multisig.collection, collection);
+    assert_eq!(migrated_multisig.nonce, 8);
+    assert_eq!(migrated_multisig.pass_rule, PASS_THRESHOLD);
+    assert!(migrated_multisig.open_proposals.is_empty());
+
+    let result = env
+        .send(
+            &[client::migrate_collection(&admin.pubkey(), &collection)],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotLegacyAccount);
+
+    env.send(
+        &[client::migrate_user(&admin.pubkey(), &collection, &member)],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let user: User = env.fetch(&user_pda(&collection, &member)).await;
+
+    assert_eq!(user.collection, collection);
+    assert_eq!(user.user, member);
+    assert_eq!(user.paid_periods, 1);
+    assert_eq!(user.last_paid, now);
+    assert_eq!(user.total_paid, AMOUNT);
+    assert_eq!(user.joined_at, 0);
+
+    let result = env
+        .send(
+            &[client::migrate_proposal(
+                &admin.pubkey(),
+                &collection,
+                &proposer.pubkey(),
+                8,
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::ProposalMismatch);
+
+    let result = env
+        .send(
+            &[client::migrate_proposal(
+                &admin.pubkey(),
+                &collection,
+                &admin.pubkey(),
+                7,
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongProposer);
+
+    let legacy_rent = env.balance(&legacy_pda(7)).await;
+    let proposer_balance = env.balance(&proposer.pubkey()).await;
+
+    env.send(
+        &[client::migrate_proposal(
+            &admin.pubkey(),
+            &collection,
+            &proposer.pubkey(),
+            7,
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let proposal: Proposal = env.fetch(&proposal_pda(&collection, 7)).await;
+    let migrated_multisig: Multisig = env.fetch(&multisig).await;
+
+    assert!(!env.exists(&legacy_pda(7)).await);
+    assert_eq!(
+        env.balance(&proposer.pubkey()).await,
+        proposer_balance + legacy_rent
+    );
+    assert_eq!(migrated_multisig.open_proposals, vec![7]);
+    assert_eq!(proposal.collection, collection);
+    assert_eq!(proposal.proposal_id, 7);
+    assert_eq!(proposal.proposer, proposer.pubkey());
+    assert_eq!(proposal.expires_at, now + PROPOSAL_LIFETIME);
+    assert_eq!(proposal.proposal_type, PROPOSAL_ADJUST_SETTINGS);
+    assert_eq!(proposal.new_duration, 12 * PERIOD);
+    assert_eq!(proposal.new_period, PERIOD);
+    assert_eq!(proposal.new_amount_per_period, 5_000);
+    assert_eq!(proposal.new_early_withdrawal_penalty_rate, 700);
+    assert_eq!(
+        proposal.settings_changes,
+        SETTING_DURATION | SETTING_PERIOD | SETTING_AMOUNT_PER_PERIOD | SETTING_PENALTY_RATE
+    );
+}
+
+#[tokio::test]
+async fn delegated_signer_votes_in_both_slots() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 3, 2).await;
+    let user_account = user_pda(&collection, &members[0].pubkey());
+    let delegate = |signer: &Keypair, delegate: Pubkey| {
+        client::delegate_vote(&signer.pubkey(), &collection, &delegate)
+    };
+    let revoke = |signer: &Keypair| client::revoke_delegation(&signer.pubkey(), &collection);
+
+    let result = env
+        .send(
+            &[delegate(&signers[0], signers[0].pubkey())],
+            &[&signers[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::SelfDelegation);
+
+    let result = env
+        .send(
+            &[delegate(&members[0], signers[1].pubkey())],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotASigner);
+
+    env.send(
+        &[delegate(&signers[0], signers[1].pubkey())],
+        &[&signers[0]],
+    )
+    .await
+    .unwrap();
+
+    let multisig: Multisig = env.fetch(&multisig_pda(&collection)).await;
+
+    assert_eq!(multisig.delegates[0], signers[1].pubkey());
+
+    env.try_close_collection(&collection, &admin, 1)
+        .await
+        .unwrap();
+    env.approve(&collection, 1, &signers[1..2]).await;
+
+    let proposal: Proposal = env.fetch(&proposal_pda(&collection, 1)).await;
+
+    assert_eq!(proposal.approvals, vec![true, true, false]);
+
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let result = env.send(&[revoke(&members[0])], &[&members[0]]).await;
+
+    assert_error(result, RotarySavingsError::NotASigner);
+
+    env.send(&[revoke(&signers[0])], &[&signers[0]])
+        .await
+        .unwrap();
+
+    let multisig: Multisig = env.fetch(&multisig_pda(&collection)).await;
+
+    assert_eq!(multisig.delegates[0], Pubkey::default());
+}
+
+#[tokio::test]
+async fn member_governance_counts_member_votes() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection(
+            "Savings",
+            CollectionParams {
+                governance_mode: GOVERNANCE_MEMBERS,
+                quorum_pct: 50,
+                approval_pct: 60,
+                ..Default::default()
+            },
+        )
+        .await;
+    let members = env.add_members(&collection, 3).await;
+    let stand_in = env.funded_keypair().await;
+    let user_account = user_pda(&collection, &members[0].pubkey());
+    let change = ProposalArgs {
+        settings_changes: SETTING_AMOUNT_PER_PERIOD,
+        new_amount_per_period: 2 * AMOUNT,
+        ..Default::default()
+    };
+    let delegate = |member: &Keypair, delegate: Pubkey| {
+        client::delegate_member_vote(&member.pubkey(), &collection, &delegate)
+    };
+    let revoke = |signer: &Keypair, member: &Keypair| {
+        ix(
+            accounts::RevokeMemberDelegation {
+                member: signer.pubkey(),
+                user_account: user_pda(&collection, &member.pubkey()),
+            },
+            instruction::RevokeMemberDelegation {},
+        )
+    };
+
+    // Member-governed collections only use the multisig as a proposal index
+    env.try_create_multisig(&collection, vec![], 0, vec![], 0, 0, PASS_THRESHOLD)
+        .await
+        .unwrap();
+    env.try_adjust_settings(&collection, &admin, 1, change.clone())
+        .await
+        .unwrap();
+    env.try_adjust_settings(&collection, &admin, 2, change)
+        .await
+        .unwrap();
+
+    let result = env
+        .send(
+            &[delegate(&members[0], members[0].pubkey())],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::SelfDelegation);
+
+    env.send(&[delegate(&members[0], stand_in.pubkey())], &[&members[0]])
+        .await
+        .unwrap();
+
+    let result = env
+        .send(
+            &[client::cast_vote(
+                &members[1].pubkey(),
+                &collection,
+                &members[0].pubkey(),
+                1,
+                true,
+            )],
+            &[&members[1]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidUser);
+
+    let result = env.try_execute(&collection, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::QuorumNotReached);
+
+    env.send(
+        &[client::cast_vote(
+            &members[1].pubkey(),
+            &collection,
+            &members[1].pubkey(),
+            1,
+            true,
+        )],
+        &[&members[1]],
+    )
+    .await
+    .unwrap();
+
+    let result = env.try_execute(&collection, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::QuorumNotReached);
+
+    // The stand-in votes on the first member's behalf
+    env.send(
+        &[client::cast_vote(
+            &stand_in.pubkey(),
+            &collection,
+            &members[0].pubkey(),
+            1,
+            false,
+        )],
+        &[&stand_in],
+    )
+    .await
+    .unwrap();
+
+    let vote: Vote = env
+        .fetch(&vote_pda(
+            &proposal_pda(&collection, 1),
+            &members[0].pubkey(),
+        ))
+        .await;
+
+    assert_eq!(vote.voter, members[0].pubkey());
+    assert!(!vote.approve);
+
+    let result = env.try_execute(&collection, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::ProposalRejected);
+
+    env.send(
+        &[client::cast_vote(
+            &members[2].pubkey(),
+            &collection,
+            &members[2].pubkey(),
+            1,
+            true,
+        )],
+        &[&members[2]],
+    )
+    .await
+    .unwrap();
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let proposal: Proposal = env.fetch(&proposal_pda(&collection, 1)).await;
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(proposal.member_approvals, 2);
+    assert_eq!(proposal.member_disapprovals, 1);
+    assert_eq!(account.amount_per_period, 2 * AMOUNT);
+
+    // Each member's vote account can only be created once
+    let result = env
+        .send(
+            &[client::cast_vote(
+                &members[1].pubkey(),
+                &collection,
+                &members[1].pubkey(),
+                1,
+                false,
+            )],
+            &[&members[1]],
+        )
+        .await;
+
+    assert!(result.is_err());
+
+    let result = env
+        .send(&[revoke(&stand_in, &members[0])], &[&stand_in])
+        .await;
+
+    assert_error(result, RotarySavingsError::InvalidUser);
+
+    env.send(&[revoke(&members[0], &members[0])], &[&members[0]])
+        .await
+        .unwrap();
+
+    let user: User = env.fetch(&user_account).await;
+
+    assert_eq!(user.delegate, Pubkey::default());
+
+    env.warp(PROPOSAL_LIFETIME).await;
+
+    let result = env
+        .send(
+            &[client::cast_vote(
+                &members[1].pubkey(),
+                &collection,
+                &members[1].pubkey(),
+                2,
+                true,
+            )],
+            &[&members[1]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::ProposalExpired);
+
+    // Signer-governed collections do not take member votes
+    let signed = env
+        .create_collection("Signed", CollectionParams::default())
+        .await;
+    let members = env.add_members(&signed, 1).await;
+
+    env.create_multisig(&signed, 1, 1).await;
+    env.try_close_collection(&signed, &admin, 1).await.unwrap();
+
+    let result = env
+        .send(
+            &[client::cast_vote(
+                &members[0].pubkey(),
+                &signed,
+                &members[0].pubkey(),
+                1,
+                true,
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotMemberGoverned);
+}
+
+#[tokio::test]
+async fn multisig_quorum_and_pass_rules() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+
+    // Majority of the votes cast, once two of three signers have voted
+    let majority = env
+        .create_collection("Majority", CollectionParams::default())
+        .await;
+    let members = env.add_members(&majority, 1).await;
+    let user_account = user_pda(&majority, &members[0].pubkey());
+    let signers = create_ruled_multisig(&mut env, &majority, 1, vec![], 0, 2, PASS_MAJORITY).await;
+
+    env.try_close_collection(&majority, &admin, 1)
+        .await
+        .unwrap();
+    env.approve(&majority, 1, &signers[..1]).await;
+
+    let result = env.try_execute(&majority, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::QuorumNotReached);
+
+    env.try_vote(&majority, 1, &signers[1], false)
+        .await
+        .unwrap();
+
+    let result = env.try_execute(&majority, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::ProposalRejected);
+
+    env.approve(&majority, 1, &signers[2..]).await;
+    env.try_execute(&majority, 1, &user_account).await.unwrap();
+
+    // Two thirds of the votes cast
+    let supermajority = env
+        .create_collection("Supermajority", CollectionParams::default())
+        .await;
+    let members = env.add_members(&supermajority, 1).await;
+    let user_account = user_pda(&supermajority, &members[0].pubkey());
+    let signers = create_ruled_multisig(
+        &mut env,
+        &supermajority,
+        1,
+        vec![],
+        0,
+        2,
+        PASS_SUPERMAJORITY,
+    )
+    .await;
+
+    env.try_close_collection(&supermajority, &admin, 1)
+        .await
+        .unwrap();
+    env.approve(&supermajority, 1, &signers[..1]).await;
+    env.try_vote(&supermajority, 1, &signers[1], false)
+        .await
+        .unwrap();
+
+    let result = env.try_execute(&supermajority, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::ProposalRejected);
+
+    env.approve(&supermajority, 1, &signers[2..]).await;
+    env.try_execute(&supermajority, 1, &user_account)
+        .await
+        .unwrap();
+
+    // Approving weight against the weight threshold
+    let weighted = env
+        .create_collection("Weighted", CollectionParams::default())
+        .await;
+    let members = env.add_members(&weighted, 1).await;
+    let user_account = user_pda(&weighted, &members[0].pubkey());
+    let signers =
+        create_ruled_multisig(&mut env, &weighted, 0, vec![3, 1, 1], 3, 0, PASS_THRESHOLD).await;
+
+    env.try_close_collection(&weighted, &admin, 1)
+        .await
+        .unwrap();
+    env.approve(&weighted, 1, &signers[1..]).await;
+
+    let result = env.try_execute(&weighted, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::NotEnoughApprovalWeight);
+
+    env.approve(&weighted, 1, &signers[..1]).await;
+    env.try_execute(&weighted, 1, &user_account).await.unwrap();
+}
+
+#[tokio::test]
+async fn admin_transfer_needs_acceptance() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 2, 2).await;
+    let user_account = user_pda(&collection, &members[0].pubkey());
+    let new_admin = env.funded_keypair().await;
+    let accept = |new_admin: &Keypair| client::accept_admin(&new_admin.pubkey(), &collection);
+
+    env.send(
+        &[client::set_co_admins(
+            &admin.pubkey(),
+            &collection,
+            vec![new_admin.pubkey()],
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .try_transfer_admin(&collection, &members[0], 1, new_admin.pubkey())
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    for invalid in [Pubkey::default(), admin.pubkey()] {
+        let result = env
+            .try_transfer_admin(&collection, &signers[0], 1, invalid)
+            .await;
+
+        assert_error(result, RotarySavingsError::InvalidNewAdmin);
+    }
+
+    // A signer can start the transfer without the current admin
+    env.try_transfer_admin(&collection, &signers[0], 1, new_admin.pubkey())
+        .await
+        .unwrap();
+    env.approve(&collection, 1, &signers).await;
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.pending_admin, new_admin.pubkey());
+    assert_eq!(account.admin, admin.pubkey());
+
+    let result = env.send(&[accept(&members[0])], &[&members[0]]).await;
+
+    assert_error(result, RotarySavingsError::NotPendingAdmin);
+
+    env.send(&[accept(&new_admin)], &[&new_admin])
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.admin, new_admin.pubkey());
+    assert_eq!(account.pending_admin, Pubkey::default());
+    assert!(account.co_admins.is_empty());
+
+    let result = env.try_add_user(&collection, &Pubkey::new_unique()).await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+}
+
+#[tokio::test]
+async fn pause_and_resume_through_proposals() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+    let user_account = user_pda(&collection, &members[0].pubkey());
+
+    env.try_pay(&collection, &members[0]).await.unwrap();
+
+    let result = env.try_resume_collection(&collection, &admin, 1).await;
+
+    assert_error(result, RotarySavingsError::NotPaused);
+
+    let result = env.try_pause_collection(&collection, &members[0], 1).await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.try_pause_collection(&collection, &admin, 1)
+        .await
+        .unwrap();
+    env.approve(&collection, 1, &signers).await;
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert!(account.is_paused);
+
+    let result = env.try_pause_collection(&collection, &admin, 2).await;
+
+    assert_error(result, RotarySavingsError::AlreadyPaused);
+
+    env.warp(PERIOD).await;
+
+    let result = env.try_pay(&collection, &members[0]).await;
+
+    assert_error(result, RotarySavingsError::CollectionPaused);
+
+    env.try_resume_collection(&collection, &signers[0], 2)
+        .await
+        .unwrap();
+    env.approve(&collection, 2, &signers).await;
+    env.try_execute(&collection, 2, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert!(!account.is_paused);
+
+    env.try_pay(&collection, &members[0]).await.unwrap();
+}
+
+#[tokio::test]
+async fn guardian_freeze_holds_withdrawals_until_unfrozen() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&collection, 1).await;
+    let signers = env.create_multisig(&collection, 1, 1).await;
+    let guardian = env.funded_keypair().await;
+    let member = &members[0];
+    let user_account = user_pda(&collection, &member.pubkey());
+
+    env.send(
+        &[client::set_guardian(
+            &admin.pubkey(),
+            &collection,
+            &guardian.pubkey(),
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    env.pay_periods(&collection, &members, 3).await;
+    env.try_propose_withdraw(&collection, member, 1)
+        .await
+        .unwrap();
+    env.approve(&collection, 1, &signers).await;
+    env.send(
+        &[client::guardian_freeze(
+            &guardian.pubkey(),
+            &collection,
+            3 * DAY,
+        )],
+        &[&guardian],
+    )
+    .await
+    .unwrap();
+
+    let result = env.try_execute(&collection, 1, &user_account).await;
+
+    assert_error(result, RotarySavingsError::VaultFrozen);
+
+    let result = env.try_unfreeze_vault(&collection, member, 2).await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.try_unfreeze_vault(&collection, &signers[0], 2)
+        .await
+        .unwrap();
+    env.approve(&collection, 2, &signers).await;
+    env.try_execute(&collection, 2, &user_account)
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(account.frozen_until, 0);
+
+    // Lifting the freeze does not let the guardian start another one
+    let result = env
+        .send(
+            &[client::guardian_freeze(
+                &guardian.pubkey(),
+                &collection,
+                3 * DAY,
+            )],
+            &[&guardian],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::FreezeCooldown);
+
+    env.try_execute(&collection, 1, &user_account)
+        .await
+        .unwrap();
+
+    let user: User = env.fetch(&user_account).await;
+
+    assert_eq!(user.total_paid, 0);
+}
+
+#[tokio::test]
+async fn proposals_are_bound_to_their_collection() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let first = env
+        .create_collection("First", CollectionParams::default())
+        .await;
+    let second = env
+        .create_collection("Second", CollectionParams::default())
+        .await;
+    let members = env.add_members(&first, 1).await;
+    let user_account = user_pda(&first, &members[0].pubkey());
+
+    env.create_multisig(&first, 1, 1).await;
+
+    let signers = env.create_multisig(&second, 1, 1).await;
+
+    let result = env
+        .send(
+            &[ix(
+                accounts::CloseCollection {
+                    admin: admin.pubkey(),
+                    collection: first,
+                    multisig: multisig_pda(&second),
+                    proposal: proposal_pda(&first, 1),
+                    clock: sysvar::clock::ID,
+                    rent: sysvar::rent::ID,
+                    system_program: system_program::ID,
+                },
+                instruction::CloseCollection { proposal_id: 1 },
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongMultisig);
+
+    env.try_close_collection(&second, &admin, 1).await.unwrap();
+    env.approve(&second, 1, &signers).await;
+
+    let result = env
+        .send(
+            &[ix(
+                accounts::ExecuteProposal {
+                    collection: first,
+                    multisig: multisig_pda(&first),
+                    proposal: proposal_pda(&second, 1),
+                    user_account,
+                    clock: sysvar::clock::ID,
+                },
+                instruction::ExecuteProposal {},
+            )],
+            &[],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::ProposalMismatch);
+
+    let result = env
+        .send(
+            &[ix(
+                accounts::ExecuteProposal {
+                    collection: first,
+                    multisig: multisig_pda(&second),
+                    proposal: proposal_pda(&second, 1),
+                    user_account,
+                    clock: sysvar::clock::ID,
+                },
+                instruction::ExecuteProposal {},
+            )],
+            &[],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongMultisig);
+}
@@ -0,0 +1,684 @@
+mod common;
+
+use anchor_lang::{prelude::*, solana_program::hash::hashv};
+use common::*;
+use rotary_savings::{accounts, client, dot::program::*, instruction};
+use solana_sdk::signature::{Keypair, Signer};
+
+fn rotating(payout_order_mode: u8) -> CollectionParams {
+    CollectionParams {
+        payout_order_mode,
+        ..Default::default()
+    }
+}
+
+fn keys(members: &[Keypair]) -> Vec<Pubkey> {
+    members.iter().map(|member| member.pubkey()).collect()
+}
+
+#[tokio::test]
+async fn fixed_order_pays_each_round_in_turn() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env.create_collection("Fixed", rotating(PAYOUT_FIXED)).await;
+    let mut members = env.add_members(&collection, 2).await;
+
+    let result = env
+        .send(
+            &[client::set_payout_order(
+                &admin.pubkey(),
+                &collection,
+                keys(&members),
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::CollectionNotFull);
+
+    members.extend(env.add_members(&collection, 1).await);
+
+    let mut order = keys(&members);
+
+    order.reverse();
+
+    let result = env
+        .send(
+            &[client::set_payout_order(
+                &members[0].pubkey(),
+                &collection,
+                order.clone(),
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    for invalid in [
+        order[..2].to_vec(),
+        vec![order[0], order[0], order[1]],
+        vec![order[0], order[1], Pubkey::new_unique()],
+    ] {
+        let result = env
+            .send(
+                &[client::set_payout_order(
+                    &admin.pubkey(),
+                    &collection,
+                    invalid,
+                )],
+                &[&admin],
+            )
+            .await;
+
+        assert_error(result, RotarySavingsError::InvalidPayoutOrder);
+    }
+
+    let result = env
+        .send(&[client::disburse_round(&collection, &order[0])], &[])
+        .await;
+
+    assert_error(result, RotarySavingsError::PayoutOrderNotFinalized);
+
+    env.send(
+        &[client::set_payout_order(
+            &admin.pubkey(),
+            &collection,
+            order.clone(),
+        )],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+    env.create_multisig(&collection, 1, 1).await;
+
+    let result = env
+        .send(&[client::disburse_round(&collection, &order[0])], &[])
+        .await;
+
+    assert_error(result, RotarySavingsError::PotNotFunded);
+
+    env.pay_periods(&collection, &members, 1).await;
+
+    let result = env
+        .send(&[client::disburse_round(&collection, &order[1])], &[])
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongRecipient);
+
+    let balance_before = env.balance(&order[0]).await;
+
+    env.send(&[client::disburse_round(&collection, &order[0])], &[])
+        .await
+        .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+    let user: User = env.fetch(&user_pda(&collection, &order[0])).await;
+
+    assert_eq!(env.balance(&order[0]).await, balance_before + 3 * AMOUNT);
+    assert_eq!(account.current_round, 1);
+    assert_eq!(account.total_balance, 0);
+    assert!(user.received_payout);
+
+    let result = env
+        .send(
+            &[client::set_payout_order(
+                &admin.pubkey(),
+                &collection,
+                keys(&members),
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::PayoutOrderLocked);
+
+    // Savings in a rotating collection only leave through the rounds
+    let result = env.try_propose_withdraw(&collection, &members[0], 1).await;
+
+    assert_error(result, RotarySavingsError::RotatingCollection);
+
+    for recipient in &order[1..] {
+        env.pay_periods(&collection, &members, 1).await;
+        env.send(&[client::disburse_round(&collection, recipient)], &[])
+            .await
+            .unwrap();
+    }
+
+    let result = env
+        .send(&[client::disburse_round(&collection, &order[0])], &[])
+        .await;
+
+    assert_error(result, RotarySavingsError::RotationComplete);
+
+    // The other payout instructions belong to other modes
+    let result = env
+        .send(
+            &[client::shuffle_payout_order(&admin.pubkey(), &collection)],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongPayoutOrderMode);
+
+    let result = env
+        .send(
+            &[client::place_bid(&members[0].pubkey(), &collection, AMOUNT)],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAuction);
+
+    let result = env
+        .send(&[client::settle_round(&collection, &order[0])], &[])
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAuction);
+
+    let savings = env
+        .create_collection("Savings", CollectionParams::default())
+        .await;
+    let members = env.add_members(&savings, 1).await;
+    let result = env
+        .send(
+            &[client::disburse_round(&savings, &members[0].pubkey())],
+            &[],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotRotating);
+}
+
+#[tokio::test]
+async fn shuffle_is_replayable_from_the_stored_seed() {
+    let mut env = TestEnv::new().await;
+    let admin = env.admin.insecure_clone();
+    let collection = env
+        .create_collection("Random", rotating(PAYOUT_RANDOM))
+        .await;
+    let mut members = env.add_members(&collection, 2).await;
+
+    let result = env
+        .send(
+            &[client::shuffle_payout_order(&admin.pubkey(), &collection)],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::CollectionNotFull);
+
+    members.extend(env.add_members(&collection, 1).await);
+
+    let result = env
+        .send(
+            &[client::shuffle_payout_order(
+                &members[0].pubkey(),
+                &collection,
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotAdmin);
+
+    env.send(
+        &[client::shuffle_payout_order(&admin.pubkey(), &collection)],
+        &[&admin],
+    )
+    .await
+    .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert!(account.payout_order_finalized);
+    assert_ne!(account.payout_seed, [0; 32]);
+
+    // Replay the Fisher-Yates shuffle over the join order
+    let mut order = keys(&members);
+    let mut seed = account.payout_seed;
+
+    for index in (2..=order.len()).rev() {
+        seed = hashv(&[&seed]).to_bytes();
+
+        let draw = u64::from_le_bytes(seed[..8].try_into().unwrap());
+
+        order.swap(index - 1, (draw % index as u64) as usize);
+    }
+
+    assert_eq!(account.payout_order, order);
+
+    let result = env
+        .send(
+            &[client::shuffle_payout_order(&admin.pubkey(), &collection)],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::PayoutOrderLocked);
+
+    let result = env
+        .send(
+            &[client::set_payout_order(
+                &admin.pubkey(),
+                &collection,
+                order,
+            )],
+            &[&admin],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongPayoutOrderMode);
+}
+
+#[tokio::test]
+async fn auction_awards_the_lowest_bid_and_shares_the_discount() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Auction", rotating(PAYOUT_AUCTION))
+        .await;
+    let mut members = env.add_members(&collection, 2).await;
+    let pot = 3 * AMOUNT;
+
+    let result = env
+        .send(
+            &[client::place_bid(&members[0].pubkey(), &collection, pot)],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::CollectionNotFull);
+
+    members.extend(env.add_members(&collection, 1).await);
+    env.create_multisig(&collection, 1, 1).await;
+
+    for member in &members {
+        env.try_pay(&collection, member).await.unwrap();
+    }
+
+    let result = env
+        .send(
+            &[client::disburse_round(&collection, &members[0].pubkey())],
+            &[],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::AuctionRound);
+
+    let result = env
+        .send(
+            &[client::place_bid(
+                &members[0].pubkey(),
+                &collection,
+                pot + 1,
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::BidTooHigh);
+
+    env.send(
+        &[client::place_bid(
+            &members[0].pubkey(),
+            &collection,
+            pot - 3 * AMOUNT / 10,
+        )],
+        &[&members[0]],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[client::place_bid(
+            &members[1].pubkey(),
+            &collection,
+            pot - 6 * AMOUNT / 10,
+        )],
+        &[&members[1]],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .send(
+            &[client::place_bid(
+                &members[0].pubkey(),
+                &collection,
+                pot - AMOUNT,
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::AlreadyBid);
+
+    let result = env
+        .send(
+            &[client::settle_round(&collection, &members[1].pubkey())],
+            &[],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::BiddingOpen);
+
+    // A tie goes to the earlier bid, and the last bid closes the window
+    env.send(
+        &[client::place_bid(
+            &members[2].pubkey(),
+            &collection,
+            pot - 6 * AMOUNT / 10,
+        )],
+        &[&members[2]],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .send(
+            &[client::settle_round(&collection, &members[2].pubkey())],
+            &[],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::WrongRecipient);
+
+    let balance_before = env.balance(&members[1].pubkey()).await;
+
+    env.send(
+        &[client::settle_round(&collection, &members[1].pubkey())],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+    let share = 3 * AMOUNT / 10;
+
+    assert_eq!(
+        env.balance(&members[1].pubkey()).await,
+        balance_before + pot - 6 * AMOUNT / 10
+    );
+    assert_eq!(account.current_round, 1);
+    assert_eq!(account.payout_order[0], members[1].pubkey());
+    assert_eq!(account.dividend_per_member, share);
+    assert_eq!(account.dividends_outstanding, 2 * share);
+
+    let result = env
+        .send(
+            &[client::claim_dividends(&members[1].pubkey(), &collection)],
+            &[&members[1]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NoDividends);
+
+    let balance_before = env.balance(&members[0].pubkey()).await;
+
+    env.send(
+        &[client::claim_dividends(&members[0].pubkey(), &collection)],
+        &[&members[0]],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        env.balance(&members[0].pubkey()).await,
+        balance_before + share
+    );
+
+    let result = env
+        .send(
+            &[client::claim_dividends(&members[0].pubkey(), &collection)],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NoDividends);
+
+    // The next round's window opens one period after the rotation started
+    let result = env
+        .send(
+            &[client::place_bid(&members[0].pubkey(), &collection, pot)],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::BiddingClosed);
+
+    let result = env
+        .send(
+            &[client::place_bid(&members[1].pubkey(), &collection, pot)],
+            &[&members[1]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::AlreadyReceivedPayout);
+
+    env.warp(PERIOD).await;
+
+    for member in &members {
+        env.try_pay(&collection, member).await.unwrap();
+    }
+
+    env.warp(PERIOD).await;
+
+    // Without bids the pot goes in full to the next slot in the order
+    let account: Collection = env.fetch(&collection).await;
+    let next = account.payout_order[1];
+    let balance_before = env.balance(&next).await;
+
+    env.send(&[client::settle_round(&collection, &next)], &[])
+        .await
+        .unwrap();
+
+    assert_eq!(env.balance(&next).await, balance_before + pot);
+}
+
+#[tokio::test]
+async fn members_trade_payout_slots() {
+    let mut env = TestEnv::new().await;
+    let collection = env
+        .create_collection("Swaps", rotating(PAYOUT_JOIN_ORDER))
+        .await;
+    let members = env.add_members(&collection, 3).await;
+    let side_payment = AMOUNT / 10;
+    let swap = swap_pda(&collection, &members[0].pubkey(), 0);
+
+    let result = env
+        .send(
+            &[client::propose_swap(
+                &members[0].pubkey(),
+                &collection,
+                &members[0].pubkey(),
+                0,
+                0,
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::SelfSwap);
+
+    env.send(
+        &[client::propose_swap(
+            &members[0].pubkey(),
+            &collection,
+            &members[2].pubkey(),
+            0,
+            side_payment,
+        )],
+        &[&members[0]],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .send(
+            &[client::accept_swap(
+                &members[1].pubkey(),
+                &collection,
+                &members[0].pubkey(),
+                0,
+            )],
+            &[&members[1]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotSwapCounterparty);
+
+    let balance_before = env.balance(&members[2].pubkey()).await;
+
+    env.send(
+        &[client::accept_swap(
+            &members[2].pubkey(),
+            &collection,
+            &members[0].pubkey(),
+            0,
+        )],
+        &[&members[2]],
+    )
+    .await
+    .unwrap();
+
+    let account: Collection = env.fetch(&collection).await;
+
+    assert_eq!(
+        account.payout_order,
+        vec![
+            members[2].pubkey(),
+            members[1].pubkey(),
+            members[0].pubkey()
+        ]
+    );
+    assert_eq!(
+        env.balance(&members[2].pubkey()).await,
+        balance_before + side_payment
+    );
+    assert_eq!(env.balance(&swap).await, 0);
+
+    // A settled swap is closed, so the proposer can offer another this round
+    env.send(
+        &[client::propose_swap(
+            &members[0].pubkey(),
+            &collection,
+            &members[1].pubkey(),
+            0,
+            0,
+        )],
+        &[&members[0]],
+    )
+    .await
+    .unwrap();
+    env.send(
+        &[client::cancel_swap(&members[0].pubkey(), &collection, 0)],
+        &[&members[0]],
+    )
+    .await
+    .unwrap();
+
+    let swap = swap_pda(&collection, &members[1].pubkey(), 0);
+
+    env.send(
+        &[client::propose_swap(
+            &members[1].pubkey(),
+            &collection,
+            &members[0].pubkey(),
+            0,
+            side_payment,
+        )],
+        &[&members[1]],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .send(
+            &[ix(
+                accounts::CancelSwap {
+                    proposer: members[0].pubkey(),
+                    swap,
+                },
+                instruction::CancelSwap {},
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::NotSwapProposer);
+
+    let balance_before = env.balance(&members[1].pubkey()).await;
+    let escrow = env.balance(&swap).await;
+
+    env.send(
+        &[client::cancel_swap(&members[1].pubkey(), &collection, 0)],
+        &[&members[1]],
+    )
+    .await
+    .unwrap();
+
+    // The side payment comes back along with the account's rent
+    assert!(escrow > side_payment);
+    assert_eq!(env.balance(&swap).await, 0);
+    assert_eq!(
+        env.balance(&members[1].pubkey()).await,
+        balance_before + escrow
+    );
+
+    env.send(
+        &[client::propose_swap(
+            &members[1].pubkey(),
+            &collection,
+            &members[0].pubkey(),
+            0,
+            side_payment,
+        )],
+        &[&members[1]],
+    )
+    .await
+    .unwrap();
+
+    // A slot that has been paid out can no longer be traded
+    env.create_multisig(&collection, 1, 1).await;
+    env.pay_periods(&collection, &members, 1).await;
+    env.send(
+        &[client::disburse_round(&collection, &members[2].pubkey())],
+        &[],
+    )
+    .await
+    .unwrap();
+
+    let result = env
+        .send(
+            &[client::propose_swap(
+                &members[1].pubkey(),
+                &collection,
+                &members[2].pubkey(),
+                1,
+                0,
+            )],
+            &[&members[1]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::AlreadyReceivedPayout);
+
+    // Auction slots are decided by bidding instead
+    let auction = env
+        .create_collection("Auction", rotating(PAYOUT_AUCTION))
+        .await;
+    let members = env.add_members(&auction, 2).await;
+    let result = env
+        .send(
+            &[client::propose_swap(
+                &members[0].pubkey(),
+                &auction,
+                &members[1].pubkey(),
+                0,
+                0,
+            )],
+            &[&members[0]],
+        )
+        .await;
+
+    assert_error(result, RotarySavingsError::SwapsUnavailable);
+}
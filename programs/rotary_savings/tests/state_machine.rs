@@ -0,0 +1,513 @@
+//! Drives random sequences of payments, withdrawal proposals, votes,
+//! executions and clock moves through the program and through a plain model
+//! of the savings rules, checking after every step that both agree and that
+//! the vault's books balance. Set `PROPTEST_CASES` to run more sequences.
+
+mod common;
+
+use anchor_lang::prelude::*;
+use common::*;
+use proptest::{collection::vec, prelude::*, sample::Index};
+use rotary_savings::{client, dot::program::*};
+use solana_program_test::BanksClientError;
+use solana_sdk::{
+    instruction::InstructionError,
+    signature::{Keypair, Signer},
+    transaction::TransactionError,
+};
+
+const MEMBERS: usize = 3;
+const SIGNERS: usize = 3;
+const THRESHOLD: u8 = 2;
+
+mod model {
+    use super::*;
+
+    const PROPOSAL_WITHDRAW: u8 = 0;
+    const PROPOSAL_LIFETIME: i64 = 7 * DAY;
+    const MAX_OPEN_PROPOSALS: usize = 16;
+    const BPS_DENOMINATOR: u128 = 10_000;
+
+    /// A rejected instruction: the program error code.
+    pub type Outcome = std::result::Result<(), u32>;
+
+    fn reject(error: RotarySavingsError) -> Outcome {
+        Err(error.into())
+    }
+
+    #[derive(Clone, Debug, Default)]
+    pub struct Member {
+        pub paid_periods: u8,
+        pub last_paid: i64,
+        pub total_paid: u64,
+        pub can_withdraw: bool,
+        pub early_withdrawal_requested: bool,
+        /// Lamports paid out to the member's account by executed withdrawals.
+        pub withdrawn: u64,
+    }
+
+    #[derive(Clone, Debug)]
+    pub struct Proposal {
+        pub proposal_type: u8,
+        pub member: usize,
+        pub withdraw_amount: u64,
+        pub expires_at: i64,
+        pub executed: bool,
+        pub approvals: Vec<bool>,
+        pub disapprovals: Vec<bool>,
+    }
+
+    #[derive(Clone, Copy, Debug)]
+    pub struct Settings {
+        pub early_withdrawal_penalty_rate: u16,
+        pub penalty_schedule: u8,
+        pub min_early_withdrawal_penalty_rate: u16,
+    }
+
+    /// A non-rotating, signer-governed collection with every member added
+    /// and the multisig in place, as set up by the harness.
+    #[derive(Clone, Debug)]
+    pub struct Collection {
+        pub settings: Settings,
+        pub members: Vec<Member>,
+        /// Indexed by proposal id.
+        pub proposals: Vec<Proposal>,
+        pub open_proposals: Vec<u64>,
+        pub total_balance: u64,
+        /// Early withdrawal penalties booked for the other members to claim.
+        pub dividends_outstanding: u64,
+    }
+
+    impl Collection {
+        pub fn new(settings: Settings) -> Self {
+            Self {
+                settings,
+                members: vec![Member::default(); MEMBERS],
+                proposals: vec![],
+                open_proposals: vec![],
+                total_balance: 0,
+                dividends_outstanding: 0,
+            }
+        }
+
+        pub fn pay(&mut self, member: usize, now: i64) -> Outcome {
+            let member = &mut self.members[member];
+
+            if now - member.last_paid < PERIOD {
+                return reject(RotarySavingsError::PaymentNotDue);
+            }
+
+            member.paid_periods += 1;
+            member.last_paid = now;
+            member.total_paid += AMOUNT;
+            self.total_balance += AMOUNT;
+
+            if member.paid_periods as i64 * PERIOD >= 3 * PERIOD {
+                member.can_withdraw = true;
+            }
+
+            Ok(())
+        }
+
+        pub fn propose_withdraw(&mut self, member: usize, now: i64) -> Outcome {
+            if !self.members[member].can_withdraw {
+                return reject(RotarySavingsError::CannotWithdrawYet);
+            }
+
+            self.open(PROPOSAL_WITHDRAW, member, now)
+        }
+
+        pub fn propose_early_withdraw(&mut self, member: usize, now: i64) -> Outcome {
+            if self.members[member].can_withdraw {
+                return reject(RotarySavingsError::EligibleForRegularWithdrawal);
+            }
+
+            if self.members[member].early_withdrawal_requested {
+                return reject(RotarySavingsError::EarlyWithdrawalAlreadyRequested);
+            }
+
+            self.open(PROPOSAL_EARLY_WITHDRAW, member, now)?;
+            self.members[member].early_withdrawal_requested = true;
+
+            Ok(())
+        }
+
+        fn open(&mut self, proposal_type: u8, member: usize, now: i64) -> Outcome {
+            if self.open_proposals.len() >= MAX_OPEN_PROPOSALS {
+                return reject(RotarySavingsError::TooManyOpenProposals);
+            }
+
+            self.open_proposals.push(self.proposals.len() as u64);
+            self.proposals.push(Proposal {
+                proposal_type,
+                member,
+                withdraw_amount: self.members[member].total_paid,
+                expires_at: now + PROPOSAL_LIFETIME,
+                executed: false,
+                approvals: vec![false; SIGNERS],
+                disapprovals: vec![false; SIGNERS],
+            });
+
+            Ok(())
+        }
+
+        pub fn vote(
+            &mut self,
+            signer: usize,
+            proposal_id: usize,
+            approve: bool,
+            now: i64,
+        ) -> Outcome {
+            let proposal = &mut self.proposals[proposal_id];
+
+            if proposal.executed {
+                return reject(RotarySavingsError::ProposalExecuted);
+            }
+
+            if now >= proposal.expires_at {
+                return reject(RotarySavingsError::ProposalExpired);
+            }
+
+            proposal.approvals[signer] = approve;
+            proposal.disapprovals[signer] = !approve;
+
+            Ok(())
+        }
+
+        pub fn execute(&mut self, proposal_id: usize, now: i64) -> Outcome {
+            let proposal = self.proposals[proposal_id].clone();
+
+            if proposal.executed {
+                return reject(RotarySavingsError::ProposalExecuted);
+            }
+
+            if now >= proposal.expires_at {
+                return reject(RotarySavingsError::ProposalExpired);
+            }
+
+            let approved = proposal.approvals.iter().filter(|vote| **vote).count();
+
+            if approved < THRESHOLD as usize {
+                return reject(RotarySavingsError::NotEnoughApprovals);
+            }
+
+            let member = &self.members[proposal.member];
+
+            if member.total_paid != proposal.withdraw_amount {
+                return reject(RotarySavingsError::InvalidWithdrawAmount);
+            }
+
+            let penalty = if proposal.proposal_type == PROPOSAL_WITHDRAW {
+                if !member.can_withdraw {
+                    return reject(RotarySavingsError::CannotWithdrawYet);
+                }
+
+                0
+            } else {
+                if !member.early_withdrawal_requested {
+                    return reject(RotarySavingsError::EarlyWithdrawalNotRequested);
+                }
+
+                self.penalty(member)
+            };
+
+            // Shared evenly between the other members, rounding down; the
+            // remainder stays in the balance
+            let others = MEMBERS as u64 - 1;
+            let shared = penalty / others * others;
+            let member = &mut self.members[proposal.member];
+
+            member.withdrawn += proposal.withdraw_amount - penalty;
+            member.total_paid = 0;
+            member.can_withdraw = false;
+            member.early_withdrawal_requested = false;
+            self.total_balance -= proposal.withdraw_amount - (penalty - shared);
+            self.dividends_outstanding += shared;
+            self.proposals[proposal_id].executed = true;
+            self.open_proposals.retain(|id| *id != proposal_id as u64);
+
+            Ok(())
+        }
+
+        pub fn prune(&mut self, proposal_id: usize, now: i64) -> Outcome {
+            if now < self.proposals[proposal_id].expires_at {
+                return reject(RotarySavingsError::ProposalStillOpen);
+            }
+
+            self.open_proposals.retain(|id| *id != proposal_id as u64);
+
+            Ok(())
+        }
+
+        /// The linear schedule falls from the full rate towards the minimum
+        /// as the member's paid progress approaches the duration.
+        fn penalty(&self, member: &Member) -> u64 {
+            let settings = self.settings;
+            let duration = 3 * PERIOD;
+            let mut rate = settings.early_withdrawal_penalty_rate;
+
+            if settings.penalty_schedule == 1 {
+                let progress = (member.paid_periods as i64 * PERIOD).min(duration);
+                let spread = settings.early_withdrawal_penalty_rate
+                    - settings.min_early_withdrawal_penalty_rate;
+
+                rate -= (spread as i64 * progress / duration) as u16;
+            }
+
+            (member.total_paid as u128 * rate as u128 / BPS_DENOMINATOR) as u64
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Op {
+    Pay(usize),
+    Warp(i64),
+    ProposeWithdraw(usize),
+    ProposeEarlyWithdraw(usize),
+    Vote(usize, Index, bool),
+    Execute(Index),
+    Prune(Index),
+}
+
+fn settings() -> impl Strategy<Value = model::Settings> {
+    (0..=10_000u16, 0..=1u8, 0..=100u16).prop_map(|(rate, schedule, min_pct)| model::Settings {
+        early_withdrawal_penalty_rate: rate,
+        penalty_schedule: schedule,
+        min_early_withdrawal_penalty_rate: (rate as u32 * min_pct as u32 / 100) as u16,
+    })
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    let member = 0..MEMBERS;
+
+    prop_oneof![
+        4 => member.clone().prop_map(Op::Pay),
+        2 => (1..=10i64).prop_map(|days| Op::Warp(days * DAY)),
+        1 => member.clone().prop_map(Op::ProposeWithdraw),
+        1 => member.prop_map(Op::ProposeEarlyWithdraw),
+        4 => (0..SIGNERS, any::<Index>(), prop::bool::weighted(0.8))
+            .prop_map(|(signer, proposal, approve)| Op::Vote(signer, proposal, approve)),
+        2 => any::<Index>().prop_map(Op::Execute),
+        1 => any::<Index>().prop_map(Op::Prune),
+    ]
+}
+
+fn outcome(result: std::result::Result<(), BanksClientError>) -> model::Outcome {
+    match result.map_err(|error| error.unwrap()) {
+        Ok(()) => Ok(()),
+        Err(TransactionError::InstructionError(_, InstructionError::Custom(code))) => Err(code),
+        Err(other) => panic!("unexpected failure: {:?}", other),
+    }
+}
+
+struct Harness {
+    env: TestEnv,
+    collection: Pubkey,
+    members: Vec<Keypair>,
+    signers: Vec<Keypair>,
+    vault_rent: u64,
+    user_rent: u64,
+}
+
+impl Harness {
+    async fn new(settings: model::Settings) -> Self {
+        let mut env = TestEnv::new().await;
+        let collection = env
+            .create_collection(
+                "Model",
+                CollectionParams {
+                    early_withdrawal_penalty_rate: settings.early_withdrawal_penalty_rate,
+                    penalty_schedule: settings.penalty_schedule,
+                    min_early_withdrawal_penalty_rate: settings.min_early_withdrawal_penalty_rate,
+                    ..Default::default()
+                },
+            )
+            .await;
+        let members = env.add_members(&collection, MEMBERS).await;
+        let signers = env.create_multisig(&collection, SIGNERS, THRESHOLD).await;
+
+        // Members pay every period of every sequence out of their own wallet
+        for member in &members {
+            env.airdrop(&member.pubkey(), 100 * AMOUNT).await;
+        }
+
+        let vault_rent = env.balance(&collection).await;
+        let user_rent = env
+            .balance(&user_pda(&collection, &members[0].pubkey()))
+            .await;
+
+        Self {
+            env,
+            collection,
+            members,
+            signers,
+            vault_rent,
+            user_rent,
+        }
+    }
+
+    async fn apply(&mut self, model: &mut model::Collection, op: &Op) {
+        let now = self.env.now().await;
+        let collection = self.collection;
+        let proposals = model.proposals.len();
+
+        let (expected, actual) = match op {
+            Op::Pay(member) => (
+                model.pay(*member, now),
+                self.env.try_pay(&collection, &self.members[*member]).await,
+            ),
+            Op::Warp(seconds) => {
+                self.env.warp(*seconds).await;
+
+                return;
+            }
+            Op::ProposeWithdraw(member) => (
+                model.propose_withdraw(*member, now),
+                self.env
+                    .try_propose_withdraw(&collection, &self.members[*member], proposals as u64)
+                    .await,
+            ),
+            Op::ProposeEarlyWithdraw(member) => {
+                let member_key = self.members[*member].pubkey();
+                let args = ProposalArgs {
+                    proposal_type: PROPOSAL_EARLY_WITHDRAW,
+                    withdraw_user: member_key,
+                    withdraw_amount: model.members[*member].total_paid,
+                    ..Default::default()
+                };
+
+                (
+                    model.propose_early_withdraw(*member, now),
+                    self.env
+                        .try_create_proposal(
+                            &collection,
+                            &self.members[*member],
+                            proposals as u64,
+                            &user_pda(&collection, &member_key),
+                            args,
+                        )
+                        .await,
+                )
+            }
+            Op::Vote(signer, proposal, approve) => {
+                if proposals == 0 {
+                    return;
+                }
+
+                let proposal_id = proposal.index(proposals);
+
+                (
+                    model.vote(*signer, proposal_id, *approve, now),
+                    self.env
+                        .try_vote(
+                            &collection,
+                            proposal_id as u64,
+                            &self.signers[*signer],
+                            *approve,
+                        )
+                        .await,
+                )
+            }
+            Op::Execute(proposal) => {
+                if proposals == 0 {
+                    return;
+                }
+
+                let proposal_id = proposal.index(proposals);
+                let member = &self.members[model.proposals[proposal_id].member];
+                let user_account = user_pda(&collection, &member.pubkey());
+
+                (
+                    model.execute(proposal_id, now),
+                    self.env
+                        .try_execute(&collection, proposal_id as u64, &user_account)
+                        .await,
+                )
+            }
+            Op::Prune(proposal) => {
+                if proposals == 0 {
+                    return;
+                }
+
+                let proposal_id = proposal.index(proposals);
+
+                (
+                    model.prune(proposal_id, now),
+                    self.env
+                        .send(
+                            &[client::prune_proposal(&collection, proposal_id as u64)],
+                            &[],
+                        )
+                        .await,
+                )
+            }
+        };
+
+        assert_eq!(outcome(actual), expected, "{:?}", op);
+    }
+
+    async fn check(&mut self, model: &model::Collection) {
+        let collection: Collection = self.env.fetch(&self.collection).await;
+        let multisig: Multisig = self.env.fetch(&multisig_pda(&self.collection)).await;
+        let mut total_paid = 0;
+
+        for (member, expected) in self.members.iter().zip(&model.members) {
+            let address = user_pda(&self.collection, &member.pubkey());
+            let user: User = self.env.fetch(&address).await;
+
+            assert_eq!(user.paid_periods, expected.paid_periods);
+            assert_eq!(user.total_paid, expected.total_paid);
+            assert_eq!(user.can_withdraw, expected.can_withdraw);
+            assert_eq!(
+                user.early_withdrawal_requested,
+                expected.early_withdrawal_requested
+            );
+            assert_eq!(
+                self.env.balance(&address).await,
+                self.user_rent + expected.withdrawn
+            );
+
+            total_paid += user.total_paid;
+        }
+
+        // Every lamport on the books belongs to a member who paid it in
+        assert_eq!(collection.total_balance, total_paid);
+        assert_eq!(collection.total_balance, model.total_balance);
+        assert_eq!(
+            collection.dividends_outstanding,
+            model.dividends_outstanding
+        );
+
+        // Every lamport in the vault is on the books
+        assert_eq!(
+            self.env.balance(&self.collection).await,
+            self.vault_rent + collection.total_balance + collection.dividends_outstanding
+        );
+        assert_eq!(multisig.open_proposals, model.open_proposals);
+    }
+}
+
+async fn run(settings: model::Settings, ops: Vec<Op>) {
+    let mut harness = Harness::new(settings).await;
+    let mut model = model::Collection::new(settings);
+
+    for op in &ops {
+        harness.apply(&mut model, op).await;
+        harness.check(&model).await;
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig {
+        cases: 16,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn program_matches_the_model(settings in settings(), ops in vec(op(), 1..48)) {
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(run(settings, ops));
+    }
+}
@@ -1,10 +1,27 @@
 # rotary_savings
 # Built with Seahorse v0.2.0
+#
+# NOTE: the Anchor crate in programs/rotary_savings is now maintained directly
+# and has features this file does not describe. Do not run `seahorse build`.
 
 from seahorse.prelude import *
 
 declare_id('Fg6PaFpoGXkYsidMpWTK6W2BeZ7FEfcYkg476zPFsLnS')
 
+# Penalty rates are expressed in basis points (1/100th of a percent)
+BPS_DENOMINATOR = 10000
+# Collection names are used as a PDA seed, which is limited to 32 bytes
+MAX_NAME_LEN = 32
+
+# Bit flags selecting which settings an Adjust Settings proposal changes
+SETTING_DURATION = 1
+SETTING_PERIOD = 2
+SETTING_AMOUNT_PER_PERIOD = 4
+SETTING_PENALTY_RATE = 8
+SETTING_PENALTY_SCHEDULE = 16
+SETTING_MIN_PENALTY_RATE = 32
+ALL_SETTINGS = 63
+
 
 class Collection(Account):
     admin: Pubkey
@@ -16,7 +33,9 @@ class Collection(Account):
     active_members: u8
     total_balance: u64
     is_active: bool
-    early_withdrawal_penalty_rate: u8
+    early_withdrawal_penalty_rate: u16  # basis points
+    penalty_schedule: u8  # 0: Flat, 1: Linear (slides down to the minimum rate)
+    min_early_withdrawal_penalty_rate: u16  # basis points, used by the linear schedule
 
 
 class User(Account):
@@ -51,7 +70,86 @@ class Proposal(Account):
     new_duration: i64
     new_period: i64
     new_amount_per_period: u64
-    new_early_withdrawal_penalty_rate: u8
+    new_early_withdrawal_penalty_rate: u16
+    settings_changes: u8  # SETTING_* flags of the fields to apply
+    new_penalty_schedule: u8
+    new_min_early_withdrawal_penalty_rate: u16
+
+
+def early_withdrawal_penalty(collection: Collection, user_account: User, amount: u64) -> u64:
+    rate = collection.early_withdrawal_penalty_rate
+    if collection.penalty_schedule == 1:  # Linear
+        # The rate slides from the full penalty down to the minimum as the
+        # member's paid periods approach the collection duration
+        progress = i64(user_account.paid_periods) * collection.period
+        if progress > collection.duration:
+            progress = collection.duration
+        if collection.duration > 0:
+            spread = collection.early_withdrawal_penalty_rate - \
+                collection.min_early_withdrawal_penalty_rate
+            rate = collection.early_withdrawal_penalty_rate - \
+                u16((i64(spread) * progress) // collection.duration)
+
+    return u64((u128(amount) * u128(rate)) // BPS_DENOMINATOR)
+
+
+def validate_collection_settings(
+    duration: i64,
+    period: i64,
+    amount_per_period: u64,
+    early_withdrawal_penalty_rate: u16,
+    penalty_schedule: u8,
+    min_early_withdrawal_penalty_rate: u16
+):
+    assert duration > 0, 'Duration must be positive'
+    assert period > 0, 'Period must be positive'
+    assert duration % period == 0, 'Duration must be a multiple of period'
+    assert amount_per_period > 0, 'Amount per period must be positive'
+    assert early_withdrawal_penalty_rate <= BPS_DENOMINATOR, 'Penalty rate exceeds 10000 basis points'
+    assert penalty_schedule <= 1, 'Invalid penalty schedule'
+    assert min_early_withdrawal_penalty_rate <= early_withdrawal_penalty_rate, 'Minimum penalty rate exceeds penalty rate'
+
+
+def validate_settings_changes(
+    collection: Collection,
+    settings_changes: u8,
+    new_duration: i64,
+    new_period: i64,
+    new_amount_per_period: u64,
+    new_early_withdrawal_penalty_rate: u16,
+    new_penalty_schedule: u8,
+    new_min_early_withdrawal_penalty_rate: u16
+):
+    assert settings_changes != 0, 'Settings proposal does not change any setting'
+    assert settings_changes & ~ALL_SETTINGS == 0, 'Settings proposal contains unknown settings'
+
+    duration = collection.duration
+    if settings_changes & SETTING_DURATION != 0:
+        duration = new_duration
+    period = collection.period
+    if settings_changes & SETTING_PERIOD != 0:
+        period = new_period
+    amount_per_period = collection.amount_per_period
+    if settings_changes & SETTING_AMOUNT_PER_PERIOD != 0:
+        amount_per_period = new_amount_per_period
+    early_withdrawal_penalty_rate = collection.early_withdrawal_penalty_rate
+    if settings_changes & SETTING_PENALTY_RATE != 0:
+        early_withdrawal_penalty_rate = new_early_withdrawal_penalty_rate
+    penalty_schedule = collection.penalty_schedule
+    if settings_changes & SETTING_PENALTY_SCHEDULE != 0:
+        penalty_schedule = new_penalty_schedule
+    min_early_withdrawal_penalty_rate = collection.min_early_withdrawal_penalty_rate
+    if settings_changes & SETTING_MIN_PENALTY_RATE != 0:
+        min_early_withdrawal_penalty_rate = new_min_early_withdrawal_penalty_rate
+
+    validate_collection_settings(
+        duration,
+        period,
+        amount_per_period,
+        early_withdrawal_penalty_rate,
+        penalty_schedule,
+        min_early_withdrawal_penalty_rate
+    )
 
 
 @instruction
@@ -63,11 +161,26 @@ def create_collection(
     period: i64,
     amount_per_period: u64,
     total_members: u8,
-    early_withdrawal_penalty_rate: u8
+    early_withdrawal_penalty_rate: u16,
+    penalty_schedule: u8,
+    min_early_withdrawal_penalty_rate: u16
 ):
+    assert len(name) > 0, 'Collection name must not be empty'
+    assert len(name) <= MAX_NAME_LEN, 'Collection name exceeds 32 bytes'
+    assert total_members > 0, 'Total members must be positive'
+    validate_collection_settings(
+        duration,
+        period,
+        amount_per_period,
+        early_withdrawal_penalty_rate,
+        penalty_schedule,
+        min_early_withdrawal_penalty_rate
+    )
+
     collection = collection.init(
         payer=admin,
-        seeds=['collection', admin.key(), name]
+        seeds=['collection', admin.key(), name],
+        padding=MAX_NAME_LEN
     )
     collection.admin = admin.key()
     collection.name = name
@@ -79,6 +192,8 @@ def create_collection(
     collection.total_balance = 0
     collection.is_active = True
     collection.early_withdrawal_penalty_rate = early_withdrawal_penalty_rate
+    collection.penalty_schedule = penalty_schedule
+    collection.min_early_withdrawal_penalty_rate = min_early_withdrawal_penalty_rate
 
 
 @instruction
@@ -113,12 +228,26 @@ def adjust_settings(
     multisig: Multisig,
     proposal: Empty[Proposal],
     clock: Clock,
+    settings_changes: u8,
     new_duration: i64,
     new_period: i64,
     new_amount_per_period: u64,
+    new_early_withdrawal_penalty_rate: u16,
+    new_penalty_schedule: u8,
+    new_min_early_withdrawal_penalty_rate: u16,
 ):
     assert admin.key() == collection.admin, 'Only admin can propose setting adjustments'
     assert collection.is_active, 'Collection is not active'
+    validate_settings_changes(
+        collection,
+        settings_changes,
+        new_duration,
+        new_period,
+        new_amount_per_period,
+        new_early_withdrawal_penalty_rate,
+        new_penalty_schedule,
+        new_min_early_withdrawal_penalty_rate
+    )
 
     proposal = proposal.init(
         payer=admin,
@@ -139,6 +268,10 @@ def adjust_settings(
     proposal.new_duration = new_duration
     proposal.new_period = new_period
     proposal.new_amount_per_period = new_amount_per_period
+    proposal.new_early_withdrawal_penalty_rate = new_early_withdrawal_penalty_rate
+    proposal.settings_changes = settings_changes
+    proposal.new_penalty_schedule = new_penalty_schedule
+    proposal.new_min_early_withdrawal_penalty_rate = new_min_early_withdrawal_penalty_rate
 
     multisig.nonce += 1
 
@@ -217,7 +350,10 @@ def create_proposal(
     new_duration: i64,
     new_period: i64,
     new_amount_per_period: u64,
-    new_early_withdrawal_penalty_rate: u8,
+    new_early_withdrawal_penalty_rate: u16,
+    settings_changes: u8,
+    new_penalty_schedule: u8,
+    new_min_early_withdrawal_penalty_rate: u16,
 ):
     assert collection.is_active, 'Collection is not active'
 
@@ -227,6 +363,16 @@ def create_proposal(
         assert proposer.key() == collection.admin, 'Only admin can propose to close collection'
     elif proposal_type == 2:  # Adjust Settings
         assert proposer.key() == collection.admin, 'Only admin can propose setting adjustments'
+        validate_settings_changes(
+            collection,
+            settings_changes,
+            new_duration,
+            new_period,
+            new_amount_per_period,
+            new_early_withdrawal_penalty_rate,
+            new_penalty_schedule,
+            new_min_early_withdrawal_penalty_rate
+        )
     elif proposal_type == 3:  # Early Withdraw
         assert not user_account.can_withdraw, 'User is eligible for regular withdrawal'
         assert not user_account.early_withdrawal_requested, 'Early withdrawal already requested'
@@ -255,6 +401,9 @@ def create_proposal(
     proposal.new_period = new_period
     proposal.new_amount_per_period = new_amount_per_period
     proposal.new_early_withdrawal_penalty_rate = new_early_withdrawal_penalty_rate
+    proposal.settings_changes = settings_changes
+    proposal.new_penalty_schedule = new_penalty_schedule
+    proposal.new_min_early_withdrawal_penalty_rate = new_min_early_withdrawal_penalty_rate
 
     if proposal_type == 3:  # Early Withdraw
         user_account.early_withdrawal_requested = True
@@ -319,8 +468,8 @@ def execute_proposal(
             amount_to_withdraw = proposal.withdraw_amount
         else:  # Early Withdraw
             assert user_account.early_withdrawal_requested, 'Early withdrawal not requested'
-            penalty = (proposal.withdraw_amount *
-                       collection.early_withdrawal_penalty_rate) // 100
+            penalty = early_withdrawal_penalty(
+                collection, user_account, proposal.withdraw_amount)
             amount_to_withdraw = proposal.withdraw_amount - penalty
             collection.total_balance -= penalty  # Penalty goes to the collection
 
@@ -337,10 +486,31 @@ def execute_proposal(
         collection.is_active = False
 
     elif proposal.proposal_type == 2:  # Adjust Settings
-        collection.duration = proposal.new_duration
-        collection.period = proposal.new_period
-        collection.amount_per_period = proposal.new_amount_per_period
-        collection.early_withdrawal_penalty_rate = proposal.new_early_withdrawal_penalty_rate
+        settings_changes = proposal.settings_changes
+        # Settings may have moved since the proposal was created, so the
+        # combined result is validated again before applying it
+        validate_settings_changes(
+            collection,
+            settings_changes,
+            proposal.new_duration,
+            proposal.new_period,
+            proposal.new_amount_per_period,
+            proposal.new_early_withdrawal_penalty_rate,
+            proposal.new_penalty_schedule,
+            proposal.new_min_early_withdrawal_penalty_rate
+        )
+        if settings_changes & SETTING_DURATION != 0:
+            collection.duration = proposal.new_duration
+        if settings_changes & SETTING_PERIOD != 0:
+            collection.period = proposal.new_period
+        if settings_changes & SETTING_AMOUNT_PER_PERIOD != 0:
+            collection.amount_per_period = proposal.new_amount_per_period
+        if settings_changes & SETTING_PENALTY_RATE != 0:
+            collection.early_withdrawal_penalty_rate = proposal.new_early_withdrawal_penalty_rate
+        if settings_changes & SETTING_PENALTY_SCHEDULE != 0:
+            collection.penalty_schedule = proposal.new_penalty_schedule
+        if settings_changes & SETTING_MIN_PENALTY_RATE != 0:
+            collection.min_early_withdrawal_penalty_rate = proposal.new_min_early_withdrawal_penalty_rate
 
     proposal.executed = True
 
@@ -1,7 +1,14 @@
 import * as anchor from '@coral-xyz/anchor';
 import { Program } from '@coral-xyz/anchor';
-import { Keypair, PublicKey, SystemProgram } from '@solana/web3.js';
+import {
+  Ed25519Program,
+  Keypair,
+  PublicKey,
+  SystemProgram,
+  SYSVAR_INSTRUCTIONS_PUBKEY,
+} from '@solana/web3.js';
 import { expect } from 'chai';
+import { createHash } from 'crypto';
 import { RotarySavings } from '../target/types/rotary_savings';
 
 describe('rotary_savings', () => {
@@ -13,6 +20,7 @@ describe('rotary_savings', () => {
   const admin = Keypair.generate();
   const user1 = Keypair.generate();
   const user2 = Keypair.generate();
+  const user3 = Keypair.generate();
   const multisigSigner1 = Keypair.generate();
   const multisigSigner2 = Keypair.generate();
   const multisigSigner3 = Keypair.generate();
@@ -21,11 +29,21 @@ describe('rotary_savings', () => {
   let multisigPDA: PublicKey;
 
   const collectionName = 'Test Collection';
-  const duration = 30 * 24 * 60 * 60; // 30 days in seconds
+  const duration = 28 * 24 * 60 * 60; // 28 days in seconds
   const period = 7 * 24 * 60 * 60; // 7 days in seconds
   const amountPerPeriod = new anchor.BN(100000000); // 0.1 SOL
   const totalMembers = 5;
-  const earlyWithdrawalPenaltyRate = 5; // 5%
+  const earlyWithdrawalPenaltyRate = 500; // 5% in basis points
+  const penaltySchedule = 0; // Flat
+  const minEarlyWithdrawalPenaltyRate = 0;
+  const collateralAmount = new anchor.BN(0);
+  const payoutOrderMode = 0; // Personal savings, no rotation
+  const governanceMode = 0; // Multisig signers
+  const quorumPct = 0;
+  const approvalPct = 0;
+  const quorum = 0;
+  const passRule = 0; // Absolute threshold
+  const proposalId = new anchor.BN(1);
 
   before(async () => {
     // Airdrop SOL to admin and users
@@ -41,6 +59,10 @@ describe('rotary_savings', () => {
       user2.publicKey,
       10 * anchor.web3.LAMPORTS_PER_SOL
     );
+    await provider.connection.requestAirdrop(
+      user3.publicKey,
+      10 * anchor.web3.LAMPORTS_PER_SOL
+    );
 
     // Find PDAs
     [collectionPDA] = PublicKey.findProgramAddressSync(
@@ -58,6 +80,25 @@ describe('rotary_savings', () => {
     );
   });
 
+  // Payments are refused until a collection has a multisig
+  const setUpGovernance = async (collection: PublicKey) => {
+    const [multisig] = PublicKey.findProgramAddressSync(
+      [Buffer.from('multisig'), collection.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .createMultisig([multisigSigner1.publicKey], 1, [], 0, quorum, passRule)
+      .accounts({
+        admin: admin.publicKey,
+        collection,
+        multisig,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+  };
+
   it('Creates a collection', async () => {
     const tx = await program.methods
       .createCollection(
@@ -66,7 +107,14 @@ describe('rotary_savings', () => {
         new anchor.BN(period),
         amountPerPeriod,
         totalMembers,
-        earlyWithdrawalPenaltyRate
+        earlyWithdrawalPenaltyRate,
+        penaltySchedule,
+        minEarlyWithdrawalPenaltyRate,
+        collateralAmount,
+        payoutOrderMode,
+        governanceMode,
+        quorumPct,
+        approvalPct
       )
       .accounts({
         admin: admin.publicKey,
@@ -95,6 +143,94 @@ describe('rotary_savings', () => {
     expect(collectionAccount.earlyWithdrawalPenaltyRate).to.equal(
       earlyWithdrawalPenaltyRate
     );
+    expect(collectionAccount.penaltySchedule).to.equal(penaltySchedule);
+    expect(collectionAccount.minEarlyWithdrawalPenaltyRate).to.equal(
+      minEarlyWithdrawalPenaltyRate
+    );
+  });
+
+  it('Rejects a duration that is not a multiple of the period', async () => {
+    const name = 'Bad Duration';
+    const [badCollectionPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from(name),
+      ],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .createCollection(
+          name,
+          new anchor.BN(duration + 1),
+          new anchor.BN(period),
+          amountPerPeriod,
+          totalMembers,
+          earlyWithdrawalPenaltyRate,
+          penaltySchedule,
+          minEarlyWithdrawalPenaltyRate,
+          collateralAmount,
+          payoutOrderMode,
+          governanceMode,
+          quorumPct,
+          approvalPct
+        )
+        .accounts({
+          admin: admin.publicKey,
+          collection: badCollectionPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal(
+        'DurationNotMultipleOfPeriod'
+      );
+    }
+  });
+
+  it('Rejects a penalty rate above 10000 basis points', async () => {
+    const name = 'Bad Penalty';
+    const [badCollectionPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from(name),
+      ],
+      program.programId
+    );
+
+    try {
+      await program.methods
+        .createCollection(
+          name,
+          new anchor.BN(duration),
+          new anchor.BN(period),
+          amountPerPeriod,
+          totalMembers,
+          10001,
+          penaltySchedule,
+          minEarlyWithdrawalPenaltyRate,
+          collateralAmount,
+          payoutOrderMode,
+          governanceMode,
+          quorumPct,
+          approvalPct
+        )
+        .accounts({
+          admin: admin.publicKey,
+          collection: badCollectionPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('PenaltyRateTooHigh');
+    }
   });
 
   it('Creates a multisig', async () => {
@@ -106,7 +242,7 @@ describe('rotary_savings', () => {
     const threshold = 2;
 
     const tx = await program.methods
-      .createMultisig(signers, threshold)
+      .createMultisig(signers, threshold, [], 0, quorum, passRule)
       .accounts({
         admin: admin.publicKey,
         collection: collectionPDA,
@@ -125,6 +261,16 @@ describe('rotary_savings', () => {
     );
     expect(multisigAccount.threshold).to.equal(threshold);
     expect(multisigAccount.nonce.toNumber()).to.equal(0);
+    expect(multisigAccount.weights).to.be.empty;
+    expect(multisigAccount.quorum).to.equal(quorum);
+    expect(multisigAccount.passRule).to.equal(passRule);
+
+    const collectionAccount = await program.account.collection.fetch(
+      collectionPDA
+    );
+    expect(collectionAccount.multisig.toString()).to.equal(
+      multisigPDA.toString()
+    );
   });
 
   it('Adds a user to the collection', async () => {
@@ -165,6 +311,97 @@ describe('rotary_savings', () => {
     expect(collectionAccount.activeMembers).to.equal(1);
   });
 
+  it('Lets an allow-listed member join and pay their own rent', async () => {
+    // A single-leaf tree: the root is the member's leaf and the proof is empty
+    const root = createHash('sha256')
+      .update(user2.publicKey.toBuffer())
+      .digest();
+
+    await program.methods
+      .setEnrollment([...root], PublicKey.default)
+      .accounts({
+        admin: admin.publicKey,
+        collection: collectionPDA,
+      })
+      .signers([admin])
+      .rpc();
+
+    const [userPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user'),
+        collectionPDA.toBuffer(),
+        user2.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .joinCollection([])
+      .accounts({
+        member: user2.publicKey,
+        collection: collectionPDA,
+        user: userPDA,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user2])
+      .rpc();
+
+    const userAccount = await program.account.user.fetch(userPDA);
+    expect(userAccount.user.toString()).to.equal(user2.publicKey.toString());
+
+    const collectionAccount = await program.account.collection.fetch(
+      collectionPDA
+    );
+    expect(collectionAccount.activeMembers).to.equal(2);
+  });
+
+  it('Lets a member join with an invite signed by the admin', async () => {
+    const [userPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user'),
+        collectionPDA.toBuffer(),
+        user3.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .setEnrollment(new Array(32).fill(0), admin.publicKey)
+      .accounts({
+        admin: admin.publicKey,
+        collection: collectionPDA,
+      })
+      .signers([admin])
+      .rpc();
+
+    const invite = Ed25519Program.createInstructionWithPrivateKey({
+      privateKey: admin.secretKey,
+      message: Buffer.concat([
+        collectionPDA.toBuffer(),
+        user3.publicKey.toBuffer(),
+      ]),
+    });
+
+    await program.methods
+      .joinCollection([])
+      .accounts({
+        member: user3.publicKey,
+        collection: collectionPDA,
+        user: userPDA,
+        instructions: SYSVAR_INSTRUCTIONS_PUBKEY,
+        systemProgram: SystemProgram.programId,
+      })
+      .preInstructions([invite])
+      .signers([user3])
+      .rpc();
+
+    const collectionAccount = await program.account.collection.fetch(
+      collectionPDA
+    );
+    expect(collectionAccount.activeMembers).to.equal(3);
+  });
+
   it('Allows a user to pay into the collection', async () => {
     const [userPDA] = PublicKey.findProgramAddressSync(
       [
@@ -223,130 +460,946 @@ describe('rotary_savings', () => {
         .rpc();
       expect.fail('Should have thrown an error');
     } catch (error) {
-      expect(error.message).to.include('Too early for next payment');
+      expect(error.error.errorCode.code).to.equal('PaymentNotDue');
     }
   });
 
-  it('Creates a proposal for early withdrawal', async () => {
+  it('Requires collateral before the first payment', async () => {
+    const name = 'Collateral Collection';
+    const collateral = new anchor.BN(50000000); // 0.05 SOL
+    const [collateralCollectionPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from(name),
+      ],
+      program.programId
+    );
     const [userPDA] = PublicKey.findProgramAddressSync(
       [
         Buffer.from('user'),
-        collectionPDA.toBuffer(),
+        collateralCollectionPDA.toBuffer(),
         user1.publicKey.toBuffer(),
       ],
       program.programId
     );
 
-    const [proposalPDA] = PublicKey.findProgramAddressSync(
-      [Buffer.from('proposal'), collectionPDA.toBuffer(), Buffer.from([0])],
-      program.programId
-    );
-
-    const tx = await program.methods
-      .createProposal(
-        3,
-        user1.publicKey,
+    await program.methods
+      .createCollection(
+        name,
+        new anchor.BN(duration),
+        new anchor.BN(period),
         amountPerPeriod,
-        new anchor.BN(0),
-        new anchor.BN(0),
-        new anchor.BN(0),
-        0
+        totalMembers,
+        earlyWithdrawalPenaltyRate,
+        penaltySchedule,
+        minEarlyWithdrawalPenaltyRate,
+        collateral,
+        payoutOrderMode,
+        governanceMode,
+        quorumPct,
+        approvalPct
       )
       .accounts({
-        proposer: user1.publicKey,
-        collection: collectionPDA,
-        multisig: multisigPDA,
-        proposal: proposalPDA,
+        admin: admin.publicKey,
+        collection: collateralCollectionPDA,
         systemProgram: SystemProgram.programId,
       })
-      .signers([user1])
+      .signers([admin])
       .rpc();
 
-    const proposalAccount = await program.account.proposal.fetch(proposalPDA);
-    expect(proposalAccount.proposalType).to.equal(3);
-    expect(proposalAccount.withdrawUser.toString()).to.equal(
-      user1.publicKey.toString()
-    );
-    expect(proposalAccount.withdrawAmount.toNumber()).to.equal(
-      amountPerPeriod.toNumber()
-    );
-
-    const userAccount = await program.account.user.fetch(userPDA);
-    expect(userAccount.earlyWithdrawalRequested).to.be.true;
-  });
-
-  it('Allows multisig signers to vote on the proposal', async () => {
-    const [proposalPDA] = PublicKey.findProgramAddressSync(
-      [Buffer.from('proposal'), collectionPDA.toBuffer(), Buffer.from([0])],
-      program.programId
-    );
-
     await program.methods
-      .voteOnProposal(true)
+      .addUser(user1.publicKey)
       .accounts({
-        signer: multisigSigner1.publicKey,
-        collection: collectionPDA,
-        multisig: multisigPDA,
-        proposal: proposalPDA,
+        admin: admin.publicKey,
+        collection: collateralCollectionPDA,
+        user: userPDA,
+        systemProgram: SystemProgram.programId,
       })
-      .signers([multisigSigner1])
+      .signers([admin])
       .rpc();
 
+    const payIntoCollateralCollection = () =>
+      program.methods
+        .pay(amountPerPeriod)
+        .accounts({
+          user: user1.publicKey,
+          collection: collateralCollectionPDA,
+          userAccount: userPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+
+    try {
+      await payIntoCollateralCollection();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('GovernanceNotConfigured');
+    }
+
+    await setUpGovernance(collateralCollectionPDA);
+
+    try {
+      await payIntoCollateralCollection();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('CollateralRequired');
+    }
+
     await program.methods
-      .voteOnProposal(true)
+      .depositCollateral()
       .accounts({
-        signer: multisigSigner2.publicKey,
-        collection: collectionPDA,
-        multisig: multisigPDA,
-        proposal: proposalPDA,
+        member: user1.publicKey,
+        collection: collateralCollectionPDA,
+        userAccount: userPDA,
+        systemProgram: SystemProgram.programId,
       })
-      .signers([multisigSigner2])
+      .signers([user1])
       .rpc();
 
-    const proposalAccount = await program.account.proposal.fetch(proposalPDA);
-    expect(proposalAccount.approvals[0]).to.be.true;
-    expect(proposalAccount.approvals[1]).to.be.true;
-    expect(proposalAccount.approvals[2]).to.be.false;
-  });
+    const userAccount = await program.account.user.fetch(userPDA);
+    expect(userAccount.collateral.toNumber()).to.equal(collateral.toNumber());
 
-  it('Executes the early withdrawal proposal', async () => {
-    const [proposalPDA] = PublicKey.findProgramAddressSync(
-      [Buffer.from('proposal'), collectionPDA.toBuffer(), Buffer.from([0])],
-      program.programId
+    const collectionAccount = await program.account.collection.fetch(
+      collateralCollectionPDA
+    );
+    expect(collectionAccount.totalCollateral.toNumber()).to.equal(
+      collateral.toNumber()
     );
 
-    const [userPDA] = PublicKey.findProgramAddressSync(
+    try {
+      await program.methods
+        .reclaimCollateral()
+        .accounts({
+          member: user1.publicKey,
+          collection: collateralCollectionPDA,
+          userAccount: userPDA,
+        })
+        .signers([user1])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('CollateralLocked');
+    }
+  });
+
+  it('Pays the pot to members in the fixed payout order', async () => {
+    const name = 'Rotation Collection';
+    const [rotationCollectionPDA] = PublicKey.findProgramAddressSync(
       [
-        Buffer.from('user'),
-        collectionPDA.toBuffer(),
-        user1.publicKey.toBuffer(),
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from(name),
       ],
       program.programId
     );
-
-    const userBalanceBefore = await provider.connection.getBalance(
-      user1.publicKey
+    const members = [user1, user2];
+    const userPDAs = members.map(
+      (member) =>
+        PublicKey.findProgramAddressSync(
+          [
+            Buffer.from('user'),
+            rotationCollectionPDA.toBuffer(),
+            member.publicKey.toBuffer(),
+          ],
+          program.programId
+        )[0]
     );
 
-    const tx = await program.methods
-      .executeProposal()
+    await program.methods
+      .createCollection(
+        name,
+        new anchor.BN(2 * period),
+        new anchor.BN(period),
+        amountPerPeriod,
+        members.length,
+        earlyWithdrawalPenaltyRate,
+        penaltySchedule,
+        minEarlyWithdrawalPenaltyRate,
+        collateralAmount,
+        1, // Fixed
+        governanceMode,
+        quorumPct,
+        approvalPct
+      )
       .accounts({
-        executor: admin.publicKey,
-        collection: collectionPDA,
-        multisig: multisigPDA,
-        proposal: proposalPDA,
-        withdrawUser: user1.publicKey,
-        userAccount: userPDA,
+        admin: admin.publicKey,
+        collection: rotationCollectionPDA,
         systemProgram: SystemProgram.programId,
       })
       .signers([admin])
       .rpc();
 
-    const userBalanceAfter = await provider.connection.getBalance(
+    for (let i = 0; i < members.length; i++) {
+      await program.methods
+        .addUser(members[i].publicKey)
+        .accounts({
+          admin: admin.publicKey,
+          collection: rotationCollectionPDA,
+          user: userPDAs[i],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    }
+
+    await program.methods
+      .setPayoutOrder([user2.publicKey, user1.publicKey])
+      .accounts({
+        admin: admin.publicKey,
+        collection: rotationCollectionPDA,
+      })
+      .signers([admin])
+      .rpc();
+
+    await setUpGovernance(rotationCollectionPDA);
+
+    for (let i = 0; i < members.length; i++) {
+      await program.methods
+        .pay(amountPerPeriod)
+        .accounts({
+          user: members[i].publicKey,
+          collection: rotationCollectionPDA,
+          userAccount: userPDAs[i],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([members[i]])
+        .rpc();
+    }
+
+    try {
+      await program.methods
+        .disburseRound()
+        .accounts({
+          collection: rotationCollectionPDA,
+          recipientAccount: userPDAs[0],
+          recipient: user1.publicKey,
+        })
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('WrongRecipient');
+    }
+
+    const balanceBefore = await provider.connection.getBalance(
+      user2.publicKey
+    );
+
+    await program.methods
+      .disburseRound()
+      .accounts({
+        collection: rotationCollectionPDA,
+        recipientAccount: userPDAs[1],
+        recipient: user2.publicKey,
+      })
+      .rpc();
+
+    const balanceAfter = await provider.connection.getBalance(user2.publicKey);
+    expect(balanceAfter - balanceBefore).to.equal(
+      amountPerPeriod.toNumber() * members.length
+    );
+
+    const collectionAccount = await program.account.collection.fetch(
+      rotationCollectionPDA
+    );
+    expect(collectionAccount.currentRound).to.equal(1);
+    expect(collectionAccount.totalBalance.toNumber()).to.equal(0);
+
+    const recipientAccount = await program.account.user.fetch(userPDAs[1]);
+    expect(recipientAccount.receivedPayout).to.be.true;
+  });
+
+  it('Settles an auction round and shares the discount', async () => {
+    const name = 'Auction Collection';
+    const [auctionCollectionPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from(name),
+      ],
+      program.programId
+    );
+    const members = [user1, user2];
+    const userPDAs = members.map(
+      (member) =>
+        PublicKey.findProgramAddressSync(
+          [
+            Buffer.from('user'),
+            auctionCollectionPDA.toBuffer(),
+            member.publicKey.toBuffer(),
+          ],
+          program.programId
+        )[0]
+    );
+    const pot = amountPerPeriod.toNumber() * members.length;
+    const bids = [
+      new anchor.BN(pot - 50000000),
+      new anchor.BN(pot - 20000000),
+    ];
+
+    await program.methods
+      .createCollection(
+        name,
+        new anchor.BN(2 * period),
+        new anchor.BN(period),
+        amountPerPeriod,
+        members.length,
+        earlyWithdrawalPenaltyRate,
+        penaltySchedule,
+        minEarlyWithdrawalPenaltyRate,
+        collateralAmount,
+        4, // Auction
+        governanceMode,
+        quorumPct,
+        approvalPct
+      )
+      .accounts({
+        admin: admin.publicKey,
+        collection: auctionCollectionPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await setUpGovernance(auctionCollectionPDA);
+
+    for (let i = 0; i < members.length; i++) {
+      await program.methods
+        .addUser(members[i].publicKey)
+        .accounts({
+          admin: admin.publicKey,
+          collection: auctionCollectionPDA,
+          user: userPDAs[i],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+
+      await program.methods
+        .pay(amountPerPeriod)
+        .accounts({
+          user: members[i].publicKey,
+          collection: auctionCollectionPDA,
+          userAccount: userPDAs[i],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([members[i]])
+        .rpc();
+    }
+
+    for (let i = 0; i < members.length; i++) {
+      await program.methods
+        .placeBid(bids[i])
+        .accounts({
+          member: members[i].publicKey,
+          collection: auctionCollectionPDA,
+          userAccount: userPDAs[i],
+        })
+        .signers([members[i]])
+        .rpc();
+    }
+
+    const winnerBalanceBefore = await provider.connection.getBalance(
+      user1.publicKey
+    );
+
+    await program.methods
+      .settleRound()
+      .accounts({
+        collection: auctionCollectionPDA,
+        recipientAccount: userPDAs[0],
+        recipient: user1.publicKey,
+      })
+      .rpc();
+
+    const winnerBalanceAfter = await provider.connection.getBalance(
+      user1.publicKey
+    );
+    expect(winnerBalanceAfter - winnerBalanceBefore).to.equal(
+      bids[0].toNumber()
+    );
+
+    const collectionAccount = await program.account.collection.fetch(
+      auctionCollectionPDA
+    );
+    expect(collectionAccount.currentRound).to.equal(1);
+    expect(collectionAccount.dividendsOutstanding.toNumber()).to.equal(
+      pot - bids[0].toNumber()
+    );
+
+    try {
+      await program.methods
+        .claimDividends()
+        .accounts({
+          member: user1.publicKey,
+          collection: auctionCollectionPDA,
+          userAccount: userPDAs[0],
+        })
+        .signers([user1])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('NoDividends');
+    }
+
+    await program.methods
+      .claimDividends()
+      .accounts({
+        member: user2.publicKey,
+        collection: auctionCollectionPDA,
+        userAccount: userPDAs[1],
+      })
+      .signers([user2])
+      .rpc();
+
+    const userAccount = await program.account.user.fetch(userPDAs[1]);
+    expect(userAccount.dividendsClaimed.toNumber()).to.equal(
+      pot - bids[0].toNumber()
+    );
+  });
+
+  it('Lets two members swap payout slots for a side payment', async () => {
+    const name = 'Swap Collection';
+    const sidePayment = new anchor.BN(10000000); // 0.01 SOL
+    const [swapCollectionPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from(name),
+      ],
+      program.programId
+    );
+    const members = [user1, user2];
+    const userPDAs = members.map(
+      (member) =>
+        PublicKey.findProgramAddressSync(
+          [
+            Buffer.from('user'),
+            swapCollectionPDA.toBuffer(),
+            member.publicKey.toBuffer(),
+          ],
+          program.programId
+        )[0]
+    );
+    const [swapPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('swap'),
+        swapCollectionPDA.toBuffer(),
+        user1.publicKey.toBuffer(),
+        Buffer.from([0]),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .createCollection(
+        name,
+        new anchor.BN(2 * period),
+        new anchor.BN(period),
+        amountPerPeriod,
+        members.length,
+        earlyWithdrawalPenaltyRate,
+        penaltySchedule,
+        minEarlyWithdrawalPenaltyRate,
+        collateralAmount,
+        2, // Join order
+        governanceMode,
+        quorumPct,
+        approvalPct
+      )
+      .accounts({
+        admin: admin.publicKey,
+        collection: swapCollectionPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    for (let i = 0; i < members.length; i++) {
+      await program.methods
+        .addUser(members[i].publicKey)
+        .accounts({
+          admin: admin.publicKey,
+          collection: swapCollectionPDA,
+          user: userPDAs[i],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    }
+
+    await program.methods
+      .proposeSwap(sidePayment)
+      .accounts({
+        proposer: user1.publicKey,
+        collection: swapCollectionPDA,
+        proposerAccount: userPDAs[0],
+        counterpartyAccount: userPDAs[1],
+        swap: swapPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const balanceBefore = await provider.connection.getBalance(
+      user2.publicKey
+    );
+
+    await program.methods
+      .acceptSwap()
+      .accounts({
+        counterparty: user2.publicKey,
+        collection: swapCollectionPDA,
+        swap: swapPDA,
+        proposer: user1.publicKey,
+        proposerAccount: userPDAs[0],
+        counterpartyAccount: userPDAs[1],
+      })
+      .signers([user2])
+      .rpc();
+
+    const balanceAfter = await provider.connection.getBalance(user2.publicKey);
+    expect(balanceAfter - balanceBefore).to.equal(sidePayment.toNumber());
+
+    const collectionAccount = await program.account.collection.fetch(
+      swapCollectionPDA
+    );
+    const payoutOrder = collectionAccount.payoutOrder.map((key) =>
+      key.toString()
+    );
+    expect(payoutOrder).to.eql([
+      user2.publicKey.toString(),
+      user1.publicKey.toString(),
+    ]);
+
+    // Settled swaps are closed back to the proposer
+    const swapAccount = await provider.connection.getAccountInfo(swapPDA);
+    expect(swapAccount).to.be.null;
+  });
+
+  it('Creates a weighted multisig', async () => {
+    const [swapCollectionPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from('Swap Collection'),
+      ],
+      program.programId
+    );
+    const [weightedMultisigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('multisig'), swapCollectionPDA.toBuffer()],
+      program.programId
+    );
+    const signers = [
+      multisigSigner1.publicKey,
+      multisigSigner2.publicKey,
+      multisigSigner3.publicKey,
+    ];
+    const weights = [2, 1, 1]; // The treasurer counts double
+    const accounts = {
+      admin: admin.publicKey,
+      collection: swapCollectionPDA,
+      multisig: weightedMultisigPDA,
+      systemProgram: SystemProgram.programId,
+    };
+
+    try {
+      await program.methods
+        .createMultisig(signers, 0, weights, 5, 0, 0)
+        .accounts(accounts)
+        .signers([admin])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('InvalidWeightThreshold');
+    }
+
+    try {
+      await program.methods
+        .createMultisig(
+          [multisigSigner1.publicKey, multisigSigner1.publicKey],
+          0,
+          [1, 1],
+          2,
+          0,
+          0
+        )
+        .accounts(accounts)
+        .signers([admin])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('DuplicateSigner');
+    }
+
+    try {
+      await program.methods
+        .createMultisig(signers, 0, weights, 3, 0, 3)
+        .accounts(accounts)
+        .signers([admin])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('InvalidPassRule');
+    }
+
+    try {
+      await program.methods
+        .createMultisig(signers, 0, weights, 3, 4, 0)
+        .accounts(accounts)
+        .signers([admin])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('InvalidQuorum');
+    }
+
+    await program.methods
+      .createMultisig(signers, 0, weights, 3, 2, 2) // Supermajority of two
+      .accounts(accounts)
+      .signers([admin])
+      .rpc();
+
+    const multisigAccount = await program.account.multisig.fetch(
+      weightedMultisigPDA
+    );
+    expect(multisigAccount.weights).to.eql(weights);
+    expect(multisigAccount.weightThreshold).to.equal(3);
+    expect(multisigAccount.quorum).to.equal(2);
+    expect(multisigAccount.passRule).to.equal(2);
+  });
+
+  it('Lets members vote in a member-governed collection', async () => {
+    const name = 'Member Collection';
+    const [memberCollectionPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('collection'),
+        admin.publicKey.toBuffer(),
+        Buffer.from(name),
+      ],
+      program.programId
+    );
+    const [memberMultisigPDA] = PublicKey.findProgramAddressSync(
+      [Buffer.from('multisig'), memberCollectionPDA.toBuffer()],
+      program.programId
+    );
+    const [closeProposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        memberCollectionPDA.toBuffer(),
+        proposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+    const members = [user1, user2];
+    const userPDAs = members.map(
+      (member) =>
+        PublicKey.findProgramAddressSync(
+          [
+            Buffer.from('user'),
+            memberCollectionPDA.toBuffer(),
+            member.publicKey.toBuffer(),
+          ],
+          program.programId
+        )[0]
+    );
+
+    await program.methods
+      .createCollection(
+        name,
+        new anchor.BN(duration),
+        new anchor.BN(period),
+        amountPerPeriod,
+        members.length,
+        earlyWithdrawalPenaltyRate,
+        penaltySchedule,
+        minEarlyWithdrawalPenaltyRate,
+        collateralAmount,
+        payoutOrderMode,
+        1, // Members
+        50,
+        51
+      )
+      .accounts({
+        admin: admin.publicKey,
+        collection: memberCollectionPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    for (let i = 0; i < members.length; i++) {
+      await program.methods
+        .addUser(members[i].publicKey)
+        .accounts({
+          admin: admin.publicKey,
+          collection: memberCollectionPDA,
+          user: userPDAs[i],
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([admin])
+        .rpc();
+    }
+
+    // The multisig has no signers and only indexes open proposals
+    await program.methods
+      .createMultisig([], 0, [], 0, 0, 0)
+      .accounts({
+        admin: admin.publicKey,
+        collection: memberCollectionPDA,
+        multisig: memberMultisigPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    await program.methods
+      .closeCollection(proposalId)
+      .accounts({
+        admin: admin.publicKey,
+        collection: memberCollectionPDA,
+        multisig: memberMultisigPDA,
+        proposal: closeProposalPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    const executeAccounts = {
+      collection: memberCollectionPDA,
+      multisig: memberMultisigPDA,
+      proposal: closeProposalPDA,
+      userAccount: userPDAs[0],
+    };
+
+    try {
+      await program.methods.executeProposal().accounts(executeAccounts).rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('QuorumNotReached');
+    }
+
+    const [votePDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('vote'),
+        closeProposalPDA.toBuffer(),
+        user1.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .castVote(true)
+      .accounts({
+        member: user1.publicKey,
+        collection: memberCollectionPDA,
+        userAccount: userPDAs[0],
+        proposal: closeProposalPDA,
+        vote: votePDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    await program.methods.executeProposal().accounts(executeAccounts).rpc();
+
+    const collectionAccount = await program.account.collection.fetch(
+      memberCollectionPDA
+    );
+    expect(collectionAccount.isActive).to.be.false;
+  });
+
+  it('Creates a proposal for early withdrawal', async () => {
+    const [userPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user'),
+        collectionPDA.toBuffer(),
+        user1.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        collectionPDA.toBuffer(),
+        proposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+
+    const tx = await program.methods
+      .createProposal(
+        proposalId,
+        3,
+        user1.publicKey,
+        amountPerPeriod,
+        new anchor.BN(0),
+        new anchor.BN(0),
+        new anchor.BN(0),
+        0,
+        0,
+        0,
+        0
+      )
+      .accounts({
+        proposer: user1.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([user1])
+      .rpc();
+
+    const proposalAccount = await program.account.proposal.fetch(proposalPDA);
+    expect(proposalAccount.proposalType).to.equal(3);
+    expect(proposalAccount.withdrawUser.toString()).to.equal(
+      user1.publicKey.toString()
+    );
+    expect(proposalAccount.withdrawAmount.toNumber()).to.equal(
+      amountPerPeriod.toNumber()
+    );
+
+    const userAccount = await program.account.user.fetch(userPDA);
+    expect(userAccount.earlyWithdrawalRequested).to.be.true;
+
+    const multisigAccount = await program.account.multisig.fetch(multisigPDA);
+    expect(multisigAccount.openProposals.map((id) => id.toNumber())).to.eql([
+      proposalId.toNumber(),
+    ]);
+  });
+
+  it('Allows multisig signers to vote on the proposal', async () => {
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        collectionPDA.toBuffer(),
+        proposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .voteOnProposal(true)
+      .accounts({
+        signer: multisigSigner1.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+      })
+      .signers([multisigSigner1])
+      .rpc();
+
+    await program.methods
+      .voteOnProposal(true)
+      .accounts({
+        signer: multisigSigner2.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+      })
+      .signers([multisigSigner2])
+      .rpc();
+
+    const proposalAccount = await program.account.proposal.fetch(proposalPDA);
+    expect(proposalAccount.approvals[0]).to.be.true;
+    expect(proposalAccount.approvals[1]).to.be.true;
+    expect(proposalAccount.approvals[2]).to.be.false;
+  });
+
+  it('Lets a delegate vote in the delegating signer slot', async () => {
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        collectionPDA.toBuffer(),
+        proposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+
+    await program.methods
+      .delegateVote(user3.publicKey)
+      .accounts({
+        signer: multisigSigner3.publicKey,
+        multisig: multisigPDA,
+      })
+      .signers([multisigSigner3])
+      .rpc();
+
+    await program.methods
+      .voteOnProposal(false)
+      .accounts({
+        signer: user3.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+      })
+      .signers([user3])
+      .rpc();
+
+    const proposalAccount = await program.account.proposal.fetch(proposalPDA);
+    expect(proposalAccount.disapprovals[2]).to.be.true;
+
+    await program.methods
+      .revokeDelegation()
+      .accounts({
+        signer: multisigSigner3.publicKey,
+        multisig: multisigPDA,
+      })
+      .signers([multisigSigner3])
+      .rpc();
+
+    const multisigAccount = await program.account.multisig.fetch(multisigPDA);
+    expect(multisigAccount.delegates[2].toString()).to.equal(
+      PublicKey.default.toString()
+    );
+  });
+
+  it('Executes the early withdrawal proposal', async () => {
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        collectionPDA.toBuffer(),
+        proposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+
+    const [userPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user'),
+        collectionPDA.toBuffer(),
+        user1.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    const userBalanceBefore = await provider.connection.getBalance(
+      user1.publicKey
+    );
+
+    const tx = await program.methods
+      .executeProposal()
+      .accounts({
+        executor: admin.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        withdrawUser: user1.publicKey,
+        userAccount: userPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([admin])
+      .rpc();
+
+    const userBalanceAfter = await provider.connection.getBalance(
       user1.publicKey
     );
     const expectedWithdrawalAmount =
-      (amountPerPeriod.toNumber() * (100 - earlyWithdrawalPenaltyRate)) / 100;
+      (amountPerPeriod.toNumber() * (10000 - earlyWithdrawalPenaltyRate)) /
+      10000;
 
     expect(userBalanceAfter - userBalanceBefore).to.be.closeTo(
       expectedWithdrawalAmount,
@@ -361,12 +1414,292 @@ describe('rotary_savings', () => {
       collectionPDA
     );
     const expectedPenaltyAmount =
-      (amountPerPeriod.toNumber() * earlyWithdrawalPenaltyRate) / 100;
+      (amountPerPeriod.toNumber() * earlyWithdrawalPenaltyRate) / 10000;
     expect(collectionAccount.totalBalance.toNumber()).to.equal(
       expectedPenaltyAmount
     );
 
     const proposalAccount = await program.account.proposal.fetch(proposalPDA);
     expect(proposalAccount.executed).to.be.true;
+
+    const multisigAccount = await program.account.multisig.fetch(multisigPDA);
+    expect(multisigAccount.openProposals).to.be.empty;
+  });
+
+  it('Transfers the admin role through the multisig', async () => {
+    const transferProposalId = new anchor.BN(2);
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        collectionPDA.toBuffer(),
+        transferProposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+    const [userPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user'),
+        collectionPDA.toBuffer(),
+        user1.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    // A signer can start the transfer without the current admin key
+    await program.methods
+      .transferAdmin(transferProposalId, user2.publicKey)
+      .accounts({
+        proposer: multisigSigner1.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([multisigSigner1])
+      .rpc();
+
+    for (const signer of [multisigSigner1, multisigSigner2]) {
+      await program.methods
+        .voteOnProposal(true)
+        .accounts({
+          signer: signer.publicKey,
+          collection: collectionPDA,
+          multisig: multisigPDA,
+          proposal: proposalPDA,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    await program.methods
+      .executeProposal()
+      .accounts({
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        userAccount: userPDA,
+      })
+      .rpc();
+
+    try {
+      await program.methods
+        .acceptAdmin()
+        .accounts({ newAdmin: user3.publicKey, collection: collectionPDA })
+        .signers([user3])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('NotPendingAdmin');
+    }
+
+    await program.methods
+      .acceptAdmin()
+      .accounts({ newAdmin: user2.publicKey, collection: collectionPDA })
+      .signers([user2])
+      .rpc();
+
+    // The previous admin stays on as a co-admin
+    await program.methods
+      .setCoAdmins([admin.publicKey])
+      .accounts({ admin: user2.publicKey, collection: collectionPDA })
+      .signers([user2])
+      .rpc();
+
+    const collectionAccount = await program.account.collection.fetch(
+      collectionPDA
+    );
+    expect(collectionAccount.admin.toString()).to.equal(
+      user2.publicKey.toString()
+    );
+    expect(collectionAccount.creator.toString()).to.equal(
+      admin.publicKey.toString()
+    );
+    expect(collectionAccount.pendingAdmin.toString()).to.equal(
+      PublicKey.default.toString()
+    );
+    expect(collectionAccount.coAdmins.map((key) => key.toString())).to.eql([
+      admin.publicKey.toString(),
+    ]);
+  });
+
+  it('Pauses with the emergency key and resumes by proposal', async () => {
+    const resumeProposalId = new anchor.BN(3);
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        collectionPDA.toBuffer(),
+        resumeProposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+    const [userPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user'),
+        collectionPDA.toBuffer(),
+        user1.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+
+    // The former admin is a co-admin now and can still set the pause key
+    await program.methods
+      .setPauseKey(user3.publicKey)
+      .accounts({ admin: admin.publicKey, collection: collectionPDA })
+      .signers([admin])
+      .rpc();
+
+    await program.methods
+      .emergencyPause()
+      .accounts({ pauseKey: user3.publicKey, collection: collectionPDA })
+      .signers([user3])
+      .rpc();
+
+    try {
+      await program.methods
+        .pay(amountPerPeriod)
+        .accounts({
+          user: user1.publicKey,
+          collection: collectionPDA,
+          userAccount: userPDA,
+          systemProgram: SystemProgram.programId,
+        })
+        .signers([user1])
+        .rpc();
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('CollectionPaused');
+    }
+
+    await program.methods
+      .resumeCollection(resumeProposalId)
+      .accounts({
+        proposer: multisigSigner1.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([multisigSigner1])
+      .rpc();
+
+    // Voting still works while the collection is paused
+    for (const signer of [multisigSigner1, multisigSigner2]) {
+      await program.methods
+        .voteOnProposal(true)
+        .accounts({
+          signer: signer.publicKey,
+          collection: collectionPDA,
+          multisig: multisigPDA,
+          proposal: proposalPDA,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    await program.methods
+      .executeProposal()
+      .accounts({
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        userAccount: userPDA,
+      })
+      .rpc();
+
+    const collectionAccount = await program.account.collection.fetch(
+      collectionPDA
+    );
+    expect(collectionAccount.isPaused).to.be.false;
+  });
+
+  it('Lets the guardian freeze the vault until a vote lifts it', async () => {
+    const unfreezeProposalId = new anchor.BN(4);
+    const [proposalPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('proposal'),
+        collectionPDA.toBuffer(),
+        unfreezeProposalId.toArrayLike(Buffer, 'le', 8),
+      ],
+      program.programId
+    );
+    const [userPDA] = PublicKey.findProgramAddressSync(
+      [
+        Buffer.from('user'),
+        collectionPDA.toBuffer(),
+        user1.publicKey.toBuffer(),
+      ],
+      program.programId
+    );
+    const freeze = (duration: number) =>
+      program.methods
+        .guardianFreeze(new anchor.BN(duration))
+        .accounts({ guardian: user3.publicKey, collection: collectionPDA })
+        .signers([user3])
+        .rpc();
+
+    await program.methods
+      .setGuardian(user3.publicKey)
+      .accounts({ admin: admin.publicKey, collection: collectionPDA })
+      .signers([admin])
+      .rpc();
+
+    try {
+      await freeze(4 * 24 * 60 * 60);
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('InvalidFreezeDuration');
+    }
+
+    await freeze(24 * 60 * 60);
+
+    try {
+      await freeze(24 * 60 * 60);
+      expect.fail('Should have thrown an error');
+    } catch (error) {
+      expect(error.error.errorCode.code).to.equal('AlreadyFrozen');
+    }
+
+    let collectionAccount = await program.account.collection.fetch(
+      collectionPDA
+    );
+    expect(collectionAccount.frozenUntil.toNumber()).to.be.greaterThan(0);
+
+    await program.methods
+      .unfreezeVault(unfreezeProposalId)
+      .accounts({
+        proposer: multisigSigner1.publicKey,
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        systemProgram: SystemProgram.programId,
+      })
+      .signers([multisigSigner1])
+      .rpc();
+
+    for (const signer of [multisigSigner1, multisigSigner2]) {
+      await program.methods
+        .voteOnProposal(true)
+        .accounts({
+          signer: signer.publicKey,
+          collection: collectionPDA,
+          multisig: multisigPDA,
+          proposal: proposalPDA,
+        })
+        .signers([signer])
+        .rpc();
+    }
+
+    await program.methods
+      .executeProposal()
+      .accounts({
+        collection: collectionPDA,
+        multisig: multisigPDA,
+        proposal: proposalPDA,
+        userAccount: userPDA,
+      })
+      .rpc();
+
+    collectionAccount = await program.account.collection.fetch(collectionPDA);
+    expect(collectionAccount.frozenUntil.toNumber()).to.equal(0);
   });
 });